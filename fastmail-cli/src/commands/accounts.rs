@@ -0,0 +1,21 @@
+// fastmail-cli/src/commands/accounts.rs
+use crate::output::{print_formatted, Response};
+use anyhow::Result;
+use clap::Subcommand;
+use fastmail_client::FastmailClient;
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum AccountsCommands {
+    /// List the accounts in the session, including shared ones
+    List,
+}
+
+pub async fn handle_accounts(client: &FastmailClient, cmd: AccountsCommands) -> Result<()> {
+    match cmd {
+        AccountsCommands::List => {
+            let resp = Response::ok(client.accounts());
+            print_formatted(&resp)?;
+            Ok(())
+        }
+    }
+}
@@ -1,6 +1,7 @@
 // fastmail-cli/src/commands/blob.rs
 use crate::output::{print_response, ErrorResponse, Response};
 use anyhow::Result;
+use tokio::io::AsyncReadExt;
 
 #[derive(clap::Subcommand, Clone, Debug)]
 pub enum BlobCommands {
@@ -9,10 +10,17 @@ pub enum BlobCommands {
     /// Upload a file as a blob
     Upload {
         /// Path to the file to upload
-        path: String,
+        #[arg(required_unless_present = "stdin")]
+        path: Option<String>,
+        /// Read the content from stdin instead of a file
+        #[arg(long, conflicts_with = "path")]
+        stdin: bool,
         /// Media type hint
         #[arg(short, long)]
         type_: Option<String>,
+        /// Also fetch the server's SHA-256 digest of the blob
+        #[arg(long)]
+        digest: bool,
     },
     /// Download blob content
     Download {
@@ -43,30 +51,66 @@ pub async fn handle_blob_command(
 ) -> Result<()> {
     match cmd {
         BlobCommands::Capability => {
+            // Uploads larger than these are refused before being sent
+            let cap = client.blob_capability();
+            let limits = serde_json::json!({
+                "maxSizeUpload": client.max_size_upload(),
+                "maxSizeBlobSet": cap.as_ref().and_then(|c| c.max_size_blob_set)
+            });
             if client.has_blob_capability() {
-                let cap = client.blob_capability();
                 let resp = Response::ok(serde_json::json!({
                     "supported": true,
-                    "capability": cap
+                    "capability": cap,
+                    "limits": limits
                 }));
                 print_response(&resp)?;
             } else {
                 let resp = Response::ok(serde_json::json!({
                     "supported": false,
-                    "capability": null
+                    "capability": null,
+                    "limits": limits
                 }));
                 print_response(&resp)?;
             }
             Ok(())
         }
-        BlobCommands::Upload { path, type_ } => {
-            let content = tokio::fs::read(&path).await?;
-            let blob_id = client.blob_upload_bytes(&content, type_.as_deref()).await?;
+        BlobCommands::Upload {
+            path,
+            stdin: _,
+            type_,
+            digest,
+        } => {
+            // clap requires a path unless --stdin was given
+            let (blob_id, size) = match path {
+                // Files are streamed through the uploadUrl rather than read into memory
+                Some(path) => {
+                    let path = std::path::Path::new(&path);
+                    let type_ = type_.unwrap_or_else(|| {
+                        mime_guess::from_path(path)
+                            .first_or_octet_stream()
+                            .to_string()
+                    });
+                    let blob_id = client.upload_blob_file(path, &type_).await?;
+                    (blob_id, std::fs::metadata(path)?.len())
+                }
+                None => {
+                    let mut content = Vec::new();
+                    tokio::io::stdin().read_to_end(&mut content).await?;
+                    let blob_id = client.blob_upload_bytes(&content, type_.as_deref()).await?;
+                    (blob_id, content.len() as u64)
+                }
+            };
 
-            let resp = Response::ok(serde_json::json!({
+            let mut result = serde_json::json!({
                 "blobId": blob_id,
-                "size": content.len()
-            }));
+                "size": size
+            });
+            if digest {
+                result["digest:sha-256"] =
+                    serde_json::json!(client.blob_digest(&blob_id, "sha-256").await?);
+            }
+
+            let resp = Response::ok(result);
             print_response(&resp)?;
             Ok(())
         }
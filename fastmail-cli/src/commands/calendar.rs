@@ -1,10 +1,11 @@
 // fastmail-cli/src/commands/calendar.rs
-use crate::output::{print_response, ErrorResponse, ExitCode, Meta, Response};
-use crate::utils::confirm;
+use crate::format::{BusyList, ConflictList, EventList};
+use crate::output::{print_formatted, print_response, ErrorResponse, ExitCode, Meta, Response};
+use crate::utils::{parse_rfc3339, GlobalOpts};
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveTime, TimeZone, Utc};
 use clap::Subcommand;
-use fastmail_client::{CalDavClient, CalendarEvent, Config};
+use fastmail_client::{Alarm, CalDavClient, CalendarEvent, CalendarTask};
 use serde_json::json;
 
 #[derive(Subcommand, Clone, Debug)]
@@ -36,21 +37,30 @@ pub enum CalendarCommands {
     ListEvents {
         #[arg(short, long)]
         calendar: Option<String>,
-        #[arg(short, long)]
-        from: Option<String>,
-        #[arg(short, long)]
-        to: Option<String>,
+        /// Only events ending after this time: RFC 3339, "now" or an offset like -2h or +7d
+        #[arg(short, long, value_parser = parse_when, allow_hyphen_values = true)]
+        from: Option<DateTime<Utc>>,
+        /// Only events starting before this time (exclusive), in the same forms as --from
+        #[arg(short, long, value_parser = parse_when, allow_hyphen_values = true)]
+        to: Option<DateTime<Utc>>,
+        /// Only events during today, this week (from Monday) or this month,
+        /// in the configured timezone
+        #[arg(long, value_enum, conflicts_with_all = ["from", "to"])]
+        range: Option<DateRange>,
         #[arg(short, long, default_value = "100")]
         limit: usize,
     },
     /// Get a specific event
     GetEvent { href: String },
-    /// Create an event (JSON input)
+    /// Create an event (JSON input), or replace the one with the same UID
     CreateEvent {
         #[arg(short, long)]
         calendar: String,
         #[arg(short, long)]
         data: String,
+        /// Add a reminder this long before the start, e.g. 15m, 2h or 1d (repeatable)
+        #[arg(long, value_name = "DURATION", value_parser = parse_reminder)]
+        remind: Vec<i64>,
         #[arg(long)]
         dry_run: bool,
     },
@@ -62,10 +72,160 @@ pub enum CalendarCommands {
         #[arg(long)]
         dry_run: bool,
     },
+    /// Show busy times across all calendars
+    Freebusy {
+        /// Start of the range (RFC 3339, e.g. 2024-05-01T09:00:00Z, or an offset like +1d)
+        #[arg(long, value_parser = parse_when, allow_hyphen_values = true)]
+        from: DateTime<Utc>,
+        /// End of the range (RFC 3339 or an offset)
+        #[arg(long, value_parser = parse_when, allow_hyphen_values = true)]
+        to: DateTime<Utc>,
+    },
+    /// List pairs of overlapping events across all calendars
+    Conflicts {
+        /// Start of the range (RFC 3339 or an offset like +1d)
+        #[arg(long, value_parser = parse_when, allow_hyphen_values = true)]
+        from: DateTime<Utc>,
+        /// End of the range (RFC 3339 or an offset)
+        #[arg(long, value_parser = parse_when, allow_hyphen_values = true)]
+        to: DateTime<Utc>,
+    },
+    /// Task (to-do) operations
+    #[command(subcommand)]
+    Tasks(TaskCommands),
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum TaskCommands {
+    /// List tasks
+    List {
+        #[arg(short, long)]
+        calendar: Option<String>,
+        #[arg(short, long, default_value = "100")]
+        limit: usize,
+    },
+    /// Create a task (JSON input)
+    Create {
+        #[arg(short, long)]
+        calendar: String,
+        #[arg(short, long)]
+        data: String,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Delete a task
+    Delete {
+        href: String,
+        #[arg(long)]
+        force: bool,
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// A calendar period containing the current time
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum DateRange {
+    Today,
+    Week,
+    Month,
+}
+
+impl DateRange {
+    /// Start and exclusive end of the period containing `now`, with days
+    /// starting at midnight in `tz`
+    fn bounds<Z: TimeZone>(self, now: DateTime<Utc>, tz: &Z) -> (DateTime<Utc>, DateTime<Utc>) {
+        let today = now.with_timezone(tz).date_naive();
+        let (start, end) = match self {
+            DateRange::Today => (today, today + Days::new(1)),
+            DateRange::Week => {
+                let monday = today - Days::new(today.weekday().num_days_from_monday().into());
+                (monday, monday + Days::new(7))
+            }
+            DateRange::Month => {
+                let first = today.with_day(1).unwrap_or(today);
+                (first, first + Months::new(1))
+            }
+        };
+        let midnight = |day: NaiveDate| {
+            let local = day.and_time(NaiveTime::MIN);
+            // A DST change at midnight can skip it; fall back to UTC midnight then
+            tz.from_local_datetime(&local)
+                .earliest()
+                .map_or_else(|| local.and_utc(), |dt| dt.with_timezone(&Utc))
+        };
+        (midnight(start), midnight(end))
+    }
+}
+
+/// Parse an offset like "15m", "2h", "1d" or "1w" into minutes
+fn parse_minutes(s: &str) -> Option<i64> {
+    let unit = s.chars().last()?;
+    let amount = &s[..s.len() - unit.len_utf8()];
+    if amount.is_empty() || !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let amount: i64 = amount.parse().ok()?;
+    match unit {
+        'm' => Some(amount),
+        'h' => Some(amount * 60),
+        'd' => Some(amount * 60 * 24),
+        'w' => Some(amount * 60 * 24 * 7),
+        _ => None,
+    }
+}
+
+/// Parse a reminder offset like "15m", "2h" or "1d" into minutes
+fn parse_reminder(s: &str) -> Result<i64, String> {
+    parse_minutes(s.trim())
+        .ok_or_else(|| format!("invalid reminder '{}': expected e.g. 15m, 2h or 1d", s))
+}
+
+/// Parse a time as RFC 3339, "now", or an offset from now like "+7d" or "-2h"
+/// (a clap value parser)
+fn parse_when(s: &str) -> Result<DateTime<Utc>, String> {
+    parse_when_at(s, Utc::now())
+}
+
+fn parse_when_at(s: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let s = s.trim();
+    if s == "now" {
+        return Ok(now);
+    }
+    let offset = match s.strip_prefix('-') {
+        Some(offset) => parse_minutes(offset).map(|minutes| -minutes),
+        None => parse_minutes(s.strip_prefix('+').unwrap_or(s)),
+    };
+    if let Some(minutes) = offset {
+        return Ok(now + chrono::Duration::minutes(minutes));
+    }
+    parse_rfc3339(s).map_err(|_| {
+        format!(
+            "invalid time '{}': expected e.g. 2024-05-01T09:00:00Z, now, +7d or -2h",
+            s
+        )
+    })
+}
+
+/// Find a calendar by name or href, or use the first one when none is given
+async fn resolve_calendar(client: &CalDavClient, calendar: Option<&str>) -> Result<String> {
+    let calendars = client.list_calendars().await?;
+
+    match calendar {
+        Some(cal_name) => calendars
+            .iter()
+            .find(|c| c.href.ends_with(cal_name) || c.display_name.as_deref() == Some(cal_name))
+            .map(|c| c.href.clone())
+            .ok_or_else(|| anyhow::anyhow!("Calendar not found: {}", cal_name)),
+        None => calendars
+            .first()
+            .map(|c| c.href.clone())
+            .ok_or_else(|| anyhow::anyhow!("No calendars found")),
+    }
 }
 
-pub async fn handle_calendar(cmd: CalendarCommands) -> Result<()> {
-    let config = Config::load()?;
+pub async fn handle_calendar(opts: &GlobalOpts, cmd: CalendarCommands) -> Result<()> {
+    let config = opts.load_dav_config().await?;
     let client = CalDavClient::from_config(&config).await?;
 
     match cmd {
@@ -145,16 +305,8 @@ pub async fn handle_calendar(cmd: CalendarCommands) -> Result<()> {
             force,
             dry_run,
         } => {
-            // Prompt for confirmation unless --force is specified
-            if !force && !dry_run {
-                let prompt = format!("Delete calendar '{}'?", href);
-                if !confirm(&prompt)? {
-                    let resp = Response::<()>::error(ErrorResponse::safety_rejected(
-                        "Operation cancelled".to_string(),
-                    ));
-                    print_response(&resp)?;
-                    std::process::exit(ExitCode::SafetyRejected.code());
-                }
+            if !dry_run {
+                opts.confirm_destructive(&format!("Delete calendar '{}'?", href), force)?;
             }
 
             if dry_run {
@@ -193,67 +345,79 @@ pub async fn handle_calendar(cmd: CalendarCommands) -> Result<()> {
             calendar,
             from,
             to,
+            range,
             limit,
         } => {
-            // Determine which calendar to use
-            let calendar_href = if let Some(ref cal_name) = calendar {
-                // Try to find the calendar by name/href
-                let calendars = client.list_calendars().await?;
-                calendars
-                    .iter()
-                    .find(|c| {
-                        c.href.ends_with(cal_name)
-                            || c.display_name
-                                .as_ref()
-                                .map(|dn| dn == cal_name)
-                                .unwrap_or(false)
-                    })
-                    .ok_or_else(|| anyhow::anyhow!("Calendar not found: {}", cal_name))?
-                    .href
-                    .clone()
-            } else {
-                // Use the first available calendar
-                let calendars = client.list_calendars().await?;
-                if calendars.is_empty() {
-                    return Err(anyhow::anyhow!("No calendars found"));
+            let (from, to) = match range {
+                Some(range) => {
+                    let (from, to) = range.bounds(Utc::now(), &config.timezone()?);
+                    (Some(from), Some(to))
                 }
-                calendars[0].href.clone()
+                None => (from, to),
             };
+            let calendar_href = resolve_calendar(&client, calendar.as_deref()).await?;
 
-            // Get events
-            let mut events = client.list_events(&calendar_href).await?;
-
-            // Filter by date range if provided
-            if let Some(from_str) = from {
-                if let Ok(from_dt) = DateTime::parse_from_rfc3339(&from_str) {
-                    let from_utc = from_dt.with_timezone(&Utc);
-                    events.retain(|e| e.start >= from_utc);
-                }
-            }
-
-            if let Some(to_str) = to {
-                if let Ok(to_dt) = DateTime::parse_from_rfc3339(&to_str) {
-                    let to_utc = to_dt.with_timezone(&Utc);
-                    events.retain(|e| e.start <= to_utc);
-                }
-            }
+            // The server narrows the list to events overlapping the window. With
+            // both ends known it also expands recurring events into their
+            // occurrences; a series' own start can lie long before the window.
+            let mut events = match (from, to) {
+                (Some(from), Some(to)) => client.list_occurrences(&calendar_href, from, to).await?,
+                _ => client.list_events_between(&calendar_href, from, to).await?,
+            };
+            events.sort_by_key(|e| e.start);
 
             // Apply limit
             let events: Vec<CalendarEvent> = events.into_iter().take(limit).collect();
 
             let resp = Response::ok_with_meta(
-                json!({
-                    "events": events,
-                    "count": events.len(),
-                    "calendar": calendar_href,
-                }),
+                EventList {
+                    count: events.len(),
+                    events,
+                    calendar: calendar_href,
+                },
                 Meta {
                     rate_limit: None,
                     dry_run: None,
                     operation_id: None,
                 },
             );
-            print_response(&resp)?;
+            print_formatted(&resp)?;
+            Ok(())
+        }
+        CalendarCommands::Freebusy { from, to } => {
+            if to <= from {
+                let resp = Response::<()>::error(ErrorResponse::validation_failed(
+                    "--to must be after --from".to_string(),
+                ));
+                print_response(&resp)?;
+                std::process::exit(ExitCode::PermanentError.code());
+            }
+
+            let busy = client.free_busy(from, to).await?;
+
+            let resp = Response::ok(BusyList { busy, from, to });
+            print_formatted(&resp)?;
+            Ok(())
+        }
+        CalendarCommands::Conflicts { from, to } => {
+            if to <= from {
+                let resp = Response::<()>::error(ErrorResponse::validation_failed(
+                    "--to must be after --from".to_string(),
+                ));
+                print_response(&resp)?;
+                std::process::exit(ExitCode::PermanentError.code());
+            }
+
+            let found = client.conflicts(from, to).await?;
+
+            let resp = Response::ok(ConflictList {
+                count: found.conflicts.len(),
+                conflicts: found.conflicts,
+                expanded: found.expanded,
+                from,
+                to,
+            });
+            print_formatted(&resp)?;
             Ok(())
         }
         CalendarCommands::GetEvent { href } => {
@@ -266,17 +430,40 @@ pub async fn handle_calendar(cmd: CalendarCommands) -> Result<()> {
         CalendarCommands::CreateEvent {
             calendar,
             data,
+            remind,
             dry_run,
         } => {
             // Parse event JSON
-            let event: CalendarEvent = serde_json::from_str(&data)?;
+            let mut event: CalendarEvent = serde_json::from_str(&data)?;
+            for minutes in remind {
+                event
+                    .alarms
+                    .push(Alarm::display_before(minutes, Some(event.summary.clone())));
+            }
+
+            // PUT with If-None-Match would refuse to overwrite an existing event
+            let existing = client.find_event_href(&calendar, &event.uid).await?;
+            let href = existing
+                .clone()
+                .unwrap_or_else(|| CalDavClient::event_href(&calendar, &event.uid));
+            let operation = if existing.is_some() {
+                "update_event"
+            } else {
+                "create_event"
+            };
 
             if dry_run {
+                let would = if existing.is_some() {
+                    "would_update"
+                } else {
+                    "would_create"
+                };
                 let resp = Response::ok_with_meta(
                     json!({
-                        "operation": "create_event",
-                        "would_create": event,
+                        "operation": operation,
+                        would: event,
                         "calendar": calendar,
+                        "href": href,
                     }),
                     Meta {
                         rate_limit: None,
@@ -287,12 +474,16 @@ pub async fn handle_calendar(cmd: CalendarCommands) -> Result<()> {
                 print_response(&resp)?;
                 Ok(())
             } else {
-                let etag = client.put_event(&calendar, &event).await?;
+                let etag = match existing {
+                    Some(_) => client.update_event(&href, &event).await?,
+                    None => client.put_event(&calendar, &event, None).await?,
+                };
 
                 let resp = Response::ok_with_meta(
                     json!({
-                        "operation": "create_event",
+                        "operation": operation,
                         "event": event,
+                        "href": href,
                         "etag": etag,
                     }),
                     Meta {
@@ -310,16 +501,8 @@ pub async fn handle_calendar(cmd: CalendarCommands) -> Result<()> {
             force,
             dry_run,
         } => {
-            // Prompt for confirmation unless --force is specified
-            if !force && !dry_run {
-                let prompt = format!("Delete event '{}'?", href);
-                if !confirm(&prompt)? {
-                    let resp = Response::<()>::error(ErrorResponse::safety_rejected(
-                        "Operation cancelled".to_string(),
-                    ));
-                    print_response(&resp)?;
-                    std::process::exit(ExitCode::SafetyRejected.code());
-                }
+            if !dry_run {
+                opts.confirm_destructive(&format!("Delete event '{}'?", href), force)?;
             }
 
             if dry_run {
@@ -357,5 +540,215 @@ pub async fn handle_calendar(cmd: CalendarCommands) -> Result<()> {
                 Ok(())
             }
         }
+        CalendarCommands::Tasks(cmd) => handle_tasks(opts, &client, cmd).await,
+    }
+}
+
+async fn handle_tasks(opts: &GlobalOpts, client: &CalDavClient, cmd: TaskCommands) -> Result<()> {
+    match cmd {
+        TaskCommands::List { calendar, limit } => {
+            let calendar_href = resolve_calendar(client, calendar.as_deref()).await?;
+
+            let tasks: Vec<CalendarTask> = client
+                .list_tasks(&calendar_href)
+                .await?
+                .into_iter()
+                .take(limit)
+                .collect();
+
+            let resp = Response::ok_with_meta(
+                json!({
+                    "tasks": tasks,
+                    "count": tasks.len(),
+                    "calendar": calendar_href,
+                }),
+                Meta {
+                    rate_limit: None,
+                    dry_run: None,
+                    operation_id: None,
+                },
+            );
+            print_response(&resp)?;
+            Ok(())
+        }
+        TaskCommands::Create {
+            calendar,
+            data,
+            dry_run,
+        } => {
+            // Parse task JSON
+            let task: CalendarTask = serde_json::from_str(&data)?;
+
+            if dry_run {
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "create_task",
+                        "would_create": task,
+                        "calendar": calendar,
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(true),
+                        operation_id: Some(format!("create-task-{}", task.uid)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            } else {
+                let etag = client.put_task(&calendar, &task, None).await?;
+
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "create_task",
+                        "task": task,
+                        "etag": etag,
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(false),
+                        operation_id: Some(format!("create-task-{}", task.uid)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            }
+        }
+        TaskCommands::Delete {
+            href,
+            force,
+            dry_run,
+        } => {
+            if !dry_run {
+                opts.confirm_destructive(&format!("Delete task '{}'?", href), force)?;
+            }
+
+            if dry_run {
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "delete",
+                        "would_delete": href
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(true),
+                        operation_id: Some(format!("delete-task-{}", href)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            } else {
+                client.delete_task(&href).await?;
+
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "delete",
+                        "deleted": href
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(false),
+                        operation_id: Some(format!("delete-task-{}", href)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reminder() {
+        assert_eq!(parse_reminder("15m"), Ok(15));
+        assert_eq!(parse_reminder("2h"), Ok(120));
+        assert_eq!(parse_reminder("1d"), Ok(1440));
+        assert!(parse_reminder("15").is_err());
+        assert!(parse_reminder("m").is_err());
+        assert!(parse_reminder("").is_err());
+        assert!(parse_reminder("-5m").is_err());
+        assert!(parse_reminder("15é").is_err());
+    }
+
+    #[test]
+    fn test_parse_when() {
+        let now = parse_rfc3339("2024-05-01T09:00:00Z").unwrap();
+        let at = |s| parse_when_at(s, now).map(|dt| dt.to_rfc3339());
+        assert_eq!(at("now"), Ok("2024-05-01T09:00:00+00:00".to_string()));
+        assert_eq!(at("+7d"), Ok("2024-05-08T09:00:00+00:00".to_string()));
+        assert_eq!(at("2h"), Ok("2024-05-01T11:00:00+00:00".to_string()));
+        assert_eq!(at("-1w"), Ok("2024-04-24T09:00:00+00:00".to_string()));
+        assert_eq!(
+            at("2024-06-01T10:00:00+02:00"),
+            Ok("2024-06-01T08:00:00+00:00".to_string())
+        );
+        assert!(at("+-1d").is_err());
+        assert!(at("tomorrow").is_err());
+    }
+
+    #[test]
+    fn test_negative_offsets_are_values_not_flags() {
+        use clap::Parser;
+
+        #[derive(Parser)]
+        struct Args {
+            #[command(subcommand)]
+            cmd: CalendarCommands,
+        }
+        let parse = |args: &[&str]| {
+            Args::try_parse_from(std::iter::once("calendar").chain(args.iter().copied()))
+                .map(|args| args.cmd)
+                .map_err(|e| e.to_string())
+        };
+
+        let before = Utc::now();
+        match parse(&["list-events", "--from", "-2h", "-t", "-1h"]).unwrap() {
+            CalendarCommands::ListEvents {
+                from: Some(from),
+                to: Some(to),
+                ..
+            } => {
+                assert!(from < before - chrono::Duration::minutes(119));
+                assert!(to < before - chrono::Duration::minutes(59) && to > from);
+            }
+            _ => panic!("expected list-events with --from and --to"),
+        }
+        assert!(parse(&["freebusy", "--from", "-1d", "--to", "now"]).is_ok());
+        assert!(parse(&["conflicts", "--from", "-1w", "--to", "+1w"]).is_ok());
+    }
+
+    #[test]
+    fn test_date_range_bounds() {
+        // A Wednesday evening at UTC-4 is already Thursday in UTC
+        let now = parse_rfc3339("2024-05-02T01:30:00Z").unwrap();
+        let tz = chrono::FixedOffset::west_opt(4 * 3600).unwrap();
+        let bounds = |range: DateRange| {
+            let (from, to) = range.bounds(now, &tz);
+            (from.to_rfc3339(), to.to_rfc3339())
+        };
+        assert_eq!(
+            bounds(DateRange::Today),
+            (
+                "2024-05-01T04:00:00+00:00".to_string(),
+                "2024-05-02T04:00:00+00:00".to_string()
+            )
+        );
+        assert_eq!(
+            bounds(DateRange::Week),
+            (
+                "2024-04-29T04:00:00+00:00".to_string(),
+                "2024-05-06T04:00:00+00:00".to_string()
+            )
+        );
+        assert_eq!(
+            bounds(DateRange::Month),
+            (
+                "2024-05-01T04:00:00+00:00".to_string(),
+                "2024-06-01T04:00:00+00:00".to_string()
+            )
+        );
     }
 }
@@ -0,0 +1,202 @@
+// fastmail-cli/src/commands/config.rs
+use crate::keychain;
+use crate::output::{print_response, Response};
+use crate::utils::GlobalOpts;
+use anyhow::Result;
+use clap::Subcommand;
+use fastmail_client::{AccountProfile, Config};
+use serde_json::{json, Value};
+
+/// Config keys holding credentials, shown masked
+const SECRET_KEYS: &[&str] = &["token", "dav_password"];
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum ConfigCommands {
+    /// Show the effective config, with tokens and passwords masked
+    Show,
+    /// Print the effective value of a dotted key such as `safety.require_confirm`
+    Get { key: String },
+    /// Set a dotted key in config.toml; an empty value clears an optional key
+    Set { key: String, value: String },
+    /// Manage the recipient whitelist used by send safety checks
+    #[command(subcommand)]
+    AllowRecipient(AllowRecipientCommands),
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum AllowRecipientCommands {
+    /// Add an address to the whitelist
+    Add { email: String },
+    /// List whitelisted addresses
+    List,
+    /// Remove an address from the whitelist
+    Remove { email: String },
+}
+
+pub async fn handle_config(opts: &GlobalOpts, cmd: ConfigCommands) -> Result<()> {
+    match cmd {
+        ConfigCommands::Show => {
+            let config = opts.load_config()?;
+            let account = opts.selected_account();
+            let sources = json!({
+                "token": token_source(&config, account),
+                "dav_password": credential_source(
+                    &config,
+                    account,
+                    "FASTMAIL_DAV_PASSWORD",
+                    |p| p.dav_password.is_some(),
+                    config.dav_password.is_some(),
+                ),
+                "email": credential_source(
+                    &config,
+                    account,
+                    "FASTMAIL_EMAIL",
+                    |p| p.email.is_some(),
+                    config.account.email.is_some(),
+                ),
+            });
+
+            let mut shown = serde_json::to_value(&config)?;
+            mask_secrets(&mut shown);
+            let resp = Response::ok(json!({
+                "account": account.or(config.default_account.as_deref()),
+                "config": shown,
+                "sources": sources,
+            }));
+            print_response(&resp)?;
+            Ok(())
+        }
+        ConfigCommands::Get { key } => {
+            let value = opts.load_config()?.get_key(&key)?;
+            print_response(&Response::ok(json!({ "key": key, "value": value })))?;
+            Ok(())
+        }
+        ConfigCommands::Set { key, value } => {
+            let mut config = opts.load_config_file()?;
+            config.set_key(&key, &value)?;
+            config.save()?;
+
+            let value = config.get_key(&key)?;
+            print_response(&Response::ok(json!({ "key": key, "value": value })))?;
+            Ok(())
+        }
+        ConfigCommands::AllowRecipient(allow) => match allow {
+            AllowRecipientCommands::Add { email } => {
+                let mut whitelist = opts.load_whitelist()?;
+                whitelist.add(email.clone())?;
+
+                let resp = Response::ok(json!({
+                    "email": email,
+                    "added": true
+                }));
+                print_response(&resp)?;
+                Ok(())
+            }
+            AllowRecipientCommands::List => {
+                let whitelist = opts.load_whitelist()?;
+
+                let resp = Response::ok(json!({
+                    "allowed_recipients": whitelist.list()
+                }));
+                print_response(&resp)?;
+                Ok(())
+            }
+            AllowRecipientCommands::Remove { email } => {
+                let mut whitelist = opts.load_whitelist()?;
+                whitelist.remove(&email)?;
+
+                let resp = Response::ok(json!({
+                    "email": email,
+                    "removed": true
+                }));
+                print_response(&resp)?;
+                Ok(())
+            }
+        },
+    }
+}
+
+/// Where the JMAP token comes from, following `load_jmap_client`'s precedence
+fn token_source(config: &Config, account: Option<&str>) -> String {
+    let env_set = std::env::var_os("FASTMAIL_TOKEN").is_some();
+    // Without a token, JMAP uses Basic auth with the email and app password
+    let unset = if config.account.email.is_some() && config.dav_password.is_some() {
+        "app password"
+    } else {
+        "unset"
+    };
+    match config.account_profile(account).ok().flatten() {
+        Some((name, profile)) if account.is_some() || !env_set => {
+            if profile.token.is_some() {
+                format!("accounts.{}", name)
+            } else if keychain::load_token(Some(name)).is_some() {
+                format!("keychain ({})", name)
+            } else {
+                unset.to_string()
+            }
+        }
+        _ if env_set => "FASTMAIL_TOKEN".to_string(),
+        _ if !config.token.is_empty() => "config.toml".to_string(),
+        _ if keychain::load_token(None).is_some() => "keychain".to_string(),
+        _ => unset.to_string(),
+    }
+}
+
+/// Where an email or DAV password comes from, following `Config::load_account`:
+/// a named account beats the environment, which beats the default account and the file
+fn credential_source(
+    config: &Config,
+    account: Option<&str>,
+    env_var: &str,
+    in_profile: impl Fn(&AccountProfile) -> bool,
+    in_config: bool,
+) -> String {
+    let env_set = std::env::var_os(env_var).is_some();
+    match config.account_profile(account).ok().flatten() {
+        Some((name, profile)) if in_profile(profile) && (account.is_some() || !env_set) => {
+            format!("accounts.{}", name)
+        }
+        _ if env_set => env_var.to_string(),
+        _ if in_config => "config.toml".to_string(),
+        _ => "unset".to_string(),
+    }
+}
+
+/// Replace non-empty credentials with "****", at the top level and in `accounts`
+fn mask_secrets(config: &mut Value) {
+    let mask = |table: &mut Value| {
+        for key in SECRET_KEYS {
+            if let Some(value) = table.get_mut(*key) {
+                if value.as_str().is_some_and(|v| !v.is_empty()) {
+                    *value = json!("****");
+                }
+            }
+        }
+    };
+    mask(config);
+    if let Some(accounts) = config.get_mut("accounts").and_then(Value::as_object_mut) {
+        accounts.values_mut().for_each(mask);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_secrets() {
+        let mut config = json!({
+            "token": "fmu1-top",
+            "dav_password": null,
+            "account": { "email": "me@example.com" },
+            "accounts": { "work": { "token": "fmu1-work", "dav_password": "pw", "email": "w@x" } }
+        });
+        mask_secrets(&mut config);
+        assert_eq!(config["token"], "****");
+        assert_eq!(config["dav_password"], Value::Null);
+        assert_eq!(config["account"]["email"], "me@example.com");
+        assert_eq!(config["accounts"]["work"]["token"], "****");
+        assert_eq!(config["accounts"]["work"]["dav_password"], "****");
+        assert_eq!(config["accounts"]["work"]["email"], "w@x");
+    }
+}
@@ -1,10 +1,13 @@
 // fastmail-cli/src/commands/contacts.rs
-use crate::output::{print_response, ErrorResponse, ExitCode, Meta, Response};
-use crate::utils::confirm;
+use crate::format::{ContactList, ContactSearchResults};
+use crate::output::{print_formatted, print_response, ErrorResponse, ExitCode, Meta, Response};
+use crate::utils::GlobalOpts;
 use anyhow::Result;
 use clap::Subcommand;
-use fastmail_client::{CardDavClient, Config, Contact};
+use fastmail_client::{CardDavClient, Contact, ContactMatch, ContactPhoto};
 use serde_json::json;
+use std::io::Write;
+use std::path::PathBuf;
 
 #[derive(Subcommand, Clone, Debug)]
 pub enum ContactsCommands {
@@ -37,11 +40,43 @@ pub enum ContactsCommands {
         book: Option<String>,
         #[arg(short, long)]
         search: Option<String>,
+        /// Only contacts tagged with this category
+        #[arg(long)]
+        category: Option<String>,
+        #[arg(short, long, default_value = "100")]
+        limit: usize,
+    },
+    /// Search contacts by name, email, organization or notes
+    Search {
+        query: String,
+        /// Only this address book (name or href); every address book by default
+        #[arg(short, long)]
+        book: Option<String>,
         #[arg(short, long, default_value = "100")]
         limit: usize,
     },
     /// Get a specific contact
     Get { href: String },
+    /// Write a contact's vCard exactly as stored, to a file or stdout
+    Export {
+        href: String,
+        /// File to write; stdout when omitted
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Save a contact's embedded photo to a file
+    PhotoGet {
+        href: String,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Upload a .vcf file unchanged into an address book (name or href)
+    Import {
+        book: String,
+        file: PathBuf,
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Create a contact (JSON input)
     Create {
         #[arg(short, long)]
@@ -51,6 +86,15 @@ pub enum ContactsCommands {
         #[arg(long)]
         dry_run: bool,
     },
+    /// Update fields of an existing contact, keeping everything else
+    Update {
+        href: String,
+        /// Field override, e.g. --field email=jane@example.com (repeatable; empty value clears)
+        #[arg(short, long = "field", value_name = "KEY=VALUE", required = true)]
+        fields: Vec<String>,
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Delete a contact
     Delete {
         href: String,
@@ -61,8 +105,8 @@ pub enum ContactsCommands {
     },
 }
 
-pub async fn handle_contacts(cmd: ContactsCommands) -> Result<()> {
-    let config = Config::load()?;
+pub async fn handle_contacts(opts: &GlobalOpts, cmd: ContactsCommands) -> Result<()> {
+    let config = opts.load_dav_config().await?;
     let client = CardDavClient::from_config(&config).await?;
 
     match cmd {
@@ -139,16 +183,8 @@ pub async fn handle_contacts(cmd: ContactsCommands) -> Result<()> {
             force,
             dry_run,
         } => {
-            // Prompt for confirmation unless --force is specified
-            if !force && !dry_run {
-                let prompt = format!("Delete address book '{}'?", href);
-                if !confirm(&prompt)? {
-                    let resp = Response::<()>::error(ErrorResponse::safety_rejected(
-                        "Operation cancelled".to_string(),
-                    ));
-                    print_response(&resp)?;
-                    std::process::exit(ExitCode::SafetyRejected.code());
-                }
+            if !dry_run {
+                opts.confirm_destructive(&format!("Delete address book '{}'?", href), force)?;
             }
 
             if dry_run {
@@ -186,18 +222,12 @@ pub async fn handle_contacts(cmd: ContactsCommands) -> Result<()> {
         ContactsCommands::List {
             book,
             search,
+            category,
             limit,
         } => {
             // Determine which address book to use
             let book_href = if let Some(ref book_name) = book {
-                // Try to find the address book by name/href
-                let address_books = client.list_address_books().await?;
-                address_books
-                    .iter()
-                    .find(|ab| ab.href.ends_with(book_name) || ab.display_name == *book_name)
-                    .ok_or_else(|| anyhow::anyhow!("Address book not found: {}", book_name))?
-                    .href
-                    .clone()
+                find_book(&client, book_name).await?
             } else {
                 // Use the first available address book
                 let address_books = client.list_address_books().await?;
@@ -214,22 +244,51 @@ pub async fn handle_contacts(cmd: ContactsCommands) -> Result<()> {
                 client.list_contacts(&book_href).await?
             };
 
-            // Apply limit
-            let contacts: Vec<Contact> = contacts.into_iter().take(limit).collect();
+            // Apply category filter and limit
+            let contacts: Vec<Contact> = contacts
+                .into_iter()
+                .filter(|c| category.as_ref().is_none_or(|tag| c.has_category(tag)))
+                .take(limit)
+                .collect();
 
             let resp = Response::ok_with_meta(
-                json!({
-                    "contacts": contacts,
-                    "count": contacts.len(),
-                    "book": book_href,
-                }),
+                ContactList {
+                    count: contacts.len(),
+                    contacts,
+                    book: book_href,
+                },
                 Meta {
                     rate_limit: None,
                     dry_run: None,
                     operation_id: None,
                 },
             );
-            print_response(&resp)?;
+            print_formatted(&resp)?;
+            Ok(())
+        }
+        ContactsCommands::Search { query, book, limit } => {
+            let mut contacts = match book {
+                Some(book_name) => {
+                    let book = find_book(&client, &book_name).await?;
+                    client
+                        .search_contacts(&book, &query)
+                        .await?
+                        .into_iter()
+                        .map(|contact| ContactMatch {
+                            book: book.clone(),
+                            contact,
+                        })
+                        .collect()
+                }
+                None => client.search_all_contacts(&query).await?,
+            };
+            contacts.truncate(limit);
+
+            let resp = Response::ok(ContactSearchResults {
+                count: contacts.len(),
+                contacts,
+            });
+            print_formatted(&resp)?;
             Ok(())
         }
         ContactsCommands::Get { href } => {
@@ -239,6 +298,90 @@ pub async fn handle_contacts(cmd: ContactsCommands) -> Result<()> {
             print_response(&resp)?;
             Ok(())
         }
+        ContactsCommands::Export { href, output } => {
+            let (vcard, etag) = client.get_vcard(&href).await?;
+            let Some(output) = output else {
+                std::io::stdout().write_all(vcard.as_bytes())?;
+                return Ok(());
+            };
+            tokio::fs::write(&output, &vcard).await?;
+
+            let resp = Response::ok(json!({
+                "href": href,
+                "etag": etag,
+                "savedTo": output,
+            }));
+            print_response(&resp)?;
+            Ok(())
+        }
+        ContactsCommands::PhotoGet { href, output } => {
+            let contact = client.get_contact(&href).await?;
+            let (media_type, data) = match contact.photo {
+                Some(ContactPhoto::Embedded { media_type, data }) => (media_type, data),
+                Some(ContactPhoto::Uri(uri)) => {
+                    return Err(anyhow::anyhow!(
+                        "The photo of {} is not embedded; it links to {}",
+                        href,
+                        uri
+                    ))
+                }
+                None => return Err(anyhow::anyhow!("Contact has no photo: {}", href)),
+            };
+            tokio::fs::write(&output, &data).await?;
+
+            let resp = Response::ok(json!({
+                "href": href,
+                "mediaType": media_type,
+                "size": data.len(),
+                "savedTo": output,
+            }));
+            print_response(&resp)?;
+            Ok(())
+        }
+        ContactsCommands::Import {
+            book,
+            file,
+            dry_run,
+        } => {
+            let vcard = tokio::fs::read_to_string(&file)
+                .await
+                .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", file.display(), e))?;
+            let book = find_book(&client, &book).await?;
+
+            if dry_run {
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "import_contact",
+                        "would_import": file,
+                        "book": book,
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(true),
+                        operation_id: Some(format!("import-contact-{}", file.display())),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            } else {
+                let (href, etag) = client.import_vcard(&book, &vcard).await?;
+
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "import_contact",
+                        "href": href,
+                        "etag": etag,
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(false),
+                        operation_id: Some(format!("import-contact-{}", file.display())),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            }
+        }
         ContactsCommands::Create {
             book,
             data,
@@ -263,7 +406,7 @@ pub async fn handle_contacts(cmd: ContactsCommands) -> Result<()> {
                 print_response(&resp)?;
                 Ok(())
             } else {
-                let etag = client.put_contact(&book, &contact).await?;
+                let etag = client.put_contact(&book, &contact, None).await?;
 
                 let resp = Response::ok_with_meta(
                     json!({
@@ -281,21 +424,74 @@ pub async fn handle_contacts(cmd: ContactsCommands) -> Result<()> {
                 Ok(())
             }
         }
-        ContactsCommands::Delete {
+        ContactsCommands::Update {
             href,
-            force,
+            fields,
             dry_run,
         } => {
-            // Prompt for confirmation unless --force is specified
-            if !force && !dry_run {
-                let prompt = format!("Delete contact '{}'?", href);
-                if !confirm(&prompt)? {
-                    let resp = Response::<()>::error(ErrorResponse::safety_rejected(
-                        "Operation cancelled".to_string(),
-                    ));
+            let (vcard, etag) = client.get_vcard(&href).await?;
+
+            let patched = fields
+                .iter()
+                .map(|field| {
+                    field
+                        .split_once('=')
+                        .map(|(key, value)| (key.trim(), value))
+                        .ok_or_else(|| anyhow::anyhow!("Expected KEY=VALUE, got '{}'", field))
+                })
+                .collect::<Result<Vec<_>>>()
+                .and_then(|fields| CardDavClient::patch_vcard(&vcard, &fields));
+            let (vcard, contact) = match patched {
+                Ok(patched) => patched,
+                Err(e) => {
+                    let resp =
+                        Response::<()>::error(ErrorResponse::validation_failed(e.to_string()));
                     print_response(&resp)?;
-                    std::process::exit(ExitCode::SafetyRejected.code());
+                    std::process::exit(ExitCode::PermanentError.code());
                 }
+            };
+
+            if dry_run {
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "update_contact",
+                        "would_update": contact,
+                        "etag": etag,
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(true),
+                        operation_id: Some(format!("update-contact-{}", contact.uid)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            } else {
+                let new_etag = client.update_vcard(&href, &vcard, &etag).await?;
+
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "update_contact",
+                        "contact": contact,
+                        "etag": new_etag,
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(false),
+                        operation_id: Some(format!("update-contact-{}", contact.uid)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            }
+        }
+        ContactsCommands::Delete {
+            href,
+            force,
+            dry_run,
+        } => {
+            if !dry_run {
+                opts.confirm_destructive(&format!("Delete contact '{}'?", href), force)?;
             }
 
             if dry_run {
@@ -335,3 +531,13 @@ pub async fn handle_contacts(cmd: ContactsCommands) -> Result<()> {
         }
     }
 }
+
+/// Href of the address book whose href ends with, or whose name is, `book_name`
+async fn find_book(client: &CardDavClient, book_name: &str) -> Result<String> {
+    let address_books = client.list_address_books().await?;
+    address_books
+        .into_iter()
+        .find(|ab| ab.href.ends_with(book_name) || ab.display_name == book_name)
+        .map(|ab| ab.href)
+        .ok_or_else(|| anyhow::anyhow!("Address book not found: {}", book_name))
+}
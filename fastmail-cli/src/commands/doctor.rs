@@ -0,0 +1,134 @@
+// fastmail-cli/src/commands/doctor.rs
+use crate::format::{DoctorCheck, DoctorReport};
+use crate::output::{print_formatted, ErrorResponse, ExitCode, Response};
+use crate::utils::GlobalOpts;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use fastmail_client::{CalDavClient, CardDavClient, Config, FastmailClient};
+use serde_json::json;
+
+/// Capabilities the CLI has commands for, by the name shown in the report
+const CAPABILITIES: &[(&str, &str)] = &[
+    ("mail", "urn:ietf:params:jmap:mail"),
+    ("submission", "urn:ietf:params:jmap:submission"),
+    ("vacation", "urn:ietf:params:jmap:vacationresponse"),
+    ("blob", "urn:ietf:params:jmap:blob"),
+    ("principals", "urn:ietf:params:jmap:principals"),
+];
+
+/// Collects check results and remembers how the run should exit
+struct Doctor<'a> {
+    opts: &'a GlobalOpts,
+    report: DoctorReport,
+    exit_code: Option<ExitCode>,
+}
+
+impl<'a> Doctor<'a> {
+    fn new(opts: &'a GlobalOpts) -> Self {
+        Self {
+            opts,
+            report: DoctorReport::default(),
+            exit_code: None,
+        }
+    }
+
+    fn record(&mut self, name: &'static str, result: Result<String>) {
+        let (ok, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(e) => {
+                let (_, code) = ErrorResponse::from_error(&e);
+                // A permanent failure wins: retrying will not fix the run
+                if !matches!(self.exit_code, Some(ExitCode::PermanentError)) {
+                    self.exit_code = Some(code);
+                }
+                (false, self.opts.redact_secrets(&e.to_string()))
+            }
+        };
+        self.report.checks.push(DoctorCheck { name, ok, detail });
+    }
+}
+
+/// Check the session, a Core/echo round trip, capabilities and DAV access,
+/// exiting non-zero if anything failed
+pub async fn run_doctor(opts: &GlobalOpts) -> Result<()> {
+    let mut doctor = Doctor::new(opts);
+
+    match opts.load_jmap_client().await {
+        Ok(client) => {
+            doctor.report.account = client.account_email().await.ok();
+            doctor.record("session", Ok(format!("account {}", client.account_id())));
+            doctor.record("echo", check_echo(&client).await);
+            for (name, uri) in CAPABILITIES {
+                doctor
+                    .report
+                    .capabilities
+                    .insert(name, client.has_capability(uri));
+            }
+        }
+        Err(e) => doctor.record("session", Err(e)),
+    }
+
+    match opts.load_dav_config().await {
+        Ok(config) => {
+            doctor.record("caldav", check_caldav(&config).await);
+            doctor.record("carddav", check_carddav(&config).await);
+        }
+        Err(e) => doctor.record("config", Err(e)),
+    }
+
+    doctor.report.healthy = doctor.exit_code.is_none();
+    print_formatted(&Response::ok(doctor.report))?;
+    if let Some(code) = doctor.exit_code {
+        std::process::exit(code.code());
+    }
+    Ok(())
+}
+
+/// Core/echo must hand back exactly what was sent
+async fn check_echo(client: &FastmailClient) -> Result<String> {
+    let sent = json!({ "nonce": format!("fastmail-cli-{}", Utc::now().timestamp_micros()) });
+    let received = client.echo(sent.clone()).await?;
+    if received != sent {
+        return Err(anyhow!(
+            "Core/echo returned {} instead of {}",
+            received,
+            sent
+        ));
+    }
+    Ok("round trip ok".to_string())
+}
+
+async fn check_caldav(config: &Config) -> Result<String> {
+    let client = CalDavClient::from_config(config).await?;
+    let calendars = client.list_calendars().await?;
+    Ok(format!("{} calendars", calendars.len()))
+}
+
+async fn check_carddav(config: &Config) -> Result<String> {
+    let client = CardDavClient::from_config(config).await?;
+    let books = client.list_address_books().await?;
+    Ok(format!("{} address books", books.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fastmail_client::JmapError;
+
+    #[test]
+    fn test_permanent_failure_wins() {
+        let opts = GlobalOpts::default();
+        let mut doctor = Doctor::new(&opts);
+        doctor.record("session", Ok("account u1".to_string()));
+        assert!(doctor.exit_code.is_none());
+
+        doctor.record("echo", Err(JmapError::ServerUnavailable.into()));
+        assert!(matches!(doctor.exit_code, Some(ExitCode::TransientError)));
+
+        doctor.record("caldav", Err(anyhow!("403 Forbidden")));
+        doctor.record("carddav", Err(JmapError::ServerUnavailable.into()));
+        assert!(matches!(doctor.exit_code, Some(ExitCode::PermanentError)));
+        assert_eq!(doctor.report.checks.len(), 4);
+        assert!(doctor.report.checks[0].ok && !doctor.report.checks[1].ok);
+    }
+}
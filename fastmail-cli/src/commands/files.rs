@@ -1,9 +1,9 @@
 // fastmail-cli/src/commands/files.rs
-use crate::output::{print_response, ErrorResponse, ExitCode, Meta, Response};
-use crate::utils::confirm;
+use crate::output::{print_response, Meta, Response};
+use crate::utils::GlobalOpts;
 use anyhow::Result;
 use clap::Subcommand;
-use fastmail_client::{Config, DavClient, DavService};
+use fastmail_client::{DavClient, DavService};
 use serde_json::json;
 
 #[derive(Subcommand, Clone, Debug)]
@@ -12,7 +12,8 @@ pub enum FilesCommands {
     List {
         #[arg(default_value = "/")]
         path: String,
-        #[arg(short, long, default_value = "1")]
+        /// 0 (the path itself), 1 (its members) or infinity
+        #[arg(short, long, default_value = "1", value_parser = parse_depth)]
         depth: u8,
         #[arg(short, long)]
         filter: Option<String>,
@@ -25,6 +26,9 @@ pub enum FilesCommands {
         remote: String,
         #[arg(short, long)]
         content_type: Option<String>,
+        /// Only replace the file if its ETag is still this (see `files info`)
+        #[arg(long, value_name = "ETAG")]
+        if_match: Option<String>,
         #[arg(long)]
         dry_run: bool,
     },
@@ -49,6 +53,9 @@ pub enum FilesCommands {
         to: String,
         #[arg(long, default_value = "false")]
         overwrite: bool,
+        /// Skip the confirmation prompt when overwriting
+        #[arg(long)]
+        force: bool,
         #[arg(long)]
         dry_run: bool,
     },
@@ -58,6 +65,9 @@ pub enum FilesCommands {
         to: String,
         #[arg(long, default_value = "false")]
         overwrite: bool,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        force: bool,
         #[arg(long)]
         dry_run: bool,
     },
@@ -69,8 +79,17 @@ pub enum FilesCommands {
     },
 }
 
-pub async fn handle_files(cmd: FilesCommands) -> Result<()> {
-    let config = Config::load()?;
+/// Parse a PROPFIND depth, accepting "infinity" as well as a number
+fn parse_depth(s: &str) -> Result<u8, String> {
+    if s.eq_ignore_ascii_case("infinity") {
+        return Ok(u8::MAX);
+    }
+    s.parse()
+        .map_err(|_| format!("invalid depth '{}': expected 0, 1 or infinity", s))
+}
+
+pub async fn handle_files(opts: &GlobalOpts, cmd: FilesCommands) -> Result<()> {
+    let config = opts.load_dav_config().await?;
     let client = DavClient::from_config(&config, DavService::Files).await?;
 
     match cmd {
@@ -119,6 +138,7 @@ pub async fn handle_files(cmd: FilesCommands) -> Result<()> {
             local,
             remote,
             content_type,
+            if_match,
             dry_run,
         } => {
             // Read local file
@@ -139,6 +159,7 @@ pub async fn handle_files(cmd: FilesCommands) -> Result<()> {
                         "local": local,
                         "remote": remote,
                         "content_type": ct,
+                        "if_match": if_match,
                         "size": content.len(),
                     }),
                     Meta {
@@ -150,7 +171,9 @@ pub async fn handle_files(cmd: FilesCommands) -> Result<()> {
                 print_response(&resp)?;
                 Ok(())
             } else {
-                let etag = client.put(&remote, &content, &ct).await?;
+                let etag = client
+                    .put(&remote, &content, &ct, if_match.as_deref())
+                    .await?;
 
                 let resp = Response::ok_with_meta(
                     json!({
@@ -175,8 +198,6 @@ pub async fn handle_files(cmd: FilesCommands) -> Result<()> {
             local,
             dry_run,
         } => {
-            // Note: Current DavClient::get returns empty vec due to libdav limitations
-            // This is a placeholder for when actual GET is implemented
             let content = client.get(&remote).await?;
 
             if dry_run {
@@ -220,16 +241,8 @@ pub async fn handle_files(cmd: FilesCommands) -> Result<()> {
             force,
             dry_run,
         } => {
-            // Prompt for confirmation unless --force is specified
-            if !force && !dry_run {
-                let prompt = format!("Delete '{}'?", path);
-                if !confirm(&prompt)? {
-                    let resp = Response::<()>::error(ErrorResponse::safety_rejected(
-                        "Operation cancelled".to_string(),
-                    ));
-                    print_response(&resp)?;
-                    std::process::exit(ExitCode::SafetyRejected.code());
-                }
+            if !dry_run {
+                opts.confirm_destructive(&format!("Delete '{}'?", path), force)?;
             }
 
             if dry_run {
@@ -268,6 +281,7 @@ pub async fn handle_files(cmd: FilesCommands) -> Result<()> {
             from,
             to,
             overwrite,
+            force,
             dry_run,
         } => {
             if dry_run {
@@ -287,6 +301,12 @@ pub async fn handle_files(cmd: FilesCommands) -> Result<()> {
                 print_response(&resp)?;
                 Ok(())
             } else {
+                if overwrite {
+                    opts.confirm_destructive(
+                        &format!("Copy '{}' to '{}', replacing it if it exists?", from, to),
+                        force,
+                    )?;
+                }
                 client.copy(&from, &to, overwrite).await?;
 
                 let resp = Response::ok_with_meta(
@@ -310,6 +330,7 @@ pub async fn handle_files(cmd: FilesCommands) -> Result<()> {
             from,
             to,
             overwrite,
+            force,
             dry_run,
         } => {
             if dry_run {
@@ -329,6 +350,7 @@ pub async fn handle_files(cmd: FilesCommands) -> Result<()> {
                 print_response(&resp)?;
                 Ok(())
             } else {
+                opts.confirm_destructive(&format!("Move '{}' to '{}'?", from, to), force)?;
                 client.move_resource(&from, &to, overwrite).await?;
 
                 let resp = Response::ok_with_meta(
@@ -383,3 +405,16 @@ pub async fn handle_files(cmd: FilesCommands) -> Result<()> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_depth() {
+        assert_eq!(parse_depth("0"), Ok(0));
+        assert_eq!(parse_depth("1"), Ok(1));
+        assert_eq!(parse_depth("Infinity"), Ok(u8::MAX));
+        assert!(parse_depth("deep").is_err());
+    }
+}
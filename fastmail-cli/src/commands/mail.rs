@@ -0,0 +1,1245 @@
+// fastmail-cli/src/commands/mail.rs
+use crate::format::{EmailList, SearchResults, ThreadView};
+use crate::output::{print_formatted, print_response, ErrorResponse, ExitCode, Meta, Response};
+use crate::utils::{exit_if_not_destroyed, exit_if_not_updated, parse_rfc3339, GlobalOpts};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use clap::Subcommand;
+use fastmail_client::html_text::render_html_bodies;
+use fastmail_client::{
+    BodyPart, Comparator, EmailComposeSpec, EmailCreate, EmailFilterCondition, FastmailClient,
+    Filter, JmapError, SyncState,
+};
+use futures::StreamExt;
+use serde_json::json;
+use std::io::Read;
+use std::path::Path;
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum MailCommands {
+    /// List emails
+    List {
+        /// Mailbox name (e.g. Inbox)
+        #[arg(short, long)]
+        mailbox: Option<String>,
+        /// Maximum number of emails to return
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+        /// Zero-based index of the first email to return
+        #[arg(long)]
+        position: Option<usize>,
+        /// Sort by property (receivedAt, sentAt, size, from, subject)
+        #[arg(long, value_parser = ["receivedAt", "sentAt", "size", "from", "to", "subject"])]
+        sort: Option<String>,
+        /// Sort ascending
+        #[arg(long, conflicts_with = "desc")]
+        asc: bool,
+        /// Sort descending (default)
+        #[arg(long)]
+        desc: bool,
+        /// Only report where emails were added or removed since this query state;
+        /// with no value, the state saved by the last identical `mail list`
+        #[arg(long, num_args = 0..=1, conflicts_with = "position")]
+        since_query_state: Option<Option<String>>,
+        /// Only fetch these Email properties, e.g. --fields id,subject,from,receivedAt
+        #[arg(
+            long,
+            value_delimiter = ',',
+            value_name = "PROPERTY",
+            conflicts_with = "since_query_state"
+        )]
+        fields: Vec<String>,
+    },
+    /// Count emails without fetching them
+    Count {
+        /// Mailbox name or id (defaults to all mail)
+        #[arg(short, long)]
+        mailbox: Option<String>,
+        /// Only count unread emails
+        #[arg(long)]
+        unread: bool,
+    },
+    /// Search emails
+    Search {
+        /// Sender address or name contains
+        #[arg(long)]
+        from: Option<String>,
+        /// Recipient address or name contains
+        #[arg(long)]
+        to: Option<String>,
+        /// Subject contains
+        #[arg(long)]
+        subject: Option<String>,
+        /// Full-text search across headers and body
+        #[arg(long)]
+        text: Option<String>,
+        /// Received before this date (RFC 3339, e.g. 2024-01-31T00:00:00Z)
+        #[arg(long)]
+        before: Option<String>,
+        /// Received on or after this date (RFC 3339)
+        #[arg(long)]
+        after: Option<String>,
+        /// Only emails with attachments
+        #[arg(long)]
+        has_attachment: bool,
+        /// Restrict to a mailbox name (e.g. Inbox)
+        #[arg(short, long)]
+        mailbox: Option<String>,
+        /// Match any condition instead of all of them
+        #[arg(long)]
+        any: bool,
+        /// Maximum number of emails to return
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+    /// Read an email
+    Read {
+        /// Email ID
+        id: String,
+        /// Include body content
+        #[arg(long)]
+        body: bool,
+        /// Keep HTML-only bodies as raw HTML instead of converting them to text
+        #[arg(long, requires = "body")]
+        html: bool,
+    },
+    /// Show every email in the conversation containing an email
+    Thread {
+        /// Any email ID in the thread
+        email_id: String,
+    },
+    /// List the attachments of an email
+    Attachments {
+        /// Email ID
+        email_id: String,
+    },
+    /// Save an attachment to disk
+    AttachmentGet {
+        /// Email ID
+        email_id: String,
+        /// Part ID (from `mail attachments`)
+        part_id: String,
+        /// Output file (defaults to the attachment name)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Save the original message as an .eml file
+    Export {
+        /// Email ID
+        email_id: String,
+        /// Output file (defaults to <email-id>.eml)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Show a blob parsed as an email, without importing it
+    Parse {
+        /// Blob ID of an RFC 5322 message (e.g. a message/rfc822 attachment)
+        blob_id: String,
+        /// Properties to return (comma-separated), e.g. subject,headers,bodyStructure
+        #[arg(long, value_delimiter = ',')]
+        properties: Option<Vec<String>>,
+    },
+    /// Delete emails
+    Delete {
+        /// Email IDs
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+        /// Show what would be deleted without deleting
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Send a plain-text email, or one described as JSON with --json
+    Send {
+        /// Recipient address (repeatable)
+        #[arg(long, required_unless_present = "json")]
+        to: Vec<String>,
+        /// CC address (repeatable)
+        #[arg(long)]
+        cc: Vec<String>,
+        /// BCC address (repeatable)
+        #[arg(long)]
+        bcc: Vec<String>,
+        /// Subject line
+        #[arg(short, long, required_unless_present = "json")]
+        subject: Option<String>,
+        /// Message body
+        #[arg(short, long, required_unless_present = "json")]
+        body: Option<String>,
+        /// Read the whole message from a JSON file ("-" for stdin) with fields
+        /// to, cc, bcc, subject, text_body, html_body and attachments (file paths)
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["to", "cc", "bcc", "subject", "body"])]
+        json: Option<String>,
+        /// Schedule delivery for a later time (RFC 3339, e.g. 2024-06-01T09:00:00Z)
+        #[arg(long)]
+        at: Option<String>,
+        /// Sending identity, by id or email (defaults to the primary identity)
+        #[arg(long)]
+        from: Option<String>,
+        /// Allow recipients that are not on the allow list
+        #[arg(long)]
+        allow_new_recipients: bool,
+        /// Show what would be sent without sending
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Reply to an email
+    Reply {
+        /// Email ID to reply to
+        id: String,
+        /// Reply text
+        #[arg(short, long)]
+        body: String,
+        /// Reply to all recipients, not just the sender
+        #[arg(long)]
+        all: bool,
+        /// Allow recipients that are not on the allow list
+        #[arg(long)]
+        allow_new_recipients: bool,
+        /// Show the reply without sending it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Forward an email
+    Forward {
+        /// Email ID to forward
+        id: String,
+        /// Recipient address (repeatable)
+        #[arg(long, required = true)]
+        to: Vec<String>,
+        /// Text placed above the forwarded message
+        #[arg(short, long, default_value = "")]
+        body: String,
+        /// Allow recipients that are not on the allow list
+        #[arg(long)]
+        allow_new_recipients: bool,
+        /// Show the forward without sending it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Cancel a scheduled email before it is sent
+    Unsend {
+        /// Submission ID returned by `mail send --at`
+        submission_id: String,
+    },
+    /// Mark emails as read
+    MarkRead {
+        /// Email IDs
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    /// Mark emails as unread
+    #[command(alias = "mark-unread")]
+    Unread {
+        /// Email IDs
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    /// Flag emails
+    Flag {
+        /// Email IDs
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    /// Remove the flag from emails
+    Unflag {
+        /// Email IDs
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    /// Wait for new mail and print a line for each message as it arrives
+    Watch {
+        /// Also print a line when mailboxes change
+        #[arg(long)]
+        mailboxes: bool,
+    },
+    /// Print the ids of emails created, updated or destroyed since the last sync
+    Sync {
+        /// Forget the saved state and start again from the current one
+        #[arg(long)]
+        reset: bool,
+    },
+    /// Import an .eml file into a mailbox
+    Import {
+        /// Path to the RFC 5322 message
+        file: String,
+        /// Mailbox name to import into (e.g. Archive)
+        #[arg(short, long)]
+        mailbox: String,
+        /// Keyword to set on the email, e.g. $seen (repeatable)
+        #[arg(long)]
+        keyword: Vec<String>,
+        /// Received time to record (RFC 3339); defaults to now
+        #[arg(long, value_parser = parse_rfc3339)]
+        received_at: Option<DateTime<Utc>>,
+        /// Show what would be imported without importing
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Move emails to another mailbox
+    Move {
+        /// Email IDs
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// Target mailbox name (e.g. Archive)
+        #[arg(long)]
+        to: String,
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+        /// Show what would be moved without moving
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Reject the send (exit 3) if the allow list is enforced and a recipient is not on it
+fn ensure_recipients_allowed(
+    opts: &GlobalOpts,
+    client: &FastmailClient,
+    recipients: &[&str],
+    allow_new_recipients: bool,
+) -> Result<()> {
+    let config = opts.load_config()?;
+    if config.safety.require_new_recipient_flag && !allow_new_recipients {
+        let blocked = client.check_recipients_allowed(&opts.whitelist_path()?, recipients)?;
+        if !blocked.is_empty() {
+            let resp = Response::<()>::error(ErrorResponse::safety_rejected(format!(
+                "Recipients not on the allow list: {}. Add them with \
+                 `fastmail config allow-recipient add <email>` or pass \
+                 --allow-new-recipients",
+                blocked.join(", ")
+            )));
+            print_response(&resp)?;
+            std::process::exit(ExitCode::SafetyRejected.code());
+        }
+    }
+    Ok(())
+}
+
+/// Identifies a `mail list` query, so its query state is only reused for the same listing
+fn list_query_key(mailbox: Option<&str>, sort: &Option<Vec<Comparator>>) -> String {
+    json!({ "mailbox": mailbox, "sort": sort }).to_string()
+}
+
+/// File name for a saved attachment: its own name, or one derived from the media type
+fn default_attachment_filename(part: &BodyPart) -> String {
+    if let Some(name) = part.name.as_deref().filter(|n| !n.is_empty()) {
+        // Never let a server-supplied name escape the current directory
+        if let Some(base) = Path::new(name).file_name() {
+            return base.to_string_lossy().into_owned();
+        }
+    }
+
+    let extension = mime_guess::get_mime_extensions_str(&part.type_)
+        .and_then(|exts| exts.first())
+        .unwrap_or(&"bin");
+    format!("attachment-{}.{}", part.part_id, extension)
+}
+
+/// All To/Cc/Bcc addresses of a composed email
+fn composed_recipients(email: &EmailCreate) -> Vec<&str> {
+    [&email.to, &email.cc, &email.bcc]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|a| a.email.as_str())
+        .collect()
+}
+
+/// Parse an EmailComposeSpec from a file, or stdin when `source` is "-"
+fn read_compose_spec(source: &str) -> Result<EmailComposeSpec> {
+    let content = if source == "-" {
+        let mut content = String::new();
+        std::io::stdin().read_to_string(&mut content)?;
+        content
+    } else {
+        std::fs::read_to_string(source)?
+    };
+    let spec: EmailComposeSpec = serde_json::from_str(&content)?;
+    spec.validate()?;
+    Ok(spec)
+}
+
+/// Send a message from `mail send --json`, or describe it under --dry-run.
+/// Attachments are only uploaded for a real send.
+async fn send_spec(
+    opts: &GlobalOpts,
+    client: &FastmailClient,
+    spec: EmailComposeSpec,
+    send_at: Option<DateTime<Utc>>,
+    from: Option<&str>,
+    allow_new_recipients: bool,
+    dry_run: bool,
+) -> Result<()> {
+    ensure_recipients_allowed(opts, client, &spec.recipients(), allow_new_recipients)?;
+    let operation_id = format!("send-{}", spec.recipients().join(","));
+
+    if dry_run {
+        let identity = match from {
+            Some(selector) => Some(client.resolve_identity(selector).await?),
+            None => None,
+        };
+        let resp = Response::ok_with_meta(
+            json!({
+                "operation": "send",
+                "would_send": {
+                    "from": identity.map(|i| i.email),
+                    "message": spec,
+                    "sendAt": send_at
+                }
+            }),
+            Meta {
+                rate_limit: None,
+                dry_run: Some(true),
+                operation_id: Some(operation_id),
+            },
+        );
+        print_response(&resp)?;
+    } else {
+        let email = client.compose_from_spec(&spec).await?;
+        let submission = client.submit_email(email, send_at, from).await?;
+
+        let resp = Response::ok_with_meta(
+            submission,
+            Meta {
+                rate_limit: None,
+                dry_run: Some(false),
+                operation_id: Some(operation_id),
+            },
+        );
+        print_response(&resp)?;
+    }
+    Ok(())
+}
+
+/// Send a composed reply/forward, or describe it under --dry-run
+async fn send_composed(
+    opts: &GlobalOpts,
+    client: &FastmailClient,
+    email: EmailCreate,
+    operation: &str,
+    allow_new_recipients: bool,
+    dry_run: bool,
+) -> Result<()> {
+    ensure_recipients_allowed(
+        opts,
+        client,
+        &composed_recipients(&email),
+        allow_new_recipients,
+    )?;
+    let operation_id = format!("{}-{}", operation, composed_recipients(&email).join(","));
+
+    if dry_run {
+        let resp = Response::ok_with_meta(
+            json!({
+                "operation": operation,
+                "would_send": email
+            }),
+            Meta {
+                rate_limit: None,
+                dry_run: Some(true),
+                operation_id: Some(operation_id),
+            },
+        );
+        print_response(&resp)?;
+    } else {
+        let submission = client.submit_email(email, None, None).await?;
+
+        let resp = Response::ok_with_meta(
+            submission,
+            Meta {
+                rate_limit: None,
+                dry_run: Some(false),
+                operation_id: Some(operation_id),
+            },
+        );
+        print_response(&resp)?;
+    }
+    Ok(())
+}
+
+pub async fn handle_mail(
+    opts: &GlobalOpts,
+    client: &FastmailClient,
+    cmd: MailCommands,
+) -> Result<()> {
+    match cmd {
+        MailCommands::List {
+            mailbox,
+            limit,
+            position,
+            sort,
+            asc,
+            desc,
+            since_query_state,
+            fields,
+        } => {
+            // --asc/--desc alone sets the direction of the default receivedAt sort
+            let sort = (sort.is_some() || asc || desc).then(|| {
+                let property = sort.as_deref().unwrap_or("receivedAt");
+                let comparator = if asc && !desc {
+                    Comparator::new(property)
+                } else {
+                    Comparator::desc(property)
+                };
+                vec![comparator]
+            });
+            let account_id = client.account_id();
+            let query = list_query_key(mailbox.as_deref(), &sort);
+
+            if let Some(since) = since_query_state {
+                let mut sync_state = SyncState::load()?;
+                let since = match since {
+                    Some(state) => state,
+                    None => sync_state
+                        .query_state(account_id, &query)
+                        .map(str::to_string)
+                        .ok_or_else(|| {
+                            anyhow!("No saved query state for this listing; run `mail list` first")
+                        })?,
+                };
+                return match client
+                    .list_email_changes(mailbox.as_deref(), sort, &since)
+                    .await
+                {
+                    Ok(changes) => {
+                        sync_state.set_query_state(
+                            account_id,
+                            &query,
+                            changes.new_query_state.clone(),
+                        );
+                        sync_state.save()?;
+                        let resp = Response::ok_with_rate_limit(changes, client.rate_limit());
+                        print_response(&resp)?;
+                        Ok(())
+                    }
+                    // The server cannot diff from that state; callers must list again
+                    Err(e)
+                        if matches!(
+                            e.downcast_ref::<JmapError>(),
+                            Some(JmapError::CannotCalculateChanges { .. })
+                        ) =>
+                    {
+                        let resp = Response::ok_with_rate_limit(
+                            json!({"resync_required": true}),
+                            client.rate_limit(),
+                        );
+                        print_response(&resp)?;
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                };
+            }
+
+            let (page, emails) = client
+                .list_emails(
+                    mailbox.as_deref(),
+                    sort,
+                    position,
+                    limit,
+                    Some(fields).filter(|f| !f.is_empty()),
+                )
+                .await?;
+            // Saved for a later --since-query-state; the listing itself has succeeded
+            if let Some(state) = &page.query_state {
+                let saved = SyncState::load().and_then(|mut sync_state| {
+                    sync_state.set_query_state(account_id, &query, state.clone());
+                    sync_state.save()
+                });
+                if let Err(e) = saved {
+                    eprintln!("Warning: couldn't save the query state: {}", e);
+                }
+            }
+
+            let resp = Response::ok_with_rate_limit(
+                EmailList {
+                    emails,
+                    position: page.position,
+                    total: page.total,
+                    query_state: page.query_state,
+                },
+                client.rate_limit(),
+            );
+            print_formatted(&resp)?;
+            Ok(())
+        }
+        MailCommands::Count { mailbox, unread } => {
+            let total = client.count_emails(mailbox.as_deref(), unread).await?;
+            let resp = Response::ok_with_rate_limit(
+                json!({ "mailbox": mailbox, "unread": unread, "total": total }),
+                client.rate_limit(),
+            );
+            print_response(&resp)?;
+            Ok(())
+        }
+        MailCommands::Search {
+            from,
+            to,
+            subject,
+            text,
+            before,
+            after,
+            has_attachment,
+            mailbox,
+            any,
+            limit,
+        } => {
+            let mut conditions: Vec<Filter<EmailFilterCondition>> = Vec::new();
+            let mut push = |condition: EmailFilterCondition| conditions.push(condition.into());
+
+            if let Some(from) = from {
+                push(EmailFilterCondition {
+                    from: Some(from),
+                    ..Default::default()
+                });
+            }
+            if let Some(to) = to {
+                push(EmailFilterCondition {
+                    to: Some(to),
+                    ..Default::default()
+                });
+            }
+            if let Some(subject) = subject {
+                push(EmailFilterCondition {
+                    subject: Some(subject),
+                    ..Default::default()
+                });
+            }
+            let full_text = text.is_some();
+            if let Some(text) = text {
+                push(EmailFilterCondition {
+                    text: Some(text),
+                    ..Default::default()
+                });
+            }
+            if let Some(before) = before {
+                push(EmailFilterCondition {
+                    before: Some(before),
+                    ..Default::default()
+                });
+            }
+            if let Some(after) = after {
+                push(EmailFilterCondition {
+                    after: Some(after),
+                    ..Default::default()
+                });
+            }
+            if has_attachment {
+                push(EmailFilterCondition {
+                    has_attachment: Some(true),
+                    ..Default::default()
+                });
+            }
+
+            let mut filter = match conditions.len() {
+                0 => None,
+                1 => conditions.pop(),
+                _ if any => Some(Filter::or(conditions)),
+                _ => Some(Filter::and(conditions)),
+            };
+
+            // The mailbox always narrows the search, even with --any
+            if let Some(name) = mailbox {
+                let in_mailbox: Filter<_> = EmailFilterCondition {
+                    in_mailbox: Some(client.resolve_mailbox_id(&name).await?),
+                    ..Default::default()
+                }
+                .into();
+                filter = Some(match filter {
+                    Some(f) => Filter::and(vec![in_mailbox, f]),
+                    None => in_mailbox,
+                });
+            }
+
+            // No flags searches everything, exactly like an unfiltered list
+            let filter = filter.unwrap_or_else(|| EmailFilterCondition::default().into());
+            let emails = client.search_emails(filter.clone(), limit).await?;
+
+            // Text searches also show where each email matched
+            if full_text {
+                let ids: Vec<String> = emails.iter().map(|e| e.id.clone()).collect();
+                let snippets = client.search_snippets(&ids, &filter).await?;
+                let resp = Response::ok(SearchResults { emails, snippets });
+                print_formatted(&resp)?;
+                return Ok(());
+            }
+
+            let resp = Response::ok(emails);
+            print_formatted(&resp)?;
+            Ok(())
+        }
+        MailCommands::Read { id, body, html } => {
+            let email = if body {
+                let mut email = client.get_email_with_body(&id).await?;
+                if !html {
+                    render_html_bodies(&mut email);
+                }
+                email
+            } else {
+                client.get_email(&id).await?
+            };
+
+            let resp = Response::ok(email);
+            print_response(&resp)?;
+            Ok(())
+        }
+        MailCommands::Thread { email_id } => {
+            let (thread, emails) = client.get_thread(&email_id).await?;
+
+            let resp = Response::ok_with_rate_limit(
+                ThreadView {
+                    thread_id: thread.id,
+                    emails,
+                },
+                client.rate_limit(),
+            );
+            print_formatted(&resp)?;
+            Ok(())
+        }
+        MailCommands::Attachments { email_id } => {
+            let attachments = client.list_attachments(&email_id).await?;
+
+            let resp = Response::ok(
+                attachments
+                    .iter()
+                    .map(|a| {
+                        json!({
+                            "partId": a.part_id,
+                            "name": a.name,
+                            "type": a.type_,
+                            "size": a.size
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            print_response(&resp)?;
+            Ok(())
+        }
+        MailCommands::AttachmentGet {
+            email_id,
+            part_id,
+            output,
+        } => {
+            let output = match output {
+                Some(path) => path,
+                None => {
+                    let part = client
+                        .list_attachments(&email_id)
+                        .await?
+                        .into_iter()
+                        .find(|p| p.part_id == part_id)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "Attachment {} not found in email {}",
+                                part_id,
+                                email_id
+                            )
+                        })?;
+                    default_attachment_filename(&part)
+                }
+            };
+
+            let size = client
+                .download_attachment(&email_id, &part_id, Path::new(&output))
+                .await?;
+
+            let resp = Response::ok(json!({
+                "emailId": email_id,
+                "partId": part_id,
+                "savedTo": output,
+                "size": size
+            }));
+            print_response(&resp)?;
+            Ok(())
+        }
+        MailCommands::Export { email_id, output } => {
+            let output = output.unwrap_or_else(|| format!("{}.eml", email_id));
+            let size = client.export_email(&email_id, Path::new(&output)).await?;
+
+            let resp = Response::ok(json!({
+                "emailId": email_id,
+                "savedTo": output,
+                "size": size
+            }));
+            print_response(&resp)?;
+            Ok(())
+        }
+        MailCommands::Parse {
+            blob_id,
+            properties,
+        } => {
+            let email = client.parse_email(&blob_id, properties).await?;
+
+            let resp = Response::ok_with_rate_limit(email, client.rate_limit());
+            print_response(&resp)?;
+            Ok(())
+        }
+        MailCommands::Delete {
+            ids,
+            force,
+            dry_run,
+        } => {
+            if !dry_run {
+                opts.confirm_destructive(&format!("Delete {} email(s)?", ids.len()), force)?;
+            }
+
+            if dry_run {
+                let would_delete = client.get_emails(&ids).await?;
+
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "delete",
+                        "would_delete": would_delete
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(true),
+                        operation_id: Some(format!("delete-{}", ids.join(","))),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            } else {
+                let result = client.delete_emails(ids.clone()).await?;
+
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "delete",
+                        "deleted": result.destroyed,
+                        "notDestroyed": result.not_destroyed
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(false),
+                        operation_id: Some(format!("delete-{}", ids.join(","))),
+                    },
+                );
+                print_response(&resp)?;
+                exit_if_not_destroyed(&result);
+                Ok(())
+            }
+        }
+        MailCommands::Import {
+            file,
+            mailbox,
+            keyword,
+            received_at,
+            dry_run,
+        } => {
+            let path = Path::new(&file);
+            if !path.is_file() {
+                let resp = Response::<()>::error(ErrorResponse::validation_failed(format!(
+                    "No such file: {}",
+                    file
+                )));
+                print_response(&resp)?;
+                std::process::exit(ExitCode::PermanentError.code());
+            }
+
+            if dry_run {
+                // Resolve the target so a missing mailbox fails the dry run too
+                let mailbox_id = client.resolve_mailbox_id(&mailbox).await?;
+
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "import",
+                        "would_import": file,
+                        "mailbox": mailbox,
+                        "mailboxId": mailbox_id,
+                        "keywords": keyword,
+                        "receivedAt": received_at
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(true),
+                        operation_id: Some(format!("import-{}", file)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            } else {
+                let email = client
+                    .import_email(path, &mailbox, &keyword, received_at)
+                    .await?;
+
+                let resp = Response::ok_with_meta(
+                    email,
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(false),
+                        operation_id: Some(format!("import-{}", file)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            }
+        }
+        MailCommands::Move {
+            ids,
+            to,
+            force,
+            dry_run,
+        } => {
+            if dry_run {
+                // Resolve the target so a missing mailbox fails the dry run too
+                let mailbox_id = client.resolve_mailbox_id(&to).await?;
+
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "move",
+                        "would_move": ids,
+                        "to": to,
+                        "mailboxId": mailbox_id
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(true),
+                        operation_id: Some(format!("move-{}", ids.join(","))),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            } else {
+                opts.confirm_destructive(
+                    &format!("Move {} email(s) to '{}'?", ids.len(), to),
+                    force,
+                )?;
+                let result = client.move_emails(&ids, &to).await?;
+
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "move",
+                        "moved": result.updated,
+                        "notUpdated": result.not_updated,
+                        "to": to
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(false),
+                        operation_id: Some(format!("move-{}", ids.join(","))),
+                    },
+                );
+                print_response(&resp)?;
+                exit_if_not_updated(&result);
+                Ok(())
+            }
+        }
+        MailCommands::Send {
+            to,
+            cc,
+            bcc,
+            subject,
+            body,
+            at,
+            from,
+            json: spec_source,
+            allow_new_recipients,
+            dry_run,
+        } => {
+            let send_at = match at.as_deref().map(DateTime::parse_from_rfc3339) {
+                Some(Ok(at)) => Some(at.with_timezone(&Utc)),
+                Some(Err(e)) => {
+                    let resp = Response::<()>::error(ErrorResponse::validation_failed(format!(
+                        "Invalid --at time: {}",
+                        e
+                    )));
+                    print_response(&resp)?;
+                    std::process::exit(ExitCode::PermanentError.code());
+                }
+                None => None,
+            };
+
+            if let Some(source) = spec_source {
+                let spec = match read_compose_spec(&source) {
+                    Ok(spec) => spec,
+                    Err(e) => {
+                        let resp = Response::<()>::error(ErrorResponse::validation_failed(
+                            format!("Invalid --json message: {}", e),
+                        ));
+                        print_response(&resp)?;
+                        std::process::exit(ExitCode::PermanentError.code());
+                    }
+                };
+                return send_spec(
+                    opts,
+                    client,
+                    spec,
+                    send_at,
+                    from.as_deref(),
+                    allow_new_recipients,
+                    dry_run,
+                )
+                .await;
+            }
+            // clap requires both unless --json was given
+            let subject = subject.unwrap_or_default();
+            let body = body.unwrap_or_default();
+
+            let recipients: Vec<&str> = to
+                .iter()
+                .chain(&cc)
+                .chain(&bcc)
+                .map(String::as_str)
+                .collect();
+
+            ensure_recipients_allowed(opts, client, &recipients, allow_new_recipients)?;
+
+            if dry_run {
+                let identity = match from.as_deref() {
+                    Some(selector) => Some(client.resolve_identity(selector).await?),
+                    None => None,
+                };
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "send",
+                        "would_send": {
+                            "from": identity.map(|i| i.email),
+                            "to": to,
+                            "cc": cc,
+                            "bcc": bcc,
+                            "subject": subject,
+                            "sendAt": send_at
+                        }
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(true),
+                        operation_id: Some(format!("send-{}", to.join(","))),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            } else {
+                let submission = client
+                    .send_email(&to, &cc, &bcc, &subject, &body, send_at, from.as_deref())
+                    .await?;
+
+                let resp = Response::ok_with_meta(
+                    submission,
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(false),
+                        operation_id: Some(format!("send-{}", to.join(","))),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            }
+        }
+        MailCommands::Reply {
+            id,
+            body,
+            all,
+            allow_new_recipients,
+            dry_run,
+        } => {
+            let email = client.compose_reply(&id, &body, all).await?;
+            send_composed(opts, client, email, "reply", allow_new_recipients, dry_run).await
+        }
+        MailCommands::Forward {
+            id,
+            to,
+            body,
+            allow_new_recipients,
+            dry_run,
+        } => {
+            let email = client.compose_forward(&id, &to, &body).await?;
+            send_composed(
+                opts,
+                client,
+                email,
+                "forward",
+                allow_new_recipients,
+                dry_run,
+            )
+            .await
+        }
+        MailCommands::Unsend { submission_id } => {
+            client.cancel_submission(&submission_id).await?;
+
+            let resp = Response::ok(json!({
+                "submissionId": submission_id,
+                "undoStatus": "canceled"
+            }));
+            print_response(&resp)?;
+            Ok(())
+        }
+        MailCommands::MarkRead { ids } => {
+            let result = client.mark_read(&ids, true).await?;
+
+            let resp = Response::ok(json!({
+                "updated": result.updated,
+                "notUpdated": result.not_updated,
+                "seen": true
+            }));
+            print_response(&resp)?;
+            exit_if_not_updated(&result);
+            Ok(())
+        }
+        MailCommands::Unread { ids } => {
+            let result = client.mark_read(&ids, false).await?;
+
+            let resp = Response::ok(json!({
+                "updated": result.updated,
+                "notUpdated": result.not_updated,
+                "seen": false
+            }));
+            print_response(&resp)?;
+            exit_if_not_updated(&result);
+            Ok(())
+        }
+        MailCommands::Flag { ids } => {
+            let result = client.mark_flagged(&ids, true).await?;
+
+            let resp = Response::ok(json!({
+                "updated": result.updated,
+                "notUpdated": result.not_updated,
+                "flagged": true
+            }));
+            print_response(&resp)?;
+            exit_if_not_updated(&result);
+            Ok(())
+        }
+        MailCommands::Unflag { ids } => {
+            let result = client.mark_flagged(&ids, false).await?;
+
+            let resp = Response::ok(json!({
+                "updated": result.updated,
+                "notUpdated": result.not_updated,
+                "flagged": false
+            }));
+            print_response(&resp)?;
+            exit_if_not_updated(&result);
+            Ok(())
+        }
+        MailCommands::Watch { mailboxes } => {
+            let types: &[&str] = if mailboxes {
+                &["Email", "Mailbox"]
+            } else {
+                &["Email"]
+            };
+            let mut events = Box::pin(client.watch(types)?);
+            let mut email_state: Option<String> = None;
+
+            while let Some(event) = events.next().await {
+                let event = event?;
+
+                if let Some(state) = event.state(client.account_id(), "Mailbox") {
+                    let resp = Response::ok(json!({"type": "Mailbox", "state": state}));
+                    print_response(&resp)?;
+                }
+
+                let Some(state) = event.state(client.account_id(), "Email") else {
+                    continue;
+                };
+                // The server reports the current state on connect; that is the baseline
+                let Some(previous) = email_state.replace(state.to_string()) else {
+                    continue;
+                };
+
+                let (emails, new_state) = client.new_emails_since(&previous).await?;
+                email_state = Some(new_state);
+                for email in emails {
+                    let resp = Response::ok(json!({
+                        "type": "Email",
+                        "id": email.id,
+                        "from": email.from,
+                        "subject": email.subject,
+                        "receivedAt": email.received_at
+                    }));
+                    print_response(&resp)?;
+                }
+            }
+            Ok(())
+        }
+        MailCommands::Sync { reset } => {
+            let account_id = client.account_id();
+            let mut sync_state = SyncState::load()?;
+            if reset {
+                sync_state.clear_email_state(account_id);
+            }
+
+            // The first run only records where later runs start from
+            let Some(since) = sync_state.email_state(account_id).map(str::to_string) else {
+                let state = client.email_state().await?;
+                sync_state.set_email_state(account_id, state.clone());
+                sync_state.save()?;
+                let resp = Response::ok_with_rate_limit(
+                    json!({"baseline": true, "new_state": state}),
+                    client.rate_limit(),
+                );
+                print_response(&resp)?;
+                return Ok(());
+            };
+
+            match client.email_changes_since(&since).await {
+                Ok(changes) => {
+                    sync_state.set_email_state(account_id, changes.new_state.clone());
+                    sync_state.save()?;
+                    let resp = Response::ok_with_rate_limit(changes, client.rate_limit());
+                    print_response(&resp)?;
+                    Ok(())
+                }
+                // The saved state is too old for the server; callers must re-list everything
+                Err(e)
+                    if matches!(
+                        e.downcast_ref::<JmapError>(),
+                        Some(JmapError::CannotCalculateChanges { .. })
+                    ) =>
+                {
+                    let state = client.email_state().await?;
+                    sync_state.set_email_state(account_id, state.clone());
+                    sync_state.save()?;
+                    let resp = Response::ok_with_rate_limit(
+                        json!({"resync_required": true, "new_state": state}),
+                        client.rate_limit(),
+                    );
+                    print_response(&resp)?;
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part(part_id: &str, type_: &str, name: Option<&str>) -> BodyPart {
+        BodyPart {
+            part_id: part_id.to_string(),
+            type_: type_.to_string(),
+            name: name.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_default_attachment_filename() {
+        assert_eq!(
+            default_attachment_filename(&part("2", "application/pdf", Some("report.pdf"))),
+            "report.pdf"
+        );
+        // Directories in a server-supplied name are dropped
+        assert_eq!(
+            default_attachment_filename(&part("2", "text/plain", Some("../../etc/passwd"))),
+            "passwd"
+        );
+        // Without a usable name, the media type picks the extension
+        let unnamed = default_attachment_filename(&part("3", "application/pdf", None));
+        assert_eq!(unnamed, "attachment-3.pdf");
+        assert_eq!(
+            default_attachment_filename(&part("4", "application/pdf", Some(""))),
+            "attachment-4.pdf"
+        );
+        assert_eq!(
+            default_attachment_filename(&part("5", "application/x-unknown", Some(".."))),
+            "attachment-5.bin"
+        );
+    }
+}
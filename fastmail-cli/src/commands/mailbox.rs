@@ -0,0 +1,311 @@
+// fastmail-cli/src/commands/mailbox.rs
+use crate::output::{print_formatted, print_response, Meta, Response};
+use crate::utils::{exit_if_not_destroyed, GlobalOpts};
+use anyhow::Result;
+use clap::Subcommand;
+use fastmail_client::FastmailClient;
+use serde_json::json;
+
+/// Most email ids `mailbox empty --dry-run` lists; the count covers the rest
+const DRY_RUN_ID_LIMIT: usize = 100;
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum MailboxCommands {
+    /// List mailboxes
+    List {
+        /// Filter by name (case-insensitive substring)
+        #[arg(short, long)]
+        filter: Option<String>,
+        /// Only mailboxes with this role (inbox, archive, drafts, sent, trash, junk, ...)
+        #[arg(long)]
+        role: Option<String>,
+    },
+    /// Show mailboxes as an indented hierarchy
+    Tree,
+    /// Show one mailbox, including your rights on it
+    Get {
+        /// Mailbox ID or name
+        mailbox: String,
+    },
+    /// Create a mailbox
+    Create {
+        /// Mailbox name
+        name: String,
+        /// Show what would be created without creating
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Rename a mailbox
+    Rename {
+        /// Mailbox ID
+        id: String,
+        /// New mailbox name
+        name: String,
+        /// Show what would be renamed without renaming
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Move a mailbox under another mailbox, or to the top level
+    Move {
+        /// Mailbox ID
+        id: String,
+        /// ID of the new parent mailbox
+        #[arg(long, required_unless_present = "root", conflicts_with = "root")]
+        parent: Option<String>,
+        /// Move to the top level
+        #[arg(long)]
+        root: bool,
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+        /// Show what would be moved without moving
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Delete a mailbox
+    Delete {
+        /// Mailbox ID
+        id: String,
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+        /// Show what would be deleted without deleting
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Delete every email in a mailbox (e.g. Trash or Spam)
+    Empty {
+        /// Mailbox ID or name
+        mailbox: String,
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+        /// Count the emails that would be deleted without deleting
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+pub async fn handle_mailbox(
+    opts: &GlobalOpts,
+    client: &FastmailClient,
+    cmd: MailboxCommands,
+) -> Result<()> {
+    match cmd {
+        MailboxCommands::List { filter, role } => {
+            let mailboxes = client
+                .list_mailboxes(filter.as_deref(), role.as_deref())
+                .await?;
+
+            let resp = Response::ok_with_rate_limit(mailboxes, client.rate_limit());
+            print_formatted(&resp)?;
+            Ok(())
+        }
+        MailboxCommands::Tree => {
+            let tree = client.mailbox_tree().await?;
+
+            let resp = Response::ok_with_rate_limit(tree, client.rate_limit());
+            print_formatted(&resp)?;
+            Ok(())
+        }
+        MailboxCommands::Get { mailbox } => {
+            let mailbox = client.find_mailbox(&mailbox).await?;
+
+            let resp = Response::ok_with_rate_limit(mailbox, client.rate_limit());
+            print_formatted(&resp)?;
+            Ok(())
+        }
+        MailboxCommands::Create { name, dry_run } => {
+            if dry_run {
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "create_mailbox",
+                        "would_create": name
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(true),
+                        operation_id: Some(format!("create-mailbox-{}", name)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            } else {
+                let mailbox = client.create_mailbox(&name).await?;
+
+                let resp = Response::ok_with_meta(
+                    mailbox,
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(false),
+                        operation_id: Some(format!("create-mailbox-{}", name)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            }
+        }
+        MailboxCommands::Rename { id, name, dry_run } => {
+            if dry_run {
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "rename_mailbox",
+                        "would_rename": id,
+                        "name": name
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(true),
+                        operation_id: Some(format!("rename-mailbox-{}", id)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            } else {
+                let mailbox = client.rename_mailbox(&id, &name).await?;
+
+                let resp = Response::ok_with_meta(
+                    mailbox,
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(false),
+                        operation_id: Some(format!("rename-mailbox-{}", id)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            }
+        }
+        MailboxCommands::Move {
+            id,
+            parent,
+            root: _,
+            force,
+            dry_run,
+        } => {
+            if dry_run {
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "move_mailbox",
+                        "would_move": id,
+                        "parent_id": parent
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(true),
+                        operation_id: Some(format!("move-mailbox-{}", id)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            } else {
+                let target = parent
+                    .as_ref()
+                    .map_or("the top level".to_string(), |p| format!("'{}'", p));
+                opts.confirm_destructive(&format!("Move mailbox '{}' to {}?", id, target), force)?;
+                let mailbox = client.move_mailbox(&id, parent.as_deref()).await?;
+
+                let resp = Response::ok_with_meta(
+                    mailbox,
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(false),
+                        operation_id: Some(format!("move-mailbox-{}", id)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            }
+        }
+        MailboxCommands::Delete { id, force, dry_run } => {
+            if !dry_run {
+                opts.confirm_destructive(&format!("Delete mailbox '{}'?", id), force)?;
+            }
+
+            if dry_run {
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "delete",
+                        "would_delete": id
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(true),
+                        operation_id: Some(format!("delete-mailbox-{}", id)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            } else {
+                client.delete_mailbox(&id).await?;
+
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "delete",
+                        "deleted": id
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(false),
+                        operation_id: Some(format!("delete-mailbox-{}", id)),
+                    },
+                );
+                print_response(&resp)?;
+                Ok(())
+            }
+        }
+        MailboxCommands::Empty {
+            mailbox,
+            force,
+            dry_run,
+        } => {
+            let mailbox = client.find_mailbox(&mailbox).await?;
+            let ids = client.mailbox_email_ids(&mailbox.id).await?;
+
+            if dry_run {
+                let resp = Response::ok_with_meta(
+                    json!({
+                        "operation": "empty_mailbox",
+                        "mailbox": mailbox.name,
+                        "mailboxId": mailbox.id,
+                        "count": ids.len(),
+                        "would_delete": &ids[..ids.len().min(DRY_RUN_ID_LIMIT)]
+                    }),
+                    Meta {
+                        rate_limit: None,
+                        dry_run: Some(true),
+                        operation_id: Some(format!("empty-mailbox-{}", mailbox.id)),
+                    },
+                );
+                print_response(&resp)?;
+                return Ok(());
+            }
+
+            if !ids.is_empty() {
+                opts.confirm_destructive(
+                    &format!("Delete all {} email(s) in '{}'?", ids.len(), mailbox.name),
+                    force,
+                )?;
+            }
+            let result = client.delete_emails(ids).await?;
+
+            let resp = Response::ok_with_meta(
+                json!({
+                    "operation": "empty_mailbox",
+                    "mailbox": mailbox.name,
+                    "mailboxId": mailbox.id,
+                    "deleted": result.destroyed,
+                    "notDestroyed": result.not_destroyed
+                }),
+                Meta {
+                    rate_limit: None,
+                    dry_run: Some(false),
+                    operation_id: Some(format!("empty-mailbox-{}", mailbox.id)),
+                },
+            );
+            print_response(&resp)?;
+            exit_if_not_destroyed(&result);
+            Ok(())
+        }
+    }
+}
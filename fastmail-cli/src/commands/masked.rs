@@ -0,0 +1,177 @@
+// fastmail-cli/src/commands/masked.rs
+use crate::output::{print_formatted, print_response, ErrorResponse, ExitCode, Response};
+use crate::utils::GlobalOpts;
+use anyhow::Result;
+use clap::Subcommand;
+use fastmail_client::{FastmailClient, MaskedEmailState};
+use serde_json::json;
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum MaskedCommands {
+    /// List masked emails
+    List {
+        /// Filter by domain
+        #[arg(short, long)]
+        filter: Option<String>,
+        /// Filter by state (pending, enabled, disabled, deleted)
+        #[arg(short, long)]
+        state: Option<String>,
+    },
+    /// Create a masked email
+    Create {
+        /// Domain the address is for (e.g. https://example.com)
+        domain: String,
+        /// Description
+        #[arg(short, long)]
+        description: Option<String>,
+        /// Email prefix
+        #[arg(short, long)]
+        prefix: Option<String>,
+    },
+    /// Change a masked email's description or domain
+    Update {
+        /// Masked email ID or address
+        id: String,
+        /// New description
+        #[arg(short, long)]
+        description: Option<String>,
+        /// New domain the address is for
+        #[arg(long)]
+        domain: Option<String>,
+    },
+    /// Enable a pending masked email
+    Confirm {
+        /// Masked email ID or address
+        id: String,
+    },
+    /// Enable a masked email
+    Enable {
+        /// Masked email ID or address
+        id: String,
+    },
+    /// Disable a masked email
+    Disable {
+        /// Masked email ID or address
+        id: String,
+    },
+    /// Delete a masked email
+    Delete {
+        /// Masked email ID or address
+        id: String,
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+fn parse_state(state: &str) -> Result<MaskedEmailState> {
+    match state {
+        "pending" => Ok(MaskedEmailState::Pending),
+        "enabled" => Ok(MaskedEmailState::Enabled),
+        "disabled" => Ok(MaskedEmailState::Disabled),
+        "deleted" => Ok(MaskedEmailState::Deleted),
+        _ => Err(anyhow::anyhow!("Invalid state: {}", state)),
+    }
+}
+
+pub async fn handle_masked(
+    opts: &GlobalOpts,
+    client: &FastmailClient,
+    cmd: MaskedCommands,
+) -> Result<()> {
+    match cmd {
+        MaskedCommands::List { filter, state } => {
+            let mut emails = client.list_masked_emails().await?;
+
+            if let Some(domain) = filter {
+                emails.retain(|e| e.for_domain.contains(&domain));
+            }
+            if let Some(state) = state {
+                let state = parse_state(&state)?;
+                emails.retain(|e| e.state == state);
+            }
+
+            let resp = Response::ok(emails);
+            print_formatted(&resp)?;
+            Ok(())
+        }
+        MaskedCommands::Create {
+            domain,
+            description,
+            prefix,
+        } => {
+            let email = client
+                .create_masked_email(
+                    &domain,
+                    description.as_deref().unwrap_or(""),
+                    prefix.as_deref(),
+                )
+                .await?;
+
+            let resp = Response::ok(email);
+            print_response(&resp)?;
+            Ok(())
+        }
+        MaskedCommands::Update {
+            id,
+            description,
+            domain,
+        } => {
+            if description.is_none() && domain.is_none() {
+                let resp = Response::<()>::error(ErrorResponse::validation_failed(
+                    "Specify --description and/or --domain".to_string(),
+                ));
+                print_response(&resp)?;
+                std::process::exit(ExitCode::PermanentError.code());
+            }
+
+            client
+                .update_masked_email(&id, description.as_deref(), domain.as_deref())
+                .await?;
+
+            let resp = Response::ok(json!({
+                "id": id,
+                "description": description,
+                "forDomain": domain
+            }));
+            print_response(&resp)?;
+            Ok(())
+        }
+        MaskedCommands::Confirm { id } => {
+            let id = client.confirm_masked_email(&id).await?;
+
+            let resp = Response::ok(json!({"id": id, "state": "enabled"}));
+            print_response(&resp)?;
+            Ok(())
+        }
+        MaskedCommands::Enable { id } => {
+            client
+                .set_masked_email_state(&id, MaskedEmailState::Enabled)
+                .await?;
+
+            let resp = Response::ok(json!({"id": id, "state": "enabled"}));
+            print_response(&resp)?;
+            Ok(())
+        }
+        MaskedCommands::Disable { id } => {
+            client
+                .set_masked_email_state(&id, MaskedEmailState::Disabled)
+                .await?;
+
+            let resp = Response::ok(json!({"id": id, "state": "disabled"}));
+            print_response(&resp)?;
+            Ok(())
+        }
+        MaskedCommands::Delete { id, force } => {
+            opts.confirm_destructive(&format!("Delete masked email '{}'?", id), force)?;
+
+            client
+                .set_masked_email_state(&id, MaskedEmailState::Deleted)
+                .await?;
+
+            let resp = Response::ok(json!({"id": id, "state": "deleted"}));
+            print_response(&resp)?;
+            Ok(())
+        }
+    }
+}
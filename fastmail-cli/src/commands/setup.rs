@@ -1,13 +1,15 @@
 // fastmail-cli/src/commands/setup.rs
+use crate::keychain;
+use crate::utils::GlobalOpts;
 use anyhow::Result;
-use dialoguer::Password;
-use fastmail_client::{Config, DavEndpoints};
+use dialoguer::{Confirm, Password};
+use fastmail_client::{redact, AccountProfile, Config, DavEndpoints};
 
 /// Exit code type
 pub type SetupExitCode = i32;
 
 /// Run the interactive setup command
-pub async fn run_setup() -> Result<SetupExitCode> {
+pub async fn run_setup(opts: &GlobalOpts) -> Result<SetupExitCode> {
     println!("Fastmail CLI Setup");
     println!();
 
@@ -24,21 +26,39 @@ pub async fn run_setup() -> Result<SetupExitCode> {
     println!();
     println!("Validating credentials...");
 
-    // Validate token by trying to create a client
-    let validation_result = fastmail_client::FastmailClient::new(token.clone()).await;
+    // Validate token by trying to create a client, against the configured server
+    let session_url = Config::load_from(opts.config_path())
+        .ok()
+        .and_then(|config| config.session_url);
+    let validation_result = match &session_url {
+        Some(url) => {
+            fastmail_client::FastmailClient::new_with_session_url(token.clone(), url).await
+        }
+        None => fastmail_client::FastmailClient::new(token.clone()).await,
+    };
+    // Get the email and primary mail account from the session
+    let validation_result = match validation_result {
+        Ok(client) => client
+            .account_email()
+            .await
+            .map(|email| (email, client.account_id().to_string())),
+        Err(e) => Err(e),
+    };
 
     match validation_result {
-        Ok(client) => {
-            // Get the email from the session
-            let email = client.account_email().to_string();
-
+        Ok((email, account_id)) => {
             println!();
-            let config = Config {
-                token,
-                account: fastmail_client::AccountConfig { email: Some(email) },
-                dav_endpoints: Some(DavEndpoints::default()),
-                ..Default::default()
+            let account = opts.selected_account();
+            // A token kept in the keychain is left out of config.toml
+            let token = match save_to_keychain(account, &token)? {
+                true => None,
+                false => Some(token),
             };
+            if let Some(name) = account {
+                return save_account(opts, name, email, token);
+            }
+            let mut config = opts.load_config_file()?;
+            store_login(&mut config, token, email, account_id);
 
             // Save config
             if let Err(e) = config.save() {
@@ -53,7 +73,7 @@ pub async fn run_setup() -> Result<SetupExitCode> {
             Ok(0)
         }
         Err(e) => {
-            eprintln!("Error: {}", e);
+            eprintln!("Error: {}", redact(&e.to_string(), &[&token]));
             eprintln!();
             eprintln!("Visit https://app.fastmail.com/settings/security/integrations");
             eprintln!("to create an API token.");
@@ -61,3 +81,100 @@ pub async fn run_setup() -> Result<SetupExitCode> {
         }
     }
 }
+
+/// Set the top-level login, keeping other accounts, timeouts and the like
+fn store_login(config: &mut Config, token: Option<String>, email: String, account_id: String) {
+    config.token = token.unwrap_or_default();
+    config.account.email = Some(email);
+    config.account_id = Some(account_id);
+    config
+        .dav_endpoints
+        .get_or_insert_with(DavEndpoints::default);
+}
+
+/// Offer to keep the token in the OS keychain, returning whether it was saved there
+fn save_to_keychain(account: Option<&str>, token: &str) -> Result<bool> {
+    if !keychain::available() {
+        return Ok(false);
+    }
+    let wanted = Confirm::new()
+        .with_prompt("Save the token in the OS keychain instead of config.toml?")
+        .default(true)
+        .interact()?;
+    if !wanted {
+        return Ok(false);
+    }
+    match keychain::store_token(account, token) {
+        Ok(()) => {
+            println!("Token saved in the keychain.");
+            Ok(true)
+        }
+        Err(e) => {
+            eprintln!("Couldn't use the keychain ({}); saving to config.toml", e);
+            Ok(false)
+        }
+    }
+}
+
+/// Store the login as `[accounts.<name>]`, keeping the rest of the config
+fn save_account(
+    opts: &GlobalOpts,
+    name: &str,
+    email: String,
+    token: Option<String>,
+) -> Result<SetupExitCode> {
+    let mut config = opts.load_config_file()?;
+    let profile = config.accounts.entry(name.to_string()).or_default();
+    *profile = AccountProfile {
+        email: Some(email),
+        token,
+        dav_password: profile.dav_password.take(),
+    };
+    config
+        .default_account
+        .get_or_insert_with(|| name.to_string());
+
+    if let Err(e) = config.save() {
+        eprintln!("Error: Couldn't write config file: {}", e);
+        return Ok(2);
+    }
+
+    println!("Credentials saved as account '{}'!", name);
+    println!();
+    println!("Try: fastmail --account {} mail list", name);
+
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_login_keeps_the_rest_of_the_config() {
+        let mut config = Config {
+            token: "old".to_string(),
+            default_account: Some("work".to_string()),
+            dav_password: Some("app-password".to_string()),
+            timeout: Some(90),
+            accounts: [("work".to_string(), AccountProfile::default())].into(),
+            ..Default::default()
+        };
+
+        store_login(
+            &mut config,
+            None,
+            "me@fastmail.com".to_string(),
+            "u1".to_string(),
+        );
+
+        assert_eq!(config.token, "");
+        assert_eq!(config.account.email.as_deref(), Some("me@fastmail.com"));
+        assert_eq!(config.account_id.as_deref(), Some("u1"));
+        assert_eq!(config.default_account.as_deref(), Some("work"));
+        assert_eq!(config.dav_password.as_deref(), Some("app-password"));
+        assert!(config.accounts.contains_key("work"));
+        assert_eq!(config.timeout, Some(90));
+        assert!(config.dav_endpoints.is_some());
+    }
+}
@@ -0,0 +1,97 @@
+// fastmail-cli/src/commands/vacation.rs
+use crate::output::{print_response, ErrorResponse, ExitCode, Response};
+use crate::utils::parse_rfc3339;
+use anyhow::Result;
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::Subcommand;
+use fastmail_client::FastmailClient;
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum VacationCommands {
+    /// Show the vacation response
+    Show,
+    /// Turn on the vacation response
+    Enable {
+        /// Subject of the automatic reply
+        #[arg(long)]
+        subject: Option<String>,
+        /// Plain-text body of the automatic reply
+        #[arg(long)]
+        text: Option<String>,
+        /// Start replying at this time (RFC 3339, e.g. 2024-07-01T00:00:00Z);
+        /// without it, replies start now
+        #[arg(long, value_parser = parse_rfc3339)]
+        from: Option<DateTime<Utc>>,
+        /// Stop replying at this time (RFC 3339); without it, replies continue
+        /// until disabled
+        #[arg(long, value_parser = parse_rfc3339)]
+        to: Option<DateTime<Utc>>,
+    },
+    /// Turn off the vacation response, keeping its message
+    Disable,
+}
+
+/// Format as a JMAP UTCDate (RFC 8620 §1.4)
+fn utc_date(dt: &DateTime<Utc>) -> String {
+    dt.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+pub async fn handle_vacation(client: &FastmailClient, cmd: VacationCommands) -> Result<()> {
+    match cmd {
+        VacationCommands::Show => {
+            let vacation = client.get_vacation_response().await?;
+
+            let resp = Response::ok_with_rate_limit(vacation, client.rate_limit());
+            print_response(&resp)?;
+            Ok(())
+        }
+        VacationCommands::Enable {
+            subject,
+            text,
+            from,
+            to,
+        } => {
+            if let (Some(from), Some(to)) = (from, to) {
+                if to <= from {
+                    let resp = Response::<()>::error(ErrorResponse::validation_failed(
+                        "--to must be after --from".to_string(),
+                    ));
+                    print_response(&resp)?;
+                    std::process::exit(ExitCode::PermanentError.code());
+                }
+            }
+
+            let vacation = client
+                .enable_vacation_response(
+                    subject.as_deref(),
+                    text.as_deref(),
+                    from.as_ref().map(utc_date).as_deref(),
+                    to.as_ref().map(utc_date).as_deref(),
+                )
+                .await?;
+
+            let resp = Response::ok_with_rate_limit(vacation, client.rate_limit());
+            print_response(&resp)?;
+            Ok(())
+        }
+        VacationCommands::Disable => {
+            let vacation = client.disable_vacation_response().await?;
+
+            let resp = Response::ok_with_rate_limit(vacation, client.rate_limit());
+            print_response(&resp)?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dates_are_sent_as_utc() {
+        let from = parse_rfc3339("2024-07-01T09:00:00+02:00").unwrap();
+        assert_eq!(utc_date(&from), "2024-07-01T07:00:00Z");
+        assert!(parse_rfc3339("next monday").is_err());
+    }
+}
@@ -0,0 +1,611 @@
+// fastmail-cli/src/format.rs
+//! Human-readable rendering of common command results
+
+use crate::output::Formattable;
+use chrono::{DateTime, Utc};
+use fastmail_client::{
+    AccountSummary, BusyPeriod, CalendarEvent, Contact, ContactMatch, Email, EventConflict,
+    Mailbox, MailboxNode, MaskedEmail, MaskedEmailState, SearchSnippet,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Longest cell shown in a table before it is cut off
+const MAX_CELL_WIDTH: usize = 60;
+
+/// A page of emails from `mail list`
+#[derive(Debug, Serialize)]
+pub struct EmailList {
+    pub emails: Vec<Email>,
+    pub position: usize,
+    pub total: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_state: Option<String>,
+}
+
+/// Full-text search hits with the server's highlighted snippets
+#[derive(Debug, Serialize)]
+pub struct SearchResults {
+    pub emails: Vec<Email>,
+    pub snippets: Vec<SearchSnippet>,
+}
+
+/// The emails of one conversation, oldest first
+#[derive(Debug, Serialize)]
+pub struct ThreadView {
+    pub thread_id: String,
+    pub emails: Vec<Email>,
+}
+
+/// Contacts from one address book
+#[derive(Debug, Serialize)]
+pub struct ContactList {
+    pub contacts: Vec<Contact>,
+    pub count: usize,
+    pub book: String,
+}
+
+/// Contacts matching `contacts search`, from one or every address book
+#[derive(Debug, Serialize)]
+pub struct ContactSearchResults {
+    pub contacts: Vec<ContactMatch>,
+    pub count: usize,
+}
+
+/// Events from one calendar
+#[derive(Debug, Serialize)]
+pub struct EventList {
+    pub events: Vec<CalendarEvent>,
+    pub count: usize,
+    pub calendar: String,
+}
+
+/// Busy periods from `calendar freebusy`
+#[derive(Debug, Serialize)]
+pub struct BusyList {
+    pub busy: Vec<BusyPeriod>,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+/// Overlapping events from `calendar conflicts`
+#[derive(Debug, Serialize)]
+pub struct ConflictList {
+    pub conflicts: Vec<EventConflict>,
+    pub count: usize,
+    /// False if recurring events could not be expanded and only their
+    /// first occurrence was checked
+    pub expanded: bool,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+/// Outcome of one `doctor` check
+#[derive(Debug, Serialize)]
+pub struct DoctorCheck {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// Everything `doctor` found out about the account and its services
+#[derive(Debug, Default, Serialize)]
+pub struct DoctorReport {
+    pub healthy: bool,
+    pub account: Option<String>,
+    pub checks: Vec<DoctorCheck>,
+    pub capabilities: BTreeMap<&'static str, bool>,
+}
+
+impl Formattable for Vec<Email> {
+    fn to_human(&self) -> String {
+        let rows = self
+            .iter()
+            .map(|email| {
+                let unread = !email
+                    .keywords
+                    .as_ref()
+                    .is_some_and(|k| k.contains_key("$seen"));
+                vec![
+                    if unread { "*" } else { "" }.to_string(),
+                    email.received_at.clone().unwrap_or_default(),
+                    senders(email),
+                    email.subject.clone().unwrap_or_default(),
+                    email.id.clone(),
+                ]
+            })
+            .collect();
+        table(&["", "RECEIVED", "FROM", "SUBJECT", "ID"], rows)
+    }
+}
+
+impl Formattable for EmailList {
+    fn to_human(&self) -> String {
+        let first = if self.emails.is_empty() {
+            self.position
+        } else {
+            self.position + 1
+        };
+        format!(
+            "{}\n{}-{} of {}",
+            self.emails.to_human(),
+            first,
+            self.position + self.emails.len(),
+            self.total
+        )
+    }
+}
+
+impl Formattable for SearchResults {
+    fn to_human(&self) -> String {
+        let rows = self
+            .emails
+            .iter()
+            .map(|email| {
+                let snippet = self.snippets.iter().find(|s| s.email_id == email.id);
+                let subject = snippet
+                    .and_then(|s| s.subject.as_deref())
+                    .map(plain_snippet)
+                    .or_else(|| email.subject.clone())
+                    .unwrap_or_default();
+                let preview = snippet
+                    .and_then(|s| s.preview.as_deref())
+                    .map(plain_snippet)
+                    .unwrap_or_default();
+                vec![
+                    email.received_at.clone().unwrap_or_default(),
+                    senders(email),
+                    subject,
+                    preview,
+                    email.id.clone(),
+                ]
+            })
+            .collect();
+        table(&["RECEIVED", "FROM", "SUBJECT", "MATCH", "ID"], rows)
+    }
+}
+
+impl Formattable for ThreadView {
+    fn to_human(&self) -> String {
+        let rows = self
+            .emails
+            .iter()
+            .map(|email| {
+                vec![
+                    email.received_at.clone().unwrap_or_default(),
+                    senders(email),
+                    email.subject.clone().unwrap_or_default(),
+                    email.preview.clone().unwrap_or_default(),
+                ]
+            })
+            .collect();
+        table(&["RECEIVED", "FROM", "SUBJECT", "PREVIEW"], rows)
+    }
+}
+
+impl Formattable for Vec<Mailbox> {
+    fn to_human(&self) -> String {
+        let rows = self
+            .iter()
+            .map(|mailbox| {
+                vec![
+                    mailbox.name.clone(),
+                    mailbox.role.clone().unwrap_or_default(),
+                    mailbox.unread_emails.to_string(),
+                    mailbox.total_emails.to_string(),
+                    mailbox.id.clone(),
+                ]
+            })
+            .collect();
+        table(&["NAME", "ROLE", "UNREAD", "TOTAL", "ID"], rows)
+    }
+}
+
+impl Formattable for Mailbox {
+    fn to_human(&self) -> String {
+        let mut lines = vec![
+            format!("Name:    {}", self.name),
+            format!("ID:      {}", self.id),
+        ];
+        if let Some(role) = &self.role {
+            lines.push(format!("Role:    {}", role));
+        }
+        if let Some(parent) = &self.parent_id {
+            lines.push(format!("Parent:  {}", parent));
+        }
+        lines.push(format!(
+            "Emails:  {} ({} unread)",
+            self.total_emails, self.unread_emails
+        ));
+        lines.push(format!(
+            "Threads: {} ({} unread)",
+            self.total_threads, self.unread_threads
+        ));
+        if let Some(rights) = &self.my_rights {
+            let granted: Vec<&str> = [
+                (rights.may_read_items, "read"),
+                (rights.may_add_items, "add"),
+                (rights.may_remove_items, "remove"),
+                (rights.may_set_seen, "set-seen"),
+                (rights.may_set_keywords, "set-keywords"),
+                (rights.may_create_child, "create-child"),
+                (rights.may_rename, "rename"),
+                (rights.may_delete, "delete"),
+                (rights.may_submit, "submit"),
+            ]
+            .into_iter()
+            .filter_map(|(granted, name)| granted.then_some(name))
+            .collect();
+            let granted = if granted.is_empty() {
+                "none".to_string()
+            } else {
+                granted.join(", ")
+            };
+            lines.push(format!("Rights:  {}", granted));
+        }
+        lines.join("\n")
+    }
+}
+
+impl Formattable for Vec<MailboxNode> {
+    fn to_human(&self) -> String {
+        fn render(nodes: &[MailboxNode], depth: usize, lines: &mut Vec<String>) {
+            for node in nodes {
+                let mut line = format!("{}{}", "  ".repeat(depth), node.mailbox.name);
+                if node.mailbox.unread_emails > 0 {
+                    line.push_str(&format!(" ({})", node.mailbox.unread_emails));
+                }
+                lines.push(line);
+                render(&node.children, depth + 1, lines);
+            }
+        }
+
+        let mut lines = Vec::new();
+        render(self, 0, &mut lines);
+        lines.join("\n")
+    }
+}
+
+impl Formattable for Vec<AccountSummary> {
+    fn to_human(&self) -> String {
+        let yes_no = |flag: bool| if flag { "yes" } else { "no" }.to_string();
+        let rows = self
+            .iter()
+            .map(|account| {
+                vec![
+                    if account.selected { "*" } else { "" }.to_string(),
+                    account.id.clone(),
+                    account.name.clone().unwrap_or_default(),
+                    yes_no(account.is_personal),
+                    yes_no(account.is_read_only),
+                ]
+            })
+            .collect();
+        table(&["", "ID", "NAME", "PERSONAL", "READ-ONLY"], rows)
+    }
+}
+
+impl Formattable for Vec<MaskedEmail> {
+    /// One table per state, pending first since those still need confirming
+    fn to_human(&self) -> String {
+        [
+            MaskedEmailState::Pending,
+            MaskedEmailState::Enabled,
+            MaskedEmailState::Disabled,
+            MaskedEmailState::Deleted,
+        ]
+        .iter()
+        .filter_map(|state| {
+            let rows: Vec<Vec<String>> = self
+                .iter()
+                .filter(|e| e.state == *state)
+                .map(|e| {
+                    vec![
+                        e.email.clone(),
+                        e.for_domain.clone(),
+                        e.description.clone(),
+                        e.id.clone(),
+                    ]
+                })
+                .collect();
+            if rows.is_empty() {
+                return None;
+            }
+            Some(format!(
+                "{} ({})\n{}",
+                state.to_string().to_uppercase(),
+                rows.len(),
+                table(&["EMAIL", "DOMAIN", "DESCRIPTION", "ID"], rows)
+            ))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+    }
+}
+
+impl Formattable for ContactList {
+    fn to_human(&self) -> String {
+        let rows = self
+            .contacts
+            .iter()
+            .map(|contact| {
+                let name = match &contact.ln {
+                    Some(ln) => format!("{} {}", contact.fn_, ln),
+                    None => contact.fn_.clone(),
+                };
+                vec![
+                    name,
+                    contact.email.clone().unwrap_or_default(),
+                    contact.phone.clone().unwrap_or_default(),
+                    contact.organization.clone().unwrap_or_default(),
+                ]
+            })
+            .collect();
+        table(&["NAME", "EMAIL", "PHONE", "ORGANIZATION"], rows)
+    }
+}
+
+impl Formattable for ContactSearchResults {
+    fn to_human(&self) -> String {
+        let rows = self
+            .contacts
+            .iter()
+            .map(|found| {
+                let contact = &found.contact;
+                let name = match &contact.ln {
+                    Some(ln) => format!("{} {}", contact.fn_, ln),
+                    None => contact.fn_.clone(),
+                };
+                let book = found.book.trim_end_matches('/').rsplit('/').next();
+                vec![
+                    name,
+                    contact.email.clone().unwrap_or_default(),
+                    contact.phone.clone().unwrap_or_default(),
+                    book.unwrap_or_default().to_string(),
+                ]
+            })
+            .collect();
+        table(&["NAME", "EMAIL", "PHONE", "BOOK"], rows)
+    }
+}
+
+impl Formattable for EventList {
+    fn to_human(&self) -> String {
+        let rows = self
+            .events
+            .iter()
+            .map(|event| {
+                let time_format = if event.all_day {
+                    "%Y-%m-%d"
+                } else {
+                    "%Y-%m-%d %H:%M"
+                };
+                vec![
+                    event.start.format(time_format).to_string(),
+                    event.end.format(time_format).to_string(),
+                    event.summary.clone(),
+                    event.location.clone().unwrap_or_default(),
+                ]
+            })
+            .collect();
+        table(&["START", "END", "SUMMARY", "LOCATION"], rows)
+    }
+}
+
+impl Formattable for BusyList {
+    fn to_human(&self) -> String {
+        if self.busy.is_empty() {
+            return "No busy times in this range".to_string();
+        }
+        let rows = self
+            .busy
+            .iter()
+            .map(|period| {
+                vec![
+                    period.start.format("%Y-%m-%d %H:%M").to_string(),
+                    period.end.format("%Y-%m-%d %H:%M").to_string(),
+                    period.fb_type.clone(),
+                ]
+            })
+            .collect();
+        table(&["START", "END", "TYPE"], rows)
+    }
+}
+
+impl Formattable for ConflictList {
+    fn to_human(&self) -> String {
+        let mut out = if self.conflicts.is_empty() {
+            "No conflicts in this range".to_string()
+        } else {
+            let rows = self
+                .conflicts
+                .iter()
+                .map(|conflict| {
+                    vec![
+                        conflict.overlap_start.format("%Y-%m-%d %H:%M").to_string(),
+                        conflict.overlap_end.format("%Y-%m-%d %H:%M").to_string(),
+                        conflict.first.event.summary.clone(),
+                        conflict.second.event.summary.clone(),
+                    ]
+                })
+                .collect();
+            table(&["START", "END", "EVENT", "CONFLICTS WITH"], rows)
+        };
+        if !self.expanded {
+            out.push_str(
+                "\nNote: the server could not expand recurring events; \
+                 only their first occurrence was checked",
+            );
+        }
+        out
+    }
+}
+
+impl Formattable for DoctorReport {
+    fn to_human(&self) -> String {
+        let checks = self
+            .checks
+            .iter()
+            .map(|check| {
+                vec![
+                    if check.ok { "ok" } else { "FAIL" }.to_string(),
+                    check.name.to_string(),
+                    check.detail.clone(),
+                ]
+            })
+            .collect();
+        let capabilities = self
+            .capabilities
+            .iter()
+            .map(|(name, supported)| {
+                vec![
+                    name.to_string(),
+                    if *supported { "yes" } else { "no" }.to_string(),
+                ]
+            })
+            .collect();
+        format!(
+            "Account: {}\n\n{}\n\n{}",
+            self.account.as_deref().unwrap_or("unknown"),
+            table(&["", "CHECK", "DETAIL"], checks),
+            table(&["CAPABILITY", "SUPPORTED"], capabilities)
+        )
+    }
+}
+
+/// Turn a snippet's HTML (RFC 8621 §5.1) into text, showing `<mark>`ed terms as *term*
+fn plain_snippet(html: &str) -> String {
+    html.replace("<mark>", "*")
+        .replace("</mark>", "*")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Sender names, falling back to addresses
+fn senders(email: &Email) -> String {
+    email
+        .from
+        .iter()
+        .flatten()
+        .map(|a| a.name.clone().unwrap_or_else(|| a.email.clone()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Lay out rows as left-aligned columns under a header line
+fn table(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let rows: Vec<Vec<String>> = rows
+        .into_iter()
+        .map(|row| row.iter().map(|cell| truncate(cell)).collect())
+        .collect();
+
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let headers: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    std::iter::once(&headers)
+        .chain(&rows)
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Keep a cell on one line and within MAX_CELL_WIDTH characters
+fn truncate(cell: &str) -> String {
+    let cell = cell.replace(['\n', '\r', '\t'], " ");
+    if cell.chars().count() <= MAX_CELL_WIDTH {
+        return cell;
+    }
+    let mut cut: String = cell.chars().take(MAX_CELL_WIDTH - 1).collect();
+    cut.push('…');
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_aligns_columns() {
+        let out = table(
+            &["NAME", "ROLE"],
+            vec![
+                vec!["Inbox".to_string(), "inbox".to_string()],
+                vec!["Receipts 2024".to_string(), String::new()],
+            ],
+        );
+        assert_eq!(
+            out,
+            "NAME           ROLE\nInbox          inbox\nReceipts 2024"
+        );
+    }
+
+    #[test]
+    fn test_mailbox_tree_is_indented() {
+        let node = |name: &str, unread: u64, children| MailboxNode {
+            mailbox: serde_json::from_value(serde_json::json!({
+                "id": name,
+                "name": name,
+                "unreadEmails": unread,
+            }))
+            .unwrap(),
+            children,
+        };
+        let tree = vec![
+            node("Archive", 0, vec![node("Receipts", 2, vec![])]),
+            node("Inbox", 5, vec![]),
+        ];
+        assert_eq!(tree.to_human(), "Archive\n  Receipts (2)\nInbox (5)");
+    }
+
+    #[test]
+    fn test_masked_emails_grouped_by_state() {
+        let masked = |id: &str, state: &str| -> MaskedEmail {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "email": format!("{}@fastmail.com", id),
+                "state": state,
+                "forDomain": "https://example.com",
+                "description": "",
+                "createdAt": "2024-01-01T00:00:00Z",
+                "createdBy": "cli"
+            }))
+            .unwrap()
+        };
+        let out = vec![masked("a", "enabled"), masked("b", "pending")].to_human();
+        assert!(out.starts_with("PENDING (1)\nEMAIL"));
+        assert!(out.contains("\n\nENABLED (1)\n"));
+        assert!(!out.contains("DISABLED"));
+    }
+
+    #[test]
+    fn test_plain_snippet() {
+        assert_eq!(
+            plain_snippet("the <mark>budget</mark> &amp; Q3 &lt;draft&gt;"),
+            "the *budget* & Q3 <draft>"
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("line\nbreak"), "line break");
+        let long = "x".repeat(100);
+        assert_eq!(truncate(&long).chars().count(), MAX_CELL_WIDTH);
+        assert!(truncate(&long).ends_with('…'));
+    }
+}
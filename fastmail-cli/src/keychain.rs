@@ -0,0 +1,46 @@
+// fastmail-cli/src/keychain.rs
+//! API tokens in the OS keychain (Secret Service, macOS Keychain, Windows
+//! Credential Manager), when built with the `keyring` feature
+
+use anyhow::Result;
+
+/// Keychain service name every entry is stored under
+#[cfg(feature = "keyring")]
+const SERVICE: &str = "fastmail-cli";
+
+/// Keychain user for an `[accounts.<name>]` profile, or for the top-level token
+#[cfg(feature = "keyring")]
+fn entry(account: Option<&str>) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, account.unwrap_or("default"))
+}
+
+/// Whether this build can use the OS keychain
+pub fn available() -> bool {
+    cfg!(feature = "keyring")
+}
+
+/// The token saved for `account`, if any. A keychain that cannot be reached
+/// counts as empty so the config file and environment still work.
+#[cfg(feature = "keyring")]
+pub fn load_token(account: Option<&str>) -> Option<String> {
+    entry(account).and_then(|e| e.get_password()).ok()
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn load_token(_account: Option<&str>) -> Option<String> {
+    None
+}
+
+/// Save the token for `account`, replacing any previous one
+#[cfg(feature = "keyring")]
+pub fn store_token(account: Option<&str>, token: &str) -> Result<()> {
+    entry(account)?.set_password(token)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn store_token(_account: Option<&str>, _token: &str) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "This build has no keychain support; rebuild with --features keyring"
+    ))
+}
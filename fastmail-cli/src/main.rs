@@ -1,30 +1,41 @@
 mod commands;
+mod format;
+mod keychain;
 mod output;
 mod utils;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use commands::{
-    handle_calendar, handle_config, handle_contacts, handle_files, handle_mail, handle_mailbox,
-    handle_masked, run_setup, CalendarCommands, ConfigCommands, ContactsCommands, FilesCommands,
-    MailCommands, MailboxCommands, MaskedCommands, SharingCommands,
+    handle_accounts, handle_calendar, handle_config, handle_contacts, handle_files, handle_mail,
+    handle_mailbox, handle_masked, handle_vacation, run_doctor, run_setup, AccountsCommands,
+    CalendarCommands, ConfigCommands, ContactsCommands, FilesCommands, MailCommands,
+    MailboxCommands, MaskedCommands, SharingCommands, VacationCommands,
 };
-use utils::load_jmap_client;
+use fastmail_client::SessionCache;
+use output::{print_response, set_output_format, ErrorResponse, OutputFormat, Response};
+use utils::{is_unauthorized, GlobalOpts};
 
 #[derive(Parser)]
 #[command(name = "fastmail")]
 #[command(about = "A command-line interface for Fastmail", long_about = None)]
 struct Cli {
-    /// Output format: auto, json, human (default: auto)
-    #[arg(short = 'o', long, global = true, value_name = "FORMAT")]
-    output: Option<String>,
+    /// Output format: auto (human on a terminal, JSON otherwise), json, human
+    #[arg(short = 'o', long, global = true, value_enum, default_value_t = OutputFormat::Auto)]
+    output: OutputFormat,
+
+    #[command(flatten)]
+    global: GlobalOpts,
 
     #[command(subcommand)]
     command: Commands,
 }
 
-#[derive(Subcommand)]
+#[derive(Subcommand, Clone)]
 enum Commands {
+    /// JMAP accounts available to this login
+    #[command(subcommand)]
+    Accounts(AccountsCommands),
     /// Email operations
     #[command(subcommand)]
     Mail(MailCommands),
@@ -37,6 +48,9 @@ enum Commands {
     /// Masked email management
     #[command(subcommand)]
     Masked(MaskedCommands),
+    /// Vacation response (auto-reply)
+    #[command(subcommand)]
+    Vacation(VacationCommands),
     /// Contacts operations
     #[command(subcommand)]
     Contacts(ContactsCommands),
@@ -54,44 +68,94 @@ enum Commands {
     Config(ConfigCommands),
     /// Setup Fastmail CLI credentials
     Setup,
+    /// Check connectivity, credentials and server capabilities
+    Doctor,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     rustls::crypto::aws_lc_rs::default_provider()
         .install_default()
         .expect("Failed to install crypto provider");
 
     let cli = Cli::parse();
+    set_output_format(cli.output);
+    let opts = cli.global;
+
+    let command = cli.command;
+    let mut result = run(&opts, command.clone()).await;
+    // The cached session may be stale; fetch a fresh one and try once more
+    if command.uses_jmap_session() && matches!(&result, Err(e) if is_unauthorized(e)) {
+        let _ = SessionCache::clear();
+        result = run(&opts, command).await;
+    }
+
+    if let Err(e) = result {
+        // Don't keep a session the server no longer accepts
+        if is_unauthorized(&e) {
+            let _ = SessionCache::clear();
+        }
 
-    match cli.command {
+        let (error, exit_code) = ErrorResponse::from_error(&e);
+        let _ = print_response(&Response::<()>::error(error.redacted(&opts)));
+        std::process::exit(exit_code.code());
+    }
+}
+
+impl Commands {
+    /// Whether the command acts through a (possibly cached) JMAP session
+    fn uses_jmap_session(&self) -> bool {
+        matches!(
+            self,
+            Commands::Accounts(_)
+                | Commands::Mail(_)
+                | Commands::Mailbox(_)
+                | Commands::Blob(_)
+                | Commands::Masked(_)
+                | Commands::Vacation(_)
+                | Commands::Sharing(_)
+        )
+    }
+}
+
+async fn run(opts: &GlobalOpts, command: Commands) -> Result<()> {
+    match command {
+        Commands::Accounts(cmd) => {
+            let client = opts.load_jmap_client().await?;
+            handle_accounts(&client, cmd).await
+        }
         Commands::Mail(cmd) => {
-            let client = load_jmap_client().await?;
-            handle_mail(&client, cmd).await
+            let client = opts.load_jmap_client().await?;
+            handle_mail(opts, &client, cmd).await
         }
         Commands::Mailbox(cmd) => {
-            let client = load_jmap_client().await?;
-            handle_mailbox(&client, cmd).await
+            let client = opts.load_jmap_client().await?;
+            handle_mailbox(opts, &client, cmd).await
         }
         Commands::Blob(cmd) => {
-            let client = load_jmap_client().await?;
+            let client = opts.load_jmap_client().await?;
             commands::blob::handle_blob_command(&client, cmd).await
         }
         Commands::Masked(cmd) => {
-            let client = load_jmap_client().await?;
-            handle_masked(&client, cmd).await
+            let client = opts.load_jmap_client().await?;
+            handle_masked(opts, &client, cmd).await
+        }
+        Commands::Vacation(cmd) => {
+            let client = opts.load_jmap_client().await?;
+            handle_vacation(&client, cmd).await
         }
-        Commands::Contacts(cmd) => handle_contacts(cmd).await,
-        Commands::Calendar(cmd) => handle_calendar(cmd).await,
-        Commands::Files(cmd) => handle_files(cmd).await,
+        Commands::Contacts(cmd) => handle_contacts(opts, cmd).await,
+        Commands::Calendar(cmd) => handle_calendar(opts, cmd).await,
+        Commands::Files(cmd) => handle_files(opts, cmd).await,
         Commands::Sharing(cmd) => {
-            let client = load_jmap_client().await?;
+            let client = opts.load_jmap_client().await?;
             commands::sharing::handle_sharing_command(&client, cmd).await
         }
-        Commands::Config(cmd) => handle_config(cmd).await,
+        Commands::Config(cmd) => handle_config(opts, cmd).await,
         Commands::Setup => {
-            let exit_code = run_setup().await?;
+            let exit_code = run_setup(opts).await?;
             std::process::exit(exit_code);
         }
+        Commands::Doctor => run_doctor(opts).await,
     }
 }
@@ -1,13 +1,17 @@
 // fastmail-cli/src/output.rs
+use crate::utils::GlobalOpts;
+use chrono::SecondsFormat;
+use fastmail_client::{is_dav_timeout, HttpError, JmapError, RateLimit, ResourceChanged};
 use serde::Serialize;
 use std::fmt;
 use std::io::IsTerminal;
+use std::sync::OnceLock;
 
 /// Output format option
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
 pub enum OutputFormat {
     /// Auto-detect based on TTY
+    #[default]
     Auto,
     /// Force JSON output
     Json,
@@ -15,29 +19,41 @@ pub enum OutputFormat {
     Human,
 }
 
-/// Trait for types that can be formatted for output
-#[allow(dead_code)]
-pub trait Formattable {
-    /// Format as JSON string
-    fn to_json(&self) -> String;
+impl OutputFormat {
+    /// Whether to render for people; `Auto` does so when stdout is a terminal
+    pub fn is_human(self) -> bool {
+        match self {
+            Self::Json => false,
+            Self::Human => true,
+            Self::Auto => std::io::stdout().is_terminal(),
+        }
+    }
+}
+
+/// Format chosen with the global --output flag
+static OUTPUT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Set the output format for this process (first call wins)
+pub fn set_output_format(format: OutputFormat) {
+    let _ = OUTPUT_FORMAT.set(format);
+}
+
+fn output_format() -> OutputFormat {
+    OUTPUT_FORMAT.get().copied().unwrap_or_default()
+}
 
+/// Trait for types that can be formatted for output; JSON comes from `Serialize`
+pub trait Formattable {
     /// Format as human-readable string
     fn to_human(&self) -> String;
 }
 
 /// Format output based on the specified format
-#[allow(dead_code)]
-pub fn format_output<T: Formattable>(data: &T, format: OutputFormat) -> String {
-    match format {
-        OutputFormat::Json => data.to_json(),
-        OutputFormat::Human => data.to_human(),
-        OutputFormat::Auto => {
-            if std::io::stdout().is_terminal() {
-                data.to_human()
-            } else {
-                data.to_json()
-            }
-        }
+pub fn format_output<T: Serialize + Formattable>(data: &T, format: OutputFormat) -> String {
+    if format.is_human() {
+        data.to_human()
+    } else {
+        serde_json::to_string(data).unwrap_or_default()
     }
 }
 
@@ -72,6 +88,20 @@ impl<T> Response<T> {
         }
     }
 
+    /// Successful response carrying the server's rate-limit state, if known
+    pub fn ok_with_rate_limit(result: T, rate_limit: Option<RateLimit>) -> Self {
+        Self {
+            ok: true,
+            result: Some(result),
+            error: None,
+            meta: rate_limit.map(|rate_limit| Meta {
+                rate_limit: Some(rate_limit.into()),
+                dry_run: None,
+                operation_id: None,
+            }),
+        }
+    }
+
     pub fn error(error: ErrorResponse) -> Response<()> {
         Response::<()> {
             ok: false,
@@ -121,7 +151,93 @@ impl ErrorResponse {
         }
     }
 
-    #[allow(dead_code)]
+    /// A conditional write lost a race; the caller must fetch the resource again
+    pub fn conflict(message: String) -> Self {
+        Self {
+            type_: "conflict",
+            message,
+            retryable: Some(false),
+            retry_after: None,
+        }
+    }
+
+    /// Error reported by the server or the network
+    pub fn api_error(message: String, retryable: bool) -> Self {
+        Self {
+            type_: "api_error",
+            message,
+            retryable: Some(retryable),
+            retry_after: None,
+        }
+    }
+
+    /// Describe a command failure and choose the exit code for it.
+    /// Rate limits and temporarily unavailable servers are transient; other
+    /// JMAP and HTTP errors are permanent.
+    pub fn from_error(err: &anyhow::Error) -> (Self, ExitCode) {
+        // A timed-out request may well succeed on another attempt
+        if is_dav_timeout(err) {
+            return (
+                Self::api_error(err.to_string(), true),
+                ExitCode::TransientError,
+            );
+        }
+
+        for cause in err.chain() {
+            if cause.is::<ResourceChanged>() {
+                return (Self::conflict(err.to_string()), ExitCode::PermanentError);
+            }
+
+            if let Some(jmap_err) = cause.downcast_ref::<JmapError>() {
+                return match jmap_err {
+                    JmapError::AccountNotFound(_) => {
+                        (Self::not_found(err.to_string()), ExitCode::PermanentError)
+                    }
+                    e if e.is_transient() => (
+                        Self::api_error(err.to_string(), true),
+                        ExitCode::TransientError,
+                    ),
+                    _ => (
+                        Self::api_error(err.to_string(), false),
+                        ExitCode::PermanentError,
+                    ),
+                };
+            }
+
+            if let Some(http_err) = cause.downcast_ref::<HttpError>() {
+                return match (http_err.status, http_err.retry_after) {
+                    (Some(429), Some(wait)) => {
+                        (Self::rate_limited(wait.as_secs()), ExitCode::TransientError)
+                    }
+                    // No status means the request never got a response
+                    (None, _) => (
+                        Self::api_error(err.to_string(), true),
+                        ExitCode::TransientError,
+                    ),
+                    _ if http_err.is_transient() => (
+                        Self::api_error(err.to_string(), true),
+                        ExitCode::TransientError,
+                    ),
+                    _ => (
+                        Self::api_error(err.to_string(), false),
+                        ExitCode::PermanentError,
+                    ),
+                };
+            }
+        }
+
+        (
+            Self::api_error(err.to_string(), false),
+            ExitCode::PermanentError,
+        )
+    }
+
+    /// Mask any credentials quoted in the message, e.g. by a server echoing the request
+    pub fn redacted(mut self, opts: &GlobalOpts) -> Self {
+        self.message = opts.redact_secrets(&self.message);
+        self
+    }
+
     pub fn rate_limited(retry_after: u64) -> Self {
         Self {
             type_: "rate_limited",
@@ -145,7 +261,19 @@ pub struct Meta {
 #[derive(Debug, Serialize)]
 pub struct RateLimitInfo {
     pub remaining: u32,
-    pub reset_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset_at: Option<String>,
+}
+
+impl From<RateLimit> for RateLimitInfo {
+    fn from(rate_limit: RateLimit) -> Self {
+        Self {
+            remaining: rate_limit.remaining,
+            reset_at: rate_limit
+                .reset_at
+                .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        }
+    }
 }
 
 /// Exit codes for agent decision making
@@ -181,6 +309,18 @@ pub fn print_response<T: Serialize>(resp: &Response<T>) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Print a response in the selected output format. Human output shows only
+/// the result; errors and JSON output use the standard envelope.
+pub fn print_formatted<T: Serialize + Formattable>(resp: &Response<T>) -> anyhow::Result<()> {
+    match &resp.result {
+        Some(result) if output_format().is_human() => {
+            println!("{}", format_output(result, OutputFormat::Human));
+            Ok(())
+        }
+        _ => print_response(resp),
+    }
+}
+
 /// Print a styled success message
 #[allow(dead_code)]
 pub fn print_success(message: &str) {
@@ -217,15 +357,12 @@ pub fn print_header(key: &str, value: &str) {
 mod tests {
     use super::*;
 
+    #[derive(Serialize)]
     struct TestData {
         message: String,
     }
 
     impl Formattable for TestData {
-        fn to_json(&self) -> String {
-            format!(r#"{{"message":"{}"}}"#, self.message)
-        }
-
         fn to_human(&self) -> String {
             format!("Message: {}", self.message)
         }
@@ -262,7 +399,7 @@ mod tests {
         let meta = Meta {
             rate_limit: Some(RateLimitInfo {
                 remaining: 42,
-                reset_at: "2025-01-01T00:00:00Z".to_string(),
+                reset_at: Some("2025-01-01T00:00:00Z".to_string()),
             }),
             dry_run: Some(true),
             operation_id: Some("op-123".to_string()),
@@ -275,6 +412,62 @@ mod tests {
         assert!(json.contains("\"operation_id\":\"op-123\""));
     }
 
+    #[test]
+    fn test_error_response_from_error() {
+        let err = anyhow::Error::new(JmapError::RateLimit { description: None });
+        let (resp, code) = ErrorResponse::from_error(&err);
+        assert_eq!(resp.retryable, Some(true));
+        assert_eq!(code.code(), ExitCode::TransientError.code());
+
+        let err = anyhow::Error::new(JmapError::Forbidden).context("Failed to delete mailbox");
+        let (resp, code) = ErrorResponse::from_error(&err);
+        assert_eq!(resp.retryable, Some(false));
+        assert_eq!(code.code(), ExitCode::PermanentError.code());
+
+        let err = anyhow::Error::new(HttpError {
+            status: Some(429),
+            message: String::new(),
+            retry_after: Some(std::time::Duration::from_secs(30)),
+        });
+        let (resp, code) = ErrorResponse::from_error(&err);
+        assert_eq!(resp.type_, "rate_limited");
+        assert_eq!(resp.retry_after, Some(30));
+        assert_eq!(code.code(), ExitCode::TransientError.code());
+
+        let err = anyhow::Error::new(HttpError {
+            status: None,
+            message: "Request timed out: operation timed out".to_string(),
+            retry_after: None,
+        });
+        let (resp, code) = ErrorResponse::from_error(&err);
+        assert_eq!(resp.retryable, Some(true));
+        assert_eq!(code.code(), ExitCode::TransientError.code());
+
+        let err = anyhow::Error::new(ResourceChanged {
+            href: "/dav/addressbooks/user/me/Default/a.vcf".to_string(),
+        });
+        let (resp, code) = ErrorResponse::from_error(&err);
+        assert_eq!(resp.type_, "conflict");
+        assert!(resp.message.contains("fetch it again"));
+        assert_eq!(code.code(), ExitCode::PermanentError.code());
+    }
+
+    #[test]
+    fn test_response_ok_with_rate_limit() {
+        let rate_limit = RateLimit {
+            remaining: 7,
+            reset_at: chrono::DateTime::from_timestamp(1_735_689_600, 0),
+        };
+        let json =
+            serde_json::to_string(&Response::ok_with_rate_limit(1, Some(rate_limit))).unwrap();
+        assert!(
+            json.contains("\"rate_limit\":{\"remaining\":7,\"reset_at\":\"2025-01-01T00:00:00Z\"}")
+        );
+
+        let json = serde_json::to_string(&Response::ok_with_rate_limit(1, None)).unwrap();
+        assert!(!json.contains("\"meta\""));
+    }
+
     #[test]
     fn test_response_error_serialization() {
         let resp = Response::<()>::error(ErrorResponse::safety_rejected("test".to_string()));
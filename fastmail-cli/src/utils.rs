@@ -0,0 +1,304 @@
+// fastmail-cli/src/utils.rs
+use crate::keychain;
+use crate::output::{print_response, ErrorResponse, ExitCode, Response};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::Args;
+use fastmail_client::{
+    redact, Config, DestroyResult, FastmailClient, HttpError, UpdateResult, Whitelist,
+};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Options given before the subcommand, built once in main and handed to
+/// the commands that need them
+#[derive(Args, Debug, Default, Clone)]
+pub struct GlobalOpts {
+    /// Named account from config.toml (`[accounts.<name>]`); defaults to `default_account`
+    #[arg(long, global = true)]
+    pub account: Option<String>,
+
+    /// Answer yes to every confirmation prompt
+    #[arg(short = 'y', long = "yes", global = true)]
+    pub assume_yes: bool,
+
+    /// JMAP account id to act on, e.g. a shared account (see `accounts list`)
+    #[arg(long, global = true)]
+    pub jmap_account: Option<String>,
+
+    /// Seconds before a request is abandoned (default 30, or `timeout` in config.toml)
+    #[arg(long, global = true, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+
+    /// Config file to use instead of ~/.config/fastmail-cli/config.toml.
+    /// The recipient allow list is kept next to it.
+    #[arg(long = "config", global = true, value_name = "FILE")]
+    pub config_path: Option<PathBuf>,
+
+    /// Log JMAP requests and responses to stderr (the token is masked)
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+}
+
+impl GlobalOpts {
+    /// The account named with `--account`, if any
+    pub fn selected_account(&self) -> Option<&str> {
+        self.account.as_deref()
+    }
+
+    /// The config file named with `--config`, if any
+    pub fn config_path(&self) -> Option<&Path> {
+        self.config_path.as_deref()
+    }
+
+    /// Apply `--timeout` and `--verbose` to a loaded config
+    fn with_overrides(&self, mut config: Config) -> Config {
+        if let Some(seconds) = self.timeout {
+            config.timeout = Some(seconds);
+        }
+        config.verbose = self.verbose;
+        config
+    }
+
+    /// Load the config with the selected account's credentials applied
+    pub fn load_config(&self) -> Result<Config> {
+        Ok(self.with_overrides(
+            Config::load_from(self.config_path())?.with_account(self.selected_account())?,
+        ))
+    }
+
+    /// Load config.toml as written, for commands that save it back
+    pub fn load_config_file(&self) -> Result<Config> {
+        Config::load_file_from(self.config_path())
+    }
+
+    /// Where the recipient allow list is kept: next to the config file
+    pub fn whitelist_path(&self) -> Result<PathBuf> {
+        self.load_config_file()?.whitelist_path()
+    }
+
+    /// Load the recipient allow list kept next to the config file
+    pub fn load_whitelist(&self) -> Result<Whitelist> {
+        Whitelist::load_from(&self.whitelist_path()?)
+    }
+
+    /// Load the config for a DAV client. Without an email in the config or
+    /// FASTMAIL_EMAIL, the username from the JMAP session is used.
+    pub async fn load_dav_config(&self) -> Result<Config> {
+        let mut config = self.load_config()?;
+        if config.account.email.is_none() {
+            if let Ok(client) = self.load_jmap_client().await {
+                config.account.email = client.account_email().await.ok();
+            }
+        }
+        Ok(config)
+    }
+
+    /// Build a JMAP client, reusing the cached session when it is still fresh
+    pub async fn load_jmap_client(&self) -> Result<FastmailClient> {
+        let config = self.load_config()?;
+        let token = resolve_token(
+            &config,
+            self.selected_account(),
+            std::env::var("FASTMAIL_TOKEN").ok(),
+            keychain::load_token,
+        )?;
+
+        match self.jmap_account.as_deref() {
+            Some(account_id) => FastmailClient::with_account_id(token, &config, account_id).await,
+            None => FastmailClient::new_cached(token, &config).await,
+        }
+    }
+
+    /// Mask the tokens and passwords from config.toml and FASTMAIL_TOKEN in text shown to the user
+    pub fn redact_secrets(&self, text: &str) -> String {
+        let mut secrets = Config::load_from(self.config_path())
+            .map(|config| config.secrets())
+            .unwrap_or_default();
+        secrets.extend(std::env::var("FASTMAIL_TOKEN").ok());
+        let secrets: Vec<&str> = secrets.iter().map(String::as_str).collect();
+        redact(text, &secrets)
+    }
+
+    /// Ask the user a yes/no question on stderr, defaulting to "no".
+    /// Always "yes" without asking when `--yes` was given.
+    pub fn confirm(&self, prompt: &str) -> Result<bool> {
+        if self.assume_yes {
+            return Ok(true);
+        }
+        eprint!("{} [y/N] ", prompt);
+        io::stderr().flush()?;
+
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+
+    /// Ask before a destructive operation, unless `force` is set or the config's
+    /// `safety.require_confirm` is off. Exits with SafetyRejected if the user declines.
+    pub fn confirm_destructive(&self, prompt: &str, force: bool) -> Result<()> {
+        if force || !self.load_config()?.safety.require_confirm {
+            return Ok(());
+        }
+        if !self.confirm(prompt)? {
+            let resp = Response::<()>::error(ErrorResponse::safety_rejected(
+                "Operation cancelled".to_string(),
+            ));
+            print_response(&resp)?;
+            std::process::exit(ExitCode::SafetyRejected.code());
+        }
+        Ok(())
+    }
+}
+
+/// The token comes from `--account`, then FASTMAIL_TOKEN, then the default
+/// account, then the top-level `token` saved by `fastmail setup`. An account
+/// or top-level token missing from config.toml is looked up in the keychain.
+/// `None` means there is no token but an email and app password, for Basic auth.
+fn resolve_token(
+    config: &Config,
+    account: Option<&str>,
+    env_token: Option<String>,
+    keychain: impl Fn(Option<&str>) -> Option<String>,
+) -> Result<Option<String>> {
+    let app_password = config.account.email.is_some() && config.dav_password.is_some();
+    match config.account_profile(account)? {
+        Some((name, profile)) if account.is_some() || env_token.is_none() => {
+            match profile.token.clone().or_else(|| keychain(Some(name))) {
+                Some(token) => Ok(Some(token)),
+                None if app_password => Ok(None),
+                None => Err(anyhow::anyhow!(
+                    "No token set for account '{}' in config.toml; run `fastmail --account {} setup`",
+                    name,
+                    name
+                )),
+            }
+        }
+        _ => match env_token
+            .or_else(|| Some(config.token.clone()).filter(|t| !t.is_empty()))
+            .or_else(|| keychain(None))
+        {
+            Some(token) => Ok(Some(token)),
+            None if app_password => Ok(None),
+            None => Err(anyhow::anyhow!(
+                "No API token found; run `fastmail setup`, set FASTMAIL_TOKEN, \
+                 or set FASTMAIL_EMAIL and FASTMAIL_DAV_PASSWORD to use an app password"
+            )),
+        },
+    }
+}
+
+/// Whether an error was caused by the server rejecting our credentials
+pub fn is_unauthorized(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|e| e.downcast_ref::<HttpError>())
+        .any(|e| e.status == Some(401))
+}
+
+/// Exit with PermanentError after a partial delete, once the result has been printed
+pub fn exit_if_not_destroyed(result: &DestroyResult) {
+    if !result.not_destroyed.is_empty() {
+        std::process::exit(ExitCode::PermanentError.code());
+    }
+}
+
+/// Exit with PermanentError after a partial update, once the result has been printed
+pub fn exit_if_not_updated(result: &UpdateResult) {
+    if !result.not_updated.is_empty() {
+        std::process::exit(ExitCode::PermanentError.code());
+    }
+}
+
+/// Parse an RFC 3339 timestamp into UTC (a clap value parser)
+pub fn parse_rfc3339(s: &str) -> std::result::Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| {
+            format!(
+                "invalid time '{}': {} (expected e.g. 2024-05-01T09:00:00Z)",
+                s, e
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fastmail_client::{AccountConfig, AccountProfile};
+
+    #[test]
+    fn test_resolve_token_falls_back_to_config() {
+        let mut config = Config {
+            token: "fmu1-saved".to_string(),
+            ..Default::default()
+        };
+        let env = || Some("fmu1-env".to_string());
+        let empty = |_: Option<&str>| None;
+        assert_eq!(
+            resolve_token(&config, None, env(), empty)
+                .unwrap()
+                .as_deref(),
+            Some("fmu1-env")
+        );
+        assert_eq!(
+            resolve_token(&config, None, None, empty)
+                .unwrap()
+                .as_deref(),
+            Some("fmu1-saved")
+        );
+
+        config.token.clear();
+        let err = resolve_token(&config, None, None, empty).unwrap_err();
+        assert!(err.to_string().contains("fastmail setup"));
+        let app_password = Config {
+            account: AccountConfig {
+                email: Some("me@example.com".to_string()),
+                ..Default::default()
+            },
+            dav_password: Some("app-password".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            resolve_token(&app_password, None, None, empty).unwrap(),
+            None
+        );
+        let keychain = |account: Option<&str>| Some(format!("fmu1-{}", account.unwrap_or("kc")));
+        assert_eq!(
+            resolve_token(&config, None, None, keychain)
+                .unwrap()
+                .as_deref(),
+            Some("fmu1-kc")
+        );
+
+        config.accounts.insert(
+            "work".to_string(),
+            AccountProfile {
+                token: Some("fmu1-work".to_string()),
+                ..Default::default()
+            },
+        );
+        config
+            .accounts
+            .insert("home".to_string(), AccountProfile::default());
+        assert_eq!(
+            resolve_token(&config, Some("work"), env(), empty)
+                .unwrap()
+                .as_deref(),
+            Some("fmu1-work")
+        );
+        assert_eq!(
+            resolve_token(&config, Some("home"), env(), keychain)
+                .unwrap()
+                .as_deref(),
+            Some("fmu1-home")
+        );
+    }
+
+    #[test]
+    fn test_parse_rfc3339() {
+        let parsed = parse_rfc3339("2024-05-01T11:00:00+02:00").unwrap();
+        assert_eq!(parsed.to_rfc3339(), "2024-05-01T09:00:00+00:00");
+        assert!(parse_rfc3339("2024-05-01").is_err());
+    }
+}
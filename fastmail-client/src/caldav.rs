@@ -2,16 +2,25 @@
 //! CalDAV client implementation for calendar operations.
 
 use crate::config::Config;
+use crate::content_line::{
+    escape_text, fold_lines, split_content_line, unescape_text, unfold_lines,
+};
+use crate::dav::{parent_collection, put_resource, within};
 use anyhow::{anyhow, Result};
-use chrono::{DateTime, Utc};
-use http::Uri;
+use chrono::{DateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use futures::{stream, StreamExt};
+use http::{StatusCode, Uri};
 use hyper_rustls::HttpsConnectorBuilder;
 use hyper_util::client::legacy::Client;
 use hyper_util::rt::TokioExecutor;
+use jmap_client::EmailAddress;
 use libdav::caldav::{CalendarComponent, CreateCalendar, FindCalendars, GetCalendarResources};
-use libdav::dav::{Delete, FoundCollection, PutResource, WebDavClient};
-use libdav::FetchedResource;
+use libdav::dav::{Delete, FoundCollection, GetProperty, WebDavClient};
+use libdav::requests::DavRequest;
+use libdav::{names, FetchedResource, PropertyName};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use tower_http::auth::AddAuthorization;
 
 /// A calendar event (VEVENT)
@@ -31,6 +40,166 @@ pub struct CalendarEvent {
     pub location: Option<String>,
     /// Event status (e.g., "CONFIRMED", "TENTATIVE", "CANCELLED")
     pub status: Option<String>,
+    /// All-day event; start and end are dates at midnight UTC, end exclusive
+    #[serde(default)]
+    pub all_day: bool,
+    /// Meeting organizer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organizer: Option<EmailAddress>,
+    /// Invited participants
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attendees: Vec<Attendee>,
+    /// Reminders
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alarms: Vec<Alarm>,
+}
+
+/// An event, or one occurrence of a recurring event, with its calendar
+#[derive(Debug, Clone, Serialize)]
+pub struct EventOccurrence {
+    /// Href of the calendar
+    pub calendar: String,
+    #[serde(flatten)]
+    pub event: CalendarEvent,
+}
+
+/// Two events that take up the same time
+#[derive(Debug, Clone, Serialize)]
+pub struct EventConflict {
+    /// The one starting first
+    pub first: EventOccurrence,
+    pub second: EventOccurrence,
+    /// Start of the time both take up
+    pub overlap_start: DateTime<Utc>,
+    /// End of the time both take up
+    pub overlap_end: DateTime<Utc>,
+}
+
+/// Overlapping events found by `CalDavClient::conflicts`
+#[derive(Debug, Clone, Serialize)]
+pub struct EventConflicts {
+    pub conflicts: Vec<EventConflict>,
+    /// False if a server could not expand recurring events, so only their
+    /// first occurrence was checked
+    pub expanded: bool,
+}
+
+/// A reminder (VALARM, RFC 5545 §3.6.6)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alarm {
+    /// TRIGGER duration relative to the event start, e.g. "-PT15M"
+    pub trigger: String,
+    /// Alarm action, e.g. "DISPLAY" or "EMAIL"
+    pub action: String,
+    /// Text shown or sent with the alarm
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl Alarm {
+    /// A DISPLAY alarm firing `minutes` before the event starts
+    pub fn display_before(minutes: i64, description: Option<String>) -> Self {
+        Self {
+            trigger: format!("-PT{}M", minutes),
+            action: "DISPLAY".to_string(),
+            description,
+        }
+    }
+
+    /// Render as a VALARM block (with line breaks)
+    fn to_ical(&self) -> String {
+        let mut block = format!(
+            "BEGIN:VALARM\r\nACTION:{}\r\nTRIGGER:{}\r\n",
+            self.action, self.trigger
+        );
+        // DISPLAY and EMAIL alarms require a DESCRIPTION
+        let description = self.description.as_deref().unwrap_or("Reminder");
+        block.push_str(&format!("DESCRIPTION:{}\r\n", escape_text(description)));
+        block.push_str("END:VALARM\r\n");
+        block
+    }
+}
+
+/// A busy interval from a free/busy query (FREEBUSY, RFC 5545 §3.8.2.6)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BusyPeriod {
+    /// Start of the interval
+    pub start: DateTime<Utc>,
+    /// End of the interval
+    pub end: DateTime<Utc>,
+    /// Free/busy type: "BUSY", "BUSY-TENTATIVE" or "BUSY-UNAVAILABLE"
+    pub fb_type: String,
+}
+
+/// A meeting participant (ATTENDEE, RFC 5545 §3.8.4.1)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attendee {
+    /// Address from the mailto: URI
+    pub email: String,
+    /// Display name (CN parameter)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Participation role, e.g. "REQ-PARTICIPANT" or "OPT-PARTICIPANT"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// Participation status, e.g. "NEEDS-ACTION", "ACCEPTED" or "DECLINED"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partstat: Option<String>,
+}
+
+impl Attendee {
+    /// Build from an ATTENDEE value and its parameters
+    fn from_ical(params: &str, value: &str) -> Self {
+        Self {
+            email: strip_mailto(value).to_string(),
+            name: ical_param(params, "CN").map(str::to_string),
+            role: ical_param(params, "ROLE").map(str::to_string),
+            partstat: ical_param(params, "PARTSTAT").map(str::to_string),
+        }
+    }
+
+    /// Render as an ATTENDEE content line (without the line break)
+    fn to_ical(&self) -> String {
+        let mut line = String::from("ATTENDEE");
+        if let Some(ref name) = self.name {
+            line.push_str(&format!(";CN={}", quote_param(name)));
+        }
+        if let Some(ref role) = self.role {
+            line.push_str(&format!(";ROLE={}", role));
+        }
+        if let Some(ref partstat) = self.partstat {
+            line.push_str(&format!(";PARTSTAT={}", partstat));
+        }
+        line.push_str(&format!(":mailto:{}", self.email));
+        line
+    }
+}
+
+/// A task (VTODO)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarTask {
+    /// Unique identifier for the task
+    pub uid: String,
+    /// Task title/summary
+    pub summary: String,
+    /// Detailed description
+    #[serde(default)]
+    pub description: Option<String>,
+    /// When work on the task starts
+    #[serde(default)]
+    pub start: Option<DateTime<Utc>>,
+    /// When the task is due
+    #[serde(default)]
+    pub due: Option<DateTime<Utc>>,
+    /// Task status (e.g., "NEEDS-ACTION", "IN-PROCESS", "COMPLETED", "CANCELLED")
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Percent complete (0-100)
+    #[serde(default)]
+    pub percent_complete: Option<u8>,
+    /// Priority from 1 (highest) to 9 (lowest); 0 means undefined
+    #[serde(default)]
+    pub priority: Option<u8>,
 }
 
 /// A calendar collection
@@ -50,13 +219,16 @@ impl From<FoundCollection> for Calendar {
     fn from(collection: FoundCollection) -> Self {
         Self {
             href: collection.href,
-            display_name: None, // Would require additional PROPFIND
+            display_name: None,
             description: None,
             color: None,
         }
     }
 }
 
+/// How many per-calendar requests are in flight at once
+const CALENDAR_REQUEST_CONCURRENCY: usize = 4;
+
 /// CalDAV client wrapper
 ///
 /// Wraps the libdav CalDavClient with a simplified API specific to Fastmail.
@@ -65,6 +237,8 @@ pub struct CalDavClient {
     caldav: Box<dyn CalDavClientInner>,
     /// Base URL for CalDAV operations
     base_url: String,
+    /// Timezone for floating (zone-less) event times
+    timezone: Tz,
 }
 
 /// Trait to abstract over the complex CalDavClient generic type.
@@ -72,14 +246,29 @@ pub struct CalDavClient {
 trait CalDavClientInner: Send + Sync {
     async fn find_calendars(&self, home_set: &Uri) -> Result<Vec<FoundCollection>>;
     async fn get_calendar_resources(&self, href: &str) -> Result<Vec<FetchedResource>>;
+    async fn get_resource(&self, href: &str) -> Result<Option<FetchedResource>>;
     async fn delete_resource(&self, href: &str) -> Result<()>;
     async fn put_resource(
         &self,
         href: &str,
         data: String,
         content_type: &str,
+        if_match: Option<&str>,
     ) -> Result<Option<String>>;
     async fn create_calendar(&self, href: &str, display_name: &str) -> Result<()>;
+    async fn get_property(
+        &self,
+        href: &str,
+        property: &PropertyName<'_, '_>,
+    ) -> Result<Option<String>>;
+    async fn report(&self, href: &str, body: String) -> Result<Vec<u8>>;
+    /// Run a calendar-query REPORT; None if the server refused it as
+    /// unsupported (400, 403 or 501)
+    async fn query_resources(
+        &self,
+        href: &str,
+        body: String,
+    ) -> Result<Option<Vec<FetchedResource>>>;
 }
 
 /// Concrete implementation of CalDavClientInner
@@ -95,6 +284,7 @@ where
     C::Future: Send + 'static,
 {
     client: libdav::CalDavClient<C>,
+    timeout: Duration,
 }
 
 #[async_trait::async_trait]
@@ -111,17 +301,36 @@ where
     C::Future: Send + 'static,
 {
     async fn find_calendars(&self, home_set: &Uri) -> Result<Vec<FoundCollection>> {
-        let response = self.client.request(FindCalendars::new(home_set)).await?;
+        let response = within(
+            self.timeout,
+            self.client.request(FindCalendars::new(home_set)),
+        )
+        .await?;
         Ok(response.calendars)
     }
 
     async fn get_calendar_resources(&self, href: &str) -> Result<Vec<FetchedResource>> {
-        let response = self.client.request(GetCalendarResources::new(href)).await?;
+        let response = within(
+            self.timeout,
+            self.client.request(GetCalendarResources::new(href)),
+        )
+        .await?;
         Ok(response.resources)
     }
 
+    async fn get_resource(&self, href: &str) -> Result<Option<FetchedResource>> {
+        // calendar-multiget on the parent collection, restricted to this one href
+        let response = within(
+            self.timeout,
+            self.client
+                .request(GetCalendarResources::new(parent_collection(href)).with_hrefs([href])),
+        )
+        .await?;
+        Ok(response.resources.into_iter().next())
+    }
+
     async fn delete_resource(&self, href: &str) -> Result<()> {
-        self.client.request(Delete::new(href).force()).await?;
+        within(self.timeout, self.client.request(Delete::new(href).force())).await?;
         Ok(())
     }
 
@@ -130,21 +339,74 @@ where
         href: &str,
         data: String,
         content_type: &str,
+        if_match: Option<&str>,
     ) -> Result<Option<String>> {
-        let response = self
-            .client
-            .request(PutResource::new(href).create(data, content_type))
-            .await?;
-        Ok(response.etag)
+        let put = put_resource(&self.client, href, data, content_type, if_match);
+        within(self.timeout, put).await
     }
 
     async fn create_calendar(&self, href: &str, display_name: &str) -> Result<()> {
         let create_calendar = CreateCalendar::new(href)
             .with_display_name(display_name)
             .with_components(&[CalendarComponent::VEvent]);
-        self.client.request(create_calendar).await?;
+        within(self.timeout, self.client.request(create_calendar)).await?;
         Ok(())
     }
+
+    async fn get_property(
+        &self,
+        href: &str,
+        property: &PropertyName<'_, '_>,
+    ) -> Result<Option<String>> {
+        let response = within(
+            self.timeout,
+            self.client.request(GetProperty::new(href, property)),
+        )
+        .await?;
+        Ok(response.value)
+    }
+
+    async fn report(&self, href: &str, body: String) -> Result<Vec<u8>> {
+        let request = http::Request::builder()
+            .method("REPORT")
+            .uri(self.client.relative_uri(href)?)
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(body)?;
+        let (parts, body) = within(self.timeout, self.client.request_raw(request)).await?;
+        match parts.status {
+            StatusCode::OK => Ok(body.to_vec()),
+            status => Err(anyhow!("REPORT {} failed: {}", href, status)),
+        }
+    }
+
+    async fn query_resources(
+        &self,
+        href: &str,
+        body: String,
+    ) -> Result<Option<Vec<FetchedResource>>> {
+        let request = http::Request::builder()
+            .method("REPORT")
+            .uri(self.client.relative_uri(href)?)
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(body)?;
+        let (parts, body) = within(self.timeout, self.client.request_raw(request)).await?;
+        match parts.status {
+            // A server that can't run this query (e.g. no expand or text-match
+            // support) rejects it; anything else is a real failure
+            StatusCode::BAD_REQUEST | StatusCode::FORBIDDEN | StatusCode::NOT_IMPLEMENTED => {
+                return Ok(None)
+            }
+            status if !status.is_success() => {
+                return Err(anyhow!("REPORT {} failed: {}", href, status))
+            }
+            _ => {}
+        }
+        // The multistatus has the same shape as libdav's own calendar-query
+        let response = GetCalendarResources::new(href).parse_response(&parts, &body)?;
+        Ok(Some(response.resources))
+    }
 }
 
 impl CalDavClient {
@@ -165,8 +427,7 @@ impl CalDavClient {
         // Build HTTP client with DAV Basic auth
         // DAV endpoints use HTTP Basic Auth with email as username and app password as password
         let dav_username = config.get_dav_username()?;
-        let dav_password = config.dav_password.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("DAV password not set. Please set FASTMAIL_DAV_PASSWORD environment variable with your Fastmail app password. Generate one at: https://www.fastmail.com/settings/passwords"))?;
+        let dav_password = config.get_dav_password()?;
         let https_client = Client::builder(TokioExecutor::new()).build(https_connector);
         let https_client = AddAuthorization::basic(https_client, dav_username, dav_password);
 
@@ -175,11 +436,15 @@ impl CalDavClient {
         let webdav_client = WebDavClient::new(uri, https_client);
         let client = libdav::CalDavClient::new(webdav_client);
 
-        let inner: Box<dyn CalDavClientInner> = Box::new(CalDavClientInnerImpl { client });
+        let inner: Box<dyn CalDavClientInner> = Box::new(CalDavClientInnerImpl {
+            client,
+            timeout: config.get_timeout(),
+        });
 
         Ok(Self {
             caldav: inner,
             base_url: service_url,
+            timezone: config.timezone()?,
         })
     }
 
@@ -188,7 +453,61 @@ impl CalDavClient {
         let home_set: Uri = self.base_url.parse()?;
         let collections = self.caldav.find_calendars(&home_set).await?;
 
-        Ok(collections.into_iter().map(Calendar::from).collect())
+        // Fetch names and colours a few calendars at a time rather than one by one
+        let calendars = stream::iter(collections.into_iter().map(Calendar::from))
+            .map(|calendar| self.with_display_properties(calendar))
+            .buffered(CALENDAR_REQUEST_CONCURRENCY)
+            .collect()
+            .await;
+        Ok(calendars)
+    }
+
+    /// Fill in the display name and colour; a missing or failed property stays None
+    async fn with_display_properties(&self, mut calendar: Calendar) -> Calendar {
+        let (display_name, color) = futures::join!(
+            self.caldav
+                .get_property(&calendar.href, &names::DISPLAY_NAME),
+            self.caldav
+                .get_property(&calendar.href, &names::CALENDAR_COLOUR),
+        );
+        calendar.display_name = display_name.ok().flatten().filter(|n| !n.is_empty());
+        calendar.color = color.ok().flatten().filter(|c| !c.is_empty());
+        calendar
+    }
+
+    /// Busy intervals across all calendars between `start` and `end` (RFC 4791 §7.10).
+    /// Overlapping periods of the same type are merged; the result is sorted by start.
+    pub async fn free_busy(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<BusyPeriod>> {
+        let home_set: Uri = self.base_url.parse()?;
+        let collections = self.caldav.find_calendars(&home_set).await?;
+
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<C:free-busy-query xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <C:time-range start="{}" end="{}"/>
+</C:free-busy-query>"#,
+            start.format("%Y%m%dT%H%M%SZ"),
+            end.format("%Y%m%dT%H%M%SZ")
+        );
+
+        let responses: Vec<Result<Vec<u8>>> = stream::iter(collections)
+            .map(|collection| {
+                let body = body.clone();
+                async move { self.caldav.report(&collection.href, body).await }
+            })
+            .buffered(CALENDAR_REQUEST_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut periods = Vec::new();
+        for response in responses {
+            periods.extend(parse_free_busy(&response?));
+        }
+        Ok(merge_busy_periods(periods))
     }
 
     /// Get a specific calendar by href
@@ -231,7 +550,9 @@ impl CalDavClient {
         let mut events = Vec::new();
         for resource in resources {
             if let Ok(content) = resource.content {
-                if let Some(event) = Self::parse_icalendar_event(content.data.as_bytes()) {
+                if let Some(event) =
+                    Self::parse_icalendar_event_in(content.data.as_bytes(), self.timezone)
+                {
                     events.push(event);
                 }
             }
@@ -240,27 +561,218 @@ impl CalDavClient {
         Ok(events)
     }
 
+    /// Events in a calendar overlapping `start`..`end` (either may be open).
+    /// The server filters them with a calendar-query time-range (RFC 4791
+    /// §9.9); if it refuses the query, every event is fetched and filtered here.
+    /// Recurring events are returned once, with the series' first start.
+    pub async fn list_events_between(
+        &self,
+        calendar_href: &str,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<Vec<CalendarEvent>> {
+        if start.is_none() && end.is_none() {
+            return self.list_events(calendar_href).await;
+        }
+
+        let body = calendar_query(start, end, false);
+        let Some(resources) = self.caldav.query_resources(calendar_href, body).await? else {
+            let resources = self.caldav.get_calendar_resources(calendar_href).await?;
+            let mut events = Vec::new();
+            for content in resources.into_iter().filter_map(|r| r.content.ok()) {
+                let Some(event) =
+                    Self::parse_icalendar_event_in(content.data.as_bytes(), self.timezone)
+                else {
+                    continue;
+                };
+                // A series that began earlier may still have occurrences in the window
+                let overlaps = start.is_none_or(|start| event.end > start) || recurs(&content.data);
+                if overlaps && end.is_none_or(|end| event.start < end) {
+                    events.push(event);
+                }
+            }
+            return Ok(events);
+        };
+        Ok(resources
+            .into_iter()
+            .filter_map(|resource| resource.content.ok())
+            .filter_map(|content| {
+                Self::parse_icalendar_event_in(content.data.as_bytes(), self.timezone)
+            })
+            .collect())
+    }
+
+    /// Events in a calendar between `start` and `end`, with recurring events
+    /// expanded by the server into one entry per occurrence (RFC 4791 §9.6.5).
+    /// Without expansion support, recurring events only appear once.
+    pub async fn list_occurrences(
+        &self,
+        calendar_href: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<CalendarEvent>> {
+        let (events, _) = self.occurrences(calendar_href, start, end).await?;
+        Ok(events)
+    }
+
+    /// `list_occurrences`, and whether the server expanded recurring events
+    async fn occurrences(
+        &self,
+        calendar_href: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<(Vec<CalendarEvent>, bool)> {
+        let body = calendar_query(Some(start), Some(end), true);
+        let Some(resources) = self.caldav.query_resources(calendar_href, body).await? else {
+            let events = self
+                .list_events_between(calendar_href, Some(start), Some(end))
+                .await?;
+            return Ok((events, false));
+        };
+
+        let mut events = Vec::new();
+        for content in resources.into_iter().filter_map(|r| r.content.ok()) {
+            // Each expanded occurrence is its own VEVENT with a RECURRENCE-ID
+            for vevent in vevent_blocks(&content.data) {
+                events.extend(Self::parse_icalendar_event_in(
+                    vevent.as_bytes(),
+                    self.timezone,
+                ));
+            }
+        }
+        Ok((events, true))
+    }
+
+    /// Pairs of overlapping events across all calendars between `start` and `end`.
+    /// All-day and cancelled events are not counted as taking up time.
+    pub async fn conflicts(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<EventConflicts> {
+        let home_set: Uri = self.base_url.parse()?;
+        let collections = self.caldav.find_calendars(&home_set).await?;
+
+        let responses: Vec<Result<(Vec<EventOccurrence>, bool)>> = stream::iter(collections)
+            .map(|collection| async move {
+                let (events, expanded) = self.occurrences(&collection.href, start, end).await?;
+                let occurrences = events
+                    .into_iter()
+                    .map(|event| EventOccurrence {
+                        calendar: collection.href.clone(),
+                        event,
+                    })
+                    .collect();
+                Ok((occurrences, expanded))
+            })
+            .buffered(CALENDAR_REQUEST_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut occurrences = Vec::new();
+        let mut all_expanded = true;
+        for response in responses {
+            let (calendar_occurrences, expanded) = response?;
+            occurrences.extend(calendar_occurrences);
+            all_expanded &= expanded;
+        }
+        let mut conflicts = find_conflicts(occurrences);
+        conflicts.retain(|c| c.overlap_end > start && c.overlap_start < end);
+        Ok(EventConflicts {
+            conflicts,
+            expanded: all_expanded,
+        })
+    }
+
     /// Get a specific event by href
     pub async fn get_event(&self, event_href: &str) -> Result<CalendarEvent> {
-        let resources = self.caldav.get_calendar_resources(event_href).await?;
+        let resource = self
+            .caldav
+            .get_resource(event_href)
+            .await?
+            .ok_or_else(|| anyhow!("Event not found: {}", event_href))?;
+        let content = resource
+            .content
+            .map_err(|status| anyhow!("Failed to fetch event {}: {}", event_href, status))?;
+
+        Self::parse_icalendar_event_in(content.data.as_bytes(), self.timezone)
+            .ok_or_else(|| anyhow!("Event could not be parsed: {}", event_href))
+    }
 
-        resources
-            .into_iter()
-            .find(|r| r.href == event_href || r.href.ends_with(event_href))
-            .and_then(|r| r.content.ok())
-            .and_then(|c| Self::parse_icalendar_event(c.data.as_bytes()))
-            .ok_or_else(|| anyhow!("Event not found or could not be parsed: {}", event_href))
+    /// Where `put_event` stores the event with `uid`
+    pub fn event_href(calendar_href: &str, uid: &str) -> String {
+        format!("{}/{}.ics", calendar_href.trim_end_matches('/'), uid)
+    }
+
+    /// Fetch an event's iCalendar exactly as stored, with its ETag
+    pub async fn get_icalendar(&self, event_href: &str) -> Result<(String, String)> {
+        let resource = self
+            .caldav
+            .get_resource(event_href)
+            .await?
+            .ok_or_else(|| anyhow!("Event not found: {}", event_href))?;
+        let content = resource
+            .content
+            .map_err(|status| anyhow!("Failed to fetch event {}: {}", event_href, status))?;
+        Ok((content.data, content.etag))
     }
 
-    /// Create or update an event in a calendar
-    pub async fn put_event(&self, calendar_href: &str, event: &CalendarEvent) -> Result<String> {
-        // Generate href from UID
-        let event_href = format!("{}/{}.ics", calendar_href.trim_end_matches('/'), event.uid);
+    /// Where the event with `uid` is stored in a calendar, if it exists. Events
+    /// made by other clients need not be named `<uid>.ics`, so the server is
+    /// asked to match the UID (RFC 4791 §9.7.5); if it refuses, only the name
+    /// `put_event` would use is checked.
+    pub async fn find_event_href(&self, calendar_href: &str, uid: &str) -> Result<Option<String>> {
+        if let Some(resources) = self
+            .caldav
+            .query_resources(calendar_href, uid_query(uid))
+            .await?
+        {
+            return Ok(resources
+                .into_iter()
+                .find(|resource| resource.content.is_ok())
+                .map(|resource| resource.href));
+        }
+
+        let href = Self::event_href(calendar_href, uid);
+        let Some(resource) = self.caldav.get_resource(&href).await? else {
+            return Ok(None);
+        };
+        match resource.content {
+            Ok(_) => Ok(Some(href)),
+            Err(StatusCode::NOT_FOUND) => Ok(None),
+            Err(status) => Err(anyhow!("Failed to fetch event {}: {}", href, status)),
+        }
+    }
+
+    /// Create an event in a calendar, or with `if_match` replace it if it
+    /// still has that ETag
+    pub async fn put_event(
+        &self,
+        calendar_href: &str,
+        event: &CalendarEvent,
+        if_match: Option<&str>,
+    ) -> Result<String> {
+        let event_href = Self::event_href(calendar_href, &event.uid);
         let icalendar = Self::serialize_icalendar_event(event)?;
 
         let etag = self
             .caldav
-            .put_resource(&event_href, icalendar, "text/calendar")
+            .put_resource(&event_href, icalendar, "text/calendar", if_match)
+            .await?;
+
+        Ok(etag.unwrap_or_default())
+    }
+
+    /// Make the stored event at `event_href` match `event`, rewriting only the
+    /// properties that differ (see `patch_vevent`), and fail if it changed on
+    /// the server in the meantime. Returns the new ETag.
+    pub async fn update_event(&self, event_href: &str, event: &CalendarEvent) -> Result<String> {
+        let (ical, etag) = self.get_icalendar(event_href).await?;
+        let icalendar = patch_vevent(&ical, event, self.timezone)?;
+
+        let etag = self
+            .caldav
+            .put_resource(event_href, icalendar, "text/calendar", Some(&etag))
             .await?;
 
         Ok(etag.unwrap_or_default())
@@ -271,8 +783,56 @@ impl CalDavClient {
         self.caldav.delete_resource(event_href).await
     }
 
+    /// List all tasks in a calendar
+    pub async fn list_tasks(&self, calendar_href: &str) -> Result<Vec<CalendarTask>> {
+        let resources = self.caldav.get_calendar_resources(calendar_href).await?;
+
+        let mut tasks = Vec::new();
+        for resource in resources {
+            if let Ok(content) = resource.content {
+                if let Some(task) =
+                    Self::parse_icalendar_task_in(content.data.as_bytes(), self.timezone)
+                {
+                    tasks.push(task);
+                }
+            }
+        }
+
+        Ok(tasks)
+    }
+
+    /// Create a task in a calendar, or with `if_match` replace it if it still
+    /// has that ETag
+    pub async fn put_task(
+        &self,
+        calendar_href: &str,
+        task: &CalendarTask,
+        if_match: Option<&str>,
+    ) -> Result<String> {
+        let task_href = format!("{}/{}.ics", calendar_href.trim_end_matches('/'), task.uid);
+        let icalendar = Self::serialize_icalendar_task(task)?;
+
+        let etag = self
+            .caldav
+            .put_resource(&task_href, icalendar, "text/calendar", if_match)
+            .await?;
+
+        Ok(etag.unwrap_or_default())
+    }
+
+    /// Delete a task
+    pub async fn delete_task(&self, task_href: &str) -> Result<()> {
+        self.caldav.delete_resource(task_href).await
+    }
+
     /// Parse an iCalendar VEVENT from bytes (simplified MVP implementation)
+    /// Floating times are read as UTC.
     pub fn parse_icalendar_event(data: &[u8]) -> Option<CalendarEvent> {
+        Self::parse_icalendar_event_in(data, Tz::UTC)
+    }
+
+    /// Parse an iCalendar VEVENT, reading floating times in `floating_tz`
+    pub fn parse_icalendar_event_in(data: &[u8], floating_tz: Tz) -> Option<CalendarEvent> {
         let content = String::from_utf8(data.to_vec()).ok()?;
 
         // Very simplified iCalendar parser - just extracts basic fields
@@ -283,10 +843,17 @@ impl CalDavClient {
         let mut end = None;
         let mut location = None;
         let mut status = None;
+        let mut all_day = false;
+        let mut organizer = None;
+        let mut attendees = Vec::new();
+        let mut alarms = Vec::new();
+        let mut duration = None;
 
         let mut in_vevent = false;
+        // The VALARM being read, if inside one; its properties must not leak into the event
+        let mut alarm: Option<Alarm> = None;
 
-        for line in content.lines() {
+        for line in unfold_lines(&content) {
             let line = line.trim();
 
             if line.starts_with("BEGIN:VEVENT") {
@@ -302,24 +869,58 @@ impl CalDavClient {
                 continue;
             }
 
-            // Simple key-value parsing (ignoring folded lines for MVP)
-            if let Some((key, value)) = line.split_once(':') {
-                match key {
-                    "UID" => uid = Some(value.to_string()),
-                    "SUMMARY" => summary = Some(value.to_string()),
-                    "DESCRIPTION" => description = Some(value.to_string()),
-                    "LOCATION" => location = Some(value.to_string()),
+            if line.starts_with("BEGIN:VALARM") {
+                alarm = Some(Alarm {
+                    trigger: String::new(),
+                    action: String::new(),
+                    description: None,
+                });
+                continue;
+            }
+
+            if line.starts_with("END:VALARM") {
+                alarms.extend(alarm.take());
+                continue;
+            }
+
+            if let Some((key, value)) = split_content_line(line) {
+                let (name, params) = key.split_once(';').unwrap_or((key, ""));
+
+                if let Some(ref mut alarm) = alarm {
+                    match name {
+                        "TRIGGER" => alarm.trigger = value.to_string(),
+                        "ACTION" => alarm.action = value.to_string(),
+                        "DESCRIPTION" => alarm.description = Some(unescape_text(value)),
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                match name {
+                    "ORGANIZER" => {
+                        organizer = Some(EmailAddress {
+                            email: strip_mailto(value).to_string(),
+                            name: ical_param(params, "CN").map(str::to_string),
+                        })
+                    }
+                    "ATTENDEE" => attendees.push(Attendee::from_ical(params, value)),
+                    "UID" => uid = Some(unescape_text(value)),
+                    "SUMMARY" => summary = Some(unescape_text(value)),
+                    "DESCRIPTION" => description = Some(unescape_text(value)),
+                    "LOCATION" => location = Some(unescape_text(value)),
                     "STATUS" => status = Some(value.to_string()),
                     "DTSTART" => {
-                        if let Ok(dt) = Self::parse_ical_datetime(value) {
+                        all_day = is_ical_date(value, params);
+                        if let Ok(dt) = Self::parse_ical_time(value, params, floating_tz) {
                             start = Some(dt);
                         }
                     }
                     "DTEND" => {
-                        if let Ok(dt) = Self::parse_ical_datetime(value) {
+                        if let Ok(dt) = Self::parse_ical_time(value, params, floating_tz) {
                             end = Some(dt);
                         }
                     }
+                    "DURATION" => duration = parse_ical_duration(value),
                     _ => {}
                 }
             }
@@ -330,8 +931,15 @@ impl CalDavClient {
         let summary = summary.unwrap_or_default();
         let start = start?;
 
-        // Default end time to start + 1 hour if not specified
-        let end = end.unwrap_or_else(|| start + chrono::Duration::hours(1));
+        // Without DTEND use DURATION, else one day for all-day events
+        // (RFC 5545 §3.6.1) and one hour otherwise
+        let end = end.or(duration.map(|d| start + d)).unwrap_or_else(|| {
+            if all_day {
+                start + chrono::Duration::days(1)
+            } else {
+                start + chrono::Duration::hours(1)
+            }
+        });
 
         Some(CalendarEvent {
             uid,
@@ -341,36 +949,177 @@ impl CalDavClient {
             end,
             location,
             status,
+            all_day,
+            organizer,
+            attendees,
+            alarms,
         })
     }
 
-    /// Parse an iCalendar datetime string (simplified MVP implementation)
-    fn parse_ical_datetime(s: &str) -> Result<DateTime<Utc>> {
-        // Simplified: only handles basic formats like "20240115T100000Z"
-        let s = s.trim().trim_end_matches('Z');
+    /// Parse a DTSTART/DTEND value, which is either a DATE or a DATE-TIME
+    fn parse_ical_time(value: &str, params: &str, floating_tz: Tz) -> Result<DateTime<Utc>> {
+        if is_ical_date(value, params) {
+            Self::parse_ical_date(value)
+        } else {
+            Self::parse_ical_datetime(value, params, floating_tz)
+        }
+    }
 
-        if s.len() == 15 && s.contains('T') {
-            // Format: YYYYMMDDTHHMMSS
-            let year = s[0..4].parse::<i32>()?;
-            let month = s[4..6].parse::<u32>()?;
-            let day = s[6..8].parse::<u32>()?;
-            let hour = s[9..11].parse::<u32>()?;
-            let minute = s[11..13].parse::<u32>()?;
-            let second = s[13..15].parse::<u32>()?;
+    /// Parse an iCalendar DATE value (YYYYMMDD) as midnight UTC
+    fn parse_ical_date(s: &str) -> Result<DateTime<Utc>> {
+        let date = chrono::NaiveDate::parse_from_str(s.trim(), "%Y%m%d")
+            .map_err(|_| anyhow!("Invalid date: {}", s))?;
+        Ok(date.and_time(chrono::NaiveTime::MIN).and_utc())
+    }
 
-            let naive_date = chrono::NaiveDate::from_ymd_opt(year, month, day)
-                .ok_or_else(|| anyhow!("Invalid date: {}-{}-{}", year, month, day))?;
-            let naive_datetime = naive_date
-                .and_hms_opt(hour, minute, second)
-                .ok_or_else(|| anyhow!("Invalid time: {}:{}:{}", hour, minute, second))?;
+    /// Parse an iCalendar DATE-TIME value (RFC 5545 §3.3.5) given its property parameters.
+    /// UTC ("Z") times are taken as-is, TZID times are converted from that zone, and
+    /// floating times (and unknown TZIDs) are read in `floating_tz`.
+    fn parse_ical_datetime(s: &str, params: &str, floating_tz: Tz) -> Result<DateTime<Utc>> {
+        let s = s.trim();
+        let (local, is_utc) = match s.strip_suffix('Z') {
+            Some(local) => (local, true),
+            None => (s, false),
+        };
 
-            Ok(DateTime::<Utc>::from_naive_utc_and_offset(
+        if local.len() != 15 || local.as_bytes()[8] != b'T' {
+            return Err(anyhow!("Unsupported datetime format: {}", s));
+        }
+
+        // Format: YYYYMMDDTHHMMSS
+        let year = local[0..4].parse::<i32>()?;
+        let month = local[4..6].parse::<u32>()?;
+        let day = local[6..8].parse::<u32>()?;
+        let hour = local[9..11].parse::<u32>()?;
+        let minute = local[11..13].parse::<u32>()?;
+        let second = local[13..15].parse::<u32>()?;
+
+        let naive_date = chrono::NaiveDate::from_ymd_opt(year, month, day)
+            .ok_or_else(|| anyhow!("Invalid date: {}-{}-{}", year, month, day))?;
+        let naive_datetime = naive_date
+            .and_hms_opt(hour, minute, second)
+            .ok_or_else(|| anyhow!("Invalid time: {}:{}:{}", hour, minute, second))?;
+
+        if is_utc {
+            return Ok(DateTime::<Utc>::from_naive_utc_and_offset(
                 naive_datetime,
                 Utc,
-            ))
-        } else {
-            Err(anyhow!("Unsupported datetime format: {}", s))
+            ));
         }
+
+        let tz = ical_param(params, "TZID")
+            .and_then(|tzid| tzid.parse::<Tz>().ok())
+            .unwrap_or(floating_tz);
+        tz.from_local_datetime(&naive_datetime)
+            .earliest()
+            .map(|dt| dt.with_timezone(&Utc))
+            .ok_or_else(|| anyhow!("Local time {} does not exist in {}", naive_datetime, tz))
+    }
+
+    /// Parse an iCalendar VTODO from bytes. Floating times are read as UTC.
+    pub fn parse_icalendar_task(data: &[u8]) -> Option<CalendarTask> {
+        Self::parse_icalendar_task_in(data, Tz::UTC)
+    }
+
+    /// Parse an iCalendar VTODO, reading floating times in `floating_tz`
+    pub fn parse_icalendar_task_in(data: &[u8], floating_tz: Tz) -> Option<CalendarTask> {
+        let content = String::from_utf8(data.to_vec()).ok()?;
+
+        let mut uid = None;
+        let mut summary = None;
+        let mut description = None;
+        let mut start = None;
+        let mut due = None;
+        let mut status = None;
+        let mut percent_complete = None;
+        let mut priority = None;
+
+        let mut in_vtodo = false;
+
+        for line in unfold_lines(&content) {
+            let line = line.trim();
+
+            if line.starts_with("BEGIN:VTODO") {
+                in_vtodo = true;
+                continue;
+            }
+
+            if line.starts_with("END:VTODO") {
+                break;
+            }
+
+            if !in_vtodo {
+                continue;
+            }
+
+            if let Some((key, value)) = split_content_line(line) {
+                let (name, params) = key.split_once(';').unwrap_or((key, ""));
+                match name {
+                    "UID" => uid = Some(unescape_text(value)),
+                    "SUMMARY" => summary = Some(unescape_text(value)),
+                    "DESCRIPTION" => description = Some(unescape_text(value)),
+                    "STATUS" => status = Some(value.to_string()),
+                    "PERCENT-COMPLETE" => percent_complete = value.trim().parse().ok(),
+                    "PRIORITY" => priority = value.trim().parse().ok(),
+                    "DTSTART" => start = Self::parse_ical_time(value, params, floating_tz).ok(),
+                    "DUE" => due = Self::parse_ical_time(value, params, floating_tz).ok(),
+                    _ => {}
+                }
+            }
+        }
+
+        Some(CalendarTask {
+            uid: uid?,
+            summary: summary.unwrap_or_default(),
+            description,
+            start,
+            due,
+            status,
+            percent_complete,
+            priority,
+        })
+    }
+
+    /// Serialize a task to iCalendar format
+    fn serialize_icalendar_task(task: &CalendarTask) -> Result<String> {
+        let mut ical = String::from(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//fastmail-cli//EN\r\nBEGIN:VTODO\r\n",
+        );
+
+        ical.push_str(&format!("UID:{}\r\n", escape_text(&task.uid)));
+        ical.push_str(&format!(
+            "DTSTAMP:{}\r\n",
+            Utc::now().format("%Y%m%dT%H%M%SZ")
+        ));
+        ical.push_str(&format!("SUMMARY:{}\r\n", escape_text(&task.summary)));
+
+        if let Some(ref desc) = task.description {
+            ical.push_str(&format!("DESCRIPTION:{}\r\n", escape_text(desc)));
+        }
+
+        if let Some(start) = task.start {
+            ical.push_str(&format!("DTSTART:{}\r\n", start.format("%Y%m%dT%H%M%SZ")));
+        }
+
+        if let Some(due) = task.due {
+            ical.push_str(&format!("DUE:{}\r\n", due.format("%Y%m%dT%H%M%SZ")));
+        }
+
+        if let Some(ref status) = task.status {
+            ical.push_str(&format!("STATUS:{}\r\n", status));
+        }
+
+        if let Some(percent) = task.percent_complete {
+            ical.push_str(&format!("PERCENT-COMPLETE:{}\r\n", percent));
+        }
+
+        if let Some(priority) = task.priority {
+            ical.push_str(&format!("PRIORITY:{}\r\n", priority));
+        }
+
+        ical.push_str("END:VTODO\r\nEND:VCALENDAR\r\n");
+
+        Ok(fold_lines(&ical))
     }
 
     /// Serialize a calendar event to iCalendar format (simplified MVP implementation)
@@ -379,30 +1128,507 @@ impl CalDavClient {
             "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//fastmail-cli//EN\r\nBEGIN:VEVENT\r\n",
         );
 
-        ical.push_str(&format!("UID:{}\r\n", event.uid));
-        ical.push_str(&format!("SUMMARY:{}\r\n", event.summary));
+        ical.push_str(&format!("UID:{}\r\n", escape_text(&event.uid)));
+        ical.push_str(&format!("SUMMARY:{}\r\n", escape_text(&event.summary)));
 
         if let Some(ref desc) = event.description {
-            ical.push_str(&format!("DESCRIPTION:{}\r\n", desc));
+            ical.push_str(&format!("DESCRIPTION:{}\r\n", escape_text(desc)));
         }
 
-        ical.push_str(&format!(
-            "DTSTART:{}\r\n",
-            event.start.format("%Y%m%dT%H%M%SZ")
-        ));
-        ical.push_str(&format!("DTEND:{}\r\n", event.end.format("%Y%m%dT%H%M%SZ")));
+        for line in event_time_lines(event) {
+            ical.push_str(&line);
+            ical.push_str("\r\n");
+        }
 
         if let Some(ref location) = event.location {
-            ical.push_str(&format!("LOCATION:{}\r\n", location));
+            ical.push_str(&format!("LOCATION:{}\r\n", escape_text(location)));
         }
 
         if let Some(ref status) = event.status {
             ical.push_str(&format!("STATUS:{}\r\n", status));
         }
 
+        if let Some(ref organizer) = event.organizer {
+            ical.push_str(&organizer_line(organizer));
+            ical.push_str("\r\n");
+        }
+
+        for attendee in &event.attendees {
+            ical.push_str(&attendee.to_ical());
+            ical.push_str("\r\n");
+        }
+
+        for alarm in &event.alarms {
+            ical.push_str(&alarm.to_ical());
+        }
+
         ical.push_str("END:VEVENT\r\nEND:VCALENDAR\r\n");
 
-        Ok(ical)
+        Ok(fold_lines(&ical))
+    }
+}
+
+/// A calendar-query REPORT body for VEVENTs overlapping `start`..`end`,
+/// asking the server to expand recurring events when `expand` is set
+fn calendar_query(
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    expand: bool,
+) -> String {
+    let format = |time: DateTime<Utc>| time.format("%Y%m%dT%H%M%SZ").to_string();
+    let range = format!(
+        "{}{}",
+        start.map_or_else(String::new, |t| format!(r#" start="{}""#, format(t))),
+        end.map_or_else(String::new, |t| format!(r#" end="{}""#, format(t)))
+    );
+    let calendar_data = if expand {
+        format!(
+            "<C:calendar-data>\n      <C:expand{}/>\n    </C:calendar-data>",
+            range
+        )
+    } else {
+        "<C:calendar-data/>".to_string()
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop>
+    <D:getetag/>
+    {}
+  </D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VEVENT">
+        <C:time-range{}/>
+      </C:comp-filter>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#,
+        calendar_data, range
+    )
+}
+
+/// A calendar-query REPORT body for the VEVENT with `uid` (RFC 4791 §9.7.5)
+fn uid_query(uid: &str) -> String {
+    let uid = uid
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop>
+    <D:getetag/>
+    <C:calendar-data/>
+  </D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VEVENT">
+        <C:prop-filter name="UID">
+          <C:text-match collation="i;octet">{}</C:text-match>
+        </C:prop-filter>
+      </C:comp-filter>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#,
+        uid
+    )
+}
+
+/// DTSTART and DTEND content lines for an event, in UTC or as dates
+fn event_time_lines(event: &CalendarEvent) -> [String; 2] {
+    if event.all_day {
+        [
+            format!("DTSTART;VALUE=DATE:{}", event.start.format("%Y%m%d")),
+            format!("DTEND;VALUE=DATE:{}", event.end.format("%Y%m%d")),
+        ]
+    } else {
+        [
+            format!("DTSTART:{}", event.start.format("%Y%m%dT%H%M%SZ")),
+            format!("DTEND:{}", event.end.format("%Y%m%dT%H%M%SZ")),
+        ]
+    }
+}
+
+/// An ORGANIZER content line
+fn organizer_line(organizer: &EmailAddress) -> String {
+    match organizer.name {
+        Some(ref name) => format!(
+            "ORGANIZER;CN={}:mailto:{}",
+            quote_param(name),
+            organizer.email
+        ),
+        None => format!("ORGANIZER:mailto:{}", organizer.email),
+    }
+}
+
+/// Rewrite the properties of the master VEVENT (the one without a
+/// RECURRENCE-ID) that differ between what is stored in `ical` and `event`.
+/// Every other line is kept, so RRULE, EXDATE, TZID parameters, overrides and
+/// properties `CalendarEvent` does not model survive. A recurring event's time
+/// is not changed, since its exceptions would no longer line up.
+fn patch_vevent(ical: &str, event: &CalendarEvent, floating_tz: Tz) -> Result<String> {
+    let mut lines: Vec<String> = unfold_lines(ical)
+        .into_iter()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let (begin, end) = master_vevent(&lines).ok_or_else(|| anyhow!("No VEVENT to update"))?;
+    let mut block = lines[begin + 1..end].to_vec();
+    let old = CalDavClient::parse_icalendar_event_in(
+        lines[begin..=end].join("\r\n").as_bytes(),
+        floating_tz,
+    )
+    .ok_or_else(|| anyhow!("Event could not be parsed"))?;
+
+    let text = |name: &str, value: Option<&String>| -> Vec<String> {
+        value
+            .map(|value| format!("{}:{}", name, escape_text(value)))
+            .into_iter()
+            .collect()
+    };
+    if event.summary != old.summary {
+        replace_properties(&mut block, "SUMMARY", text("SUMMARY", Some(&event.summary)));
+    }
+    if event.description != old.description {
+        replace_properties(
+            &mut block,
+            "DESCRIPTION",
+            text("DESCRIPTION", event.description.as_ref()),
+        );
+    }
+    if event.location != old.location {
+        replace_properties(
+            &mut block,
+            "LOCATION",
+            text("LOCATION", event.location.as_ref()),
+        );
+    }
+    if event.status != old.status {
+        let status = event
+            .status
+            .iter()
+            .map(|s| format!("STATUS:{}", s))
+            .collect();
+        replace_properties(&mut block, "STATUS", status);
+    }
+    if (event.start, event.end, event.all_day) != (old.start, old.end, old.all_day) {
+        let recurs = ["RRULE", "RDATE"]
+            .iter()
+            .any(|name| !find_properties(&block, name).is_empty());
+        if recurs {
+            return Err(anyhow!(
+                "Cannot change the time of recurring event {}; edit it in a calendar app",
+                event.uid
+            ));
+        }
+        let [dtstart, dtend] = event_time_lines(event);
+        replace_properties(&mut block, "DTSTART", vec![dtstart]);
+        replace_properties(&mut block, "DURATION", Vec::new());
+        replace_properties(&mut block, "DTEND", vec![dtend]);
+    }
+    let address = |o: &Option<EmailAddress>| o.as_ref().map(|o| (o.email.clone(), o.name.clone()));
+    if address(&event.organizer) != address(&old.organizer) {
+        let organizer = event.organizer.iter().map(organizer_line).collect();
+        replace_properties(&mut block, "ORGANIZER", organizer);
+    }
+    if event.attendees != old.attendees {
+        let attendees = event.attendees.iter().map(Attendee::to_ical).collect();
+        replace_properties(&mut block, "ATTENDEE", attendees);
+    }
+    if event.alarms != old.alarms {
+        let alarms = event
+            .alarms
+            .iter()
+            .flat_map(|alarm| {
+                alarm
+                    .to_ical()
+                    .split_terminator("\r\n")
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        replace_components(&mut block, "VALARM", alarms);
+    }
+
+    lines.splice(begin + 1..end, block);
+    Ok(fold_lines(&(lines.join("\r\n") + "\r\n")))
+}
+
+/// Line indexes of the first VEVENT without a RECURRENCE-ID: its BEGIN and END
+fn master_vevent(lines: &[String]) -> Option<(usize, usize)> {
+    let mut begin = None;
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.trim();
+        if line.eq_ignore_ascii_case("BEGIN:VEVENT") {
+            begin = Some(i);
+        } else if line.eq_ignore_ascii_case("END:VEVENT") {
+            if let Some(b) = begin.take() {
+                if find_properties(&lines[b + 1..i], "RECURRENCE-ID").is_empty() {
+                    return Some((b, i));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Indexes of a component's own `name` properties, skipping nested components
+/// such as VALARM
+fn find_properties(block: &[String], name: &str) -> Vec<usize> {
+    let mut depth = 0usize;
+    let mut found = Vec::new();
+    for (i, line) in block.iter().enumerate() {
+        let Some((key, _)) = split_content_line(line.trim()) else {
+            continue;
+        };
+        let key = key.split(';').next().unwrap_or_default();
+        if key.eq_ignore_ascii_case("BEGIN") {
+            depth += 1;
+        } else if key.eq_ignore_ascii_case("END") {
+            depth = depth.saturating_sub(1);
+        } else if depth == 0 && key.eq_ignore_ascii_case(name) {
+            found.push(i);
+        }
+    }
+    found
+}
+
+/// Replace a component's own `name` properties with `new_lines`, where the
+/// first of them was, or else before any nested component
+fn replace_properties(block: &mut Vec<String>, name: &str, new_lines: Vec<String>) {
+    let found = find_properties(block, name);
+    let at = found.first().copied().unwrap_or_else(|| {
+        block
+            .iter()
+            .position(|line| line.trim().to_ascii_uppercase().starts_with("BEGIN:"))
+            .unwrap_or(block.len())
+    });
+    for &i in found.iter().rev() {
+        block.remove(i);
+    }
+    block.splice(at..at, new_lines);
+}
+
+/// Replace every nested `name` component (e.g. VALARM) with `new_lines`, added last
+fn replace_components(block: &mut Vec<String>, name: &str, new_lines: Vec<String>) {
+    let begin = format!("BEGIN:{}", name);
+    let end = format!("END:{}", name);
+    let mut inside = false;
+    block.retain(|line| {
+        let line = line.trim();
+        if line.eq_ignore_ascii_case(&begin) {
+            inside = true;
+        }
+        let keep = !inside;
+        if line.eq_ignore_ascii_case(&end) {
+            inside = false;
+        }
+        keep
+    });
+    block.extend(new_lines);
+}
+
+/// Whether the first VEVENT in an iCalendar object repeats (RRULE or RDATE)
+fn recurs(content: &str) -> bool {
+    vevent_blocks(content).first().is_some_and(|block| {
+        block.split("\r\n").any(|line| {
+            let name = line.split([';', ':']).next().unwrap_or_default();
+            name.eq_ignore_ascii_case("RRULE") || name.eq_ignore_ascii_case("RDATE")
+        })
+    })
+}
+
+/// Each VEVENT in an iCalendar object, as its own unfolded text
+fn vevent_blocks(content: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Vec<String>> = None;
+    for line in unfold_lines(content) {
+        let trimmed = line.trim();
+        if trimmed.eq_ignore_ascii_case("BEGIN:VEVENT") {
+            current = Some(Vec::new());
+        }
+        if let Some(ref mut lines) = current {
+            lines.push(trimmed.to_string());
+        }
+        if trimmed.eq_ignore_ascii_case("END:VEVENT") {
+            blocks.extend(current.take().map(|lines| lines.join("\r\n")));
+        }
+    }
+    blocks
+}
+
+/// Pairs of occurrences whose times overlap, in order of the overlap's start.
+/// All-day, cancelled and zero-length events do not block time.
+fn find_conflicts(mut occurrences: Vec<EventOccurrence>) -> Vec<EventConflict> {
+    occurrences.retain(|o| {
+        let cancelled = o
+            .event
+            .status
+            .as_deref()
+            .is_some_and(|s| s.eq_ignore_ascii_case("CANCELLED"));
+        !o.event.all_day && !cancelled && o.event.start < o.event.end
+    });
+    occurrences.sort_by_key(|o| (o.event.start, o.event.end));
+
+    let mut conflicts = Vec::new();
+    for (i, first) in occurrences.iter().enumerate() {
+        // Sorted by start, so the scan can stop at the first event starting after this one ends
+        for second in occurrences[i + 1..]
+            .iter()
+            .take_while(|o| o.event.start < first.event.end)
+        {
+            conflicts.push(EventConflict {
+                first: first.clone(),
+                second: second.clone(),
+                overlap_start: second.event.start,
+                overlap_end: first.event.end.min(second.event.end),
+            });
+        }
+    }
+    conflicts.sort_by_key(|c| (c.overlap_start, c.overlap_end));
+    conflicts
+}
+
+/// Extract the busy periods from a VFREEBUSY response. FREE periods are skipped.
+fn parse_free_busy(data: &[u8]) -> Vec<BusyPeriod> {
+    let content = String::from_utf8_lossy(data);
+    let mut periods = Vec::new();
+
+    for line in unfold_lines(&content) {
+        let Some((key, value)) = split_content_line(line.trim()) else {
+            continue;
+        };
+        let (name, params) = key.split_once(';').unwrap_or((key, ""));
+        if !name.eq_ignore_ascii_case("FREEBUSY") {
+            continue;
+        }
+
+        // FBTYPE defaults to BUSY (RFC 5545 §3.2.9)
+        let fb_type = ical_param(params, "FBTYPE")
+            .unwrap_or("BUSY")
+            .to_ascii_uppercase();
+        if fb_type == "FREE" {
+            continue;
+        }
+
+        for period in value.split(',') {
+            if let Some((start, end)) = parse_ical_period(period.trim()) {
+                periods.push(BusyPeriod {
+                    start,
+                    end,
+                    fb_type: fb_type.clone(),
+                });
+            }
+        }
+    }
+    periods
+}
+
+/// Parse a PERIOD value, either "start/end" or "start/duration" (RFC 5545 §3.3.9)
+fn parse_ical_period(value: &str) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let (start, rest) = value.split_once('/')?;
+    let start = CalDavClient::parse_ical_datetime(start, "", Tz::UTC).ok()?;
+    let end = match parse_ical_duration(rest) {
+        Some(duration) => start + duration,
+        None => CalDavClient::parse_ical_datetime(rest, "", Tz::UTC).ok()?,
+    };
+    Some((start, end))
+}
+
+/// Parse a DURATION value such as "PT1H30M" or "P1D" (RFC 5545 §3.3.6)
+fn parse_ical_duration(value: &str) -> Option<chrono::Duration> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let mut rest = value.strip_prefix('P')?;
+    let mut total = chrono::Duration::zero();
+    let mut in_time = false;
+    while !rest.is_empty() {
+        if let Some(time) = rest.strip_prefix('T') {
+            in_time = true;
+            rest = time;
+            continue;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let amount: i64 = rest[..digits].parse().ok()?;
+        total += match (rest[digits..].chars().next()?, in_time) {
+            ('W', false) => chrono::Duration::weeks(amount),
+            ('D', false) => chrono::Duration::days(amount),
+            ('H', true) => chrono::Duration::hours(amount),
+            ('M', true) => chrono::Duration::minutes(amount),
+            ('S', true) => chrono::Duration::seconds(amount),
+            _ => return None,
+        };
+        rest = &rest[digits + 1..];
+    }
+    Some(if negative { -total } else { total })
+}
+
+/// Sort periods by start and merge overlapping or touching ones of the same type
+fn merge_busy_periods(mut periods: Vec<BusyPeriod>) -> Vec<BusyPeriod> {
+    periods.sort_by_key(|p| (p.start, p.end));
+    let mut merged: Vec<BusyPeriod> = Vec::with_capacity(periods.len());
+    for period in periods {
+        // Only the most recent period of the same type can overlap this one
+        match merged
+            .iter_mut()
+            .rev()
+            .find(|p| p.fb_type == period.fb_type)
+        {
+            Some(last) if period.start <= last.end => last.end = last.end.max(period.end),
+            _ => merged.push(period),
+        }
+    }
+    merged
+}
+
+/// Whether a DTSTART/DTEND holds a DATE rather than a DATE-TIME
+fn is_ical_date(value: &str, params: &str) -> bool {
+    ical_param(params, "VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE"))
+        || value.trim().len() == 8
+}
+
+/// Look up a property parameter (e.g. TZID) in the `;`-separated part of a content line key
+fn ical_param<'a>(params: &'a str, name: &str) -> Option<&'a str> {
+    split_unquoted(params, ';')
+        .into_iter()
+        .filter_map(|p| p.split_once('='))
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim_matches('"'))
+}
+
+/// Split on `sep` outside double quotes
+fn split_unquoted(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == sep && !in_quotes {
+            parts.push(&s[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Quote a parameter value if it contains characters that would end it early
+fn quote_param(value: &str) -> String {
+    if value.contains([':', ';', ',']) {
+        format!("\"{}\"", value.replace('"', "'"))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Drop a case-insensitive "mailto:" prefix from a CAL-ADDRESS
+fn strip_mailto(value: &str) -> &str {
+    match value.get(..7) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("mailto:") => &value[7..],
+        _ => value,
     }
 }
 
@@ -442,7 +1668,7 @@ END:VCALENDAR";
 
     #[test]
     fn test_parse_ical_datetime() {
-        let dt = CalDavClient::parse_ical_datetime("20240115T100000Z").unwrap();
+        let dt = CalDavClient::parse_ical_datetime("20240115T100000Z", "", Tz::UTC).unwrap();
         assert_eq!(dt.year(), 2024);
         assert_eq!(dt.month(), 1);
         assert_eq!(dt.day(), 15);
@@ -451,6 +1677,43 @@ END:VCALENDAR";
         assert_eq!(dt.second(), 0);
     }
 
+    #[test]
+    fn test_parse_ical_datetime_with_timezones() {
+        // New York is UTC-5 in January and UTC-4 in July
+        let winter =
+            CalDavClient::parse_ical_datetime("20240115T100000", "TZID=America/New_York", Tz::UTC)
+                .unwrap();
+        assert_eq!(winter.to_rfc3339(), "2024-01-15T15:00:00+00:00");
+        let summer = CalDavClient::parse_ical_datetime(
+            "20240715T100000",
+            "TZID=\"America/New_York\"",
+            Tz::UTC,
+        )
+        .unwrap();
+        assert_eq!(summer.to_rfc3339(), "2024-07-15T14:00:00+00:00");
+
+        // Floating times use the configured zone
+        let floating =
+            CalDavClient::parse_ical_datetime("20240115T100000", "", Tz::Europe__Berlin).unwrap();
+        assert_eq!(floating.to_rfc3339(), "2024-01-15T09:00:00+00:00");
+    }
+
+    #[test]
+    fn test_parse_icalendar_event_with_tzid() {
+        let icalendar = b"BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:tz-event
+SUMMARY:Call
+DTSTART;TZID=Asia/Tokyo:20240115T090000
+DTEND;TZID=Asia/Tokyo:20240115T100000
+END:VEVENT
+END:VCALENDAR";
+
+        let event = CalDavClient::parse_icalendar_event(icalendar).unwrap();
+        assert_eq!(event.start.to_rfc3339(), "2024-01-15T00:00:00+00:00");
+        assert_eq!(event.end.to_rfc3339(), "2024-01-15T01:00:00+00:00");
+    }
+
     #[test]
     fn test_serialize_icalendar_event() {
         let event = CalendarEvent {
@@ -465,6 +1728,10 @@ END:VCALENDAR";
                 .with_timezone(&Utc),
             location: Some("Office".to_string()),
             status: Some("CONFIRMED".to_string()),
+            all_day: false,
+            organizer: None,
+            attendees: Vec::new(),
+            alarms: Vec::new(),
         };
 
         let icalendar = CalDavClient::serialize_icalendar_event(&event).unwrap();
@@ -500,4 +1767,910 @@ END:VCALENDAR";
         // End time should default to start + 1 hour
         assert_eq!(event.end, event.start + chrono::Duration::hours(1));
     }
+
+    #[tokio::test]
+    async fn test_get_event_fetches_single_resource() {
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let calendar = "/dav/calendars/user/me@example.com/Default/";
+        let href = format!("{}standup.ics", calendar);
+        let multistatus = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:href>{}</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:getetag>"etag-1"</d:getetag>
+        <cal:calendar-data>BEGIN:VCALENDAR
+VERSION:2.0
+BEGIN:VEVENT
+UID:standup
+SUMMARY:Standup
+DTSTART:20240115T100000Z
+END:VEVENT
+END:VCALENDAR
+</cal:calendar-data>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#,
+            href
+        );
+        Mock::given(method("REPORT"))
+            .and(path(calendar))
+            .and(body_string_contains("calendar-multiget"))
+            .and(body_string_contains(href.as_str()))
+            .respond_with(ResponseTemplate::new(207).set_body_string(multistatus))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = CalDavClient::from_config(&config).await.unwrap();
+
+        let event = client.get_event(&href).await.unwrap();
+        assert_eq!(event.uid, "standup");
+        assert_eq!(event.summary, "Standup");
+    }
+
+    #[tokio::test]
+    async fn test_list_events_between_queries_a_time_range() {
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let event = |uid: &str, start: &str| {
+            format!(
+                r#"<d:response>
+    <d:href>/dav/calendars/user/me@example.com/Work/{0}.ics</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:getetag>"{0}"</d:getetag>
+        <cal:calendar-data>BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:{0}
+SUMMARY:{0}
+DTSTART:{1}
+END:VEVENT
+END:VCALENDAR
+</cal:calendar-data>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>"#,
+                uid, start
+            )
+        };
+        let multistatus = |responses: &[&str]| {
+            format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  {}
+</d:multistatus>"#,
+                responses.join("\n  ")
+            )
+        };
+        let early = event("early", "20240101T090000Z");
+        let inside = event("inside", "20240115T100000Z");
+
+        let server = MockServer::start().await;
+        Mock::given(method("REPORT"))
+            .and(path("/dav/calendars/user/me@example.com/Work/"))
+            .and(body_string_contains(
+                r#"<C:time-range start="20240110T000000Z" end="20240120T000000Z"/>"#,
+            ))
+            .respond_with(ResponseTemplate::new(207).set_body_string(multistatus(&[&inside])))
+            .expect(1)
+            .mount(&server)
+            .await;
+        // A server without time-range support; the plain query returns everything
+        Mock::given(method("REPORT"))
+            .and(path("/dav/calendars/user/me@example.com/Old/"))
+            .and(body_string_contains("time-range"))
+            .respond_with(ResponseTemplate::new(501))
+            .mount(&server)
+            .await;
+        Mock::given(method("REPORT"))
+            .and(path("/dav/calendars/user/me@example.com/Old/"))
+            .respond_with(
+                ResponseTemplate::new(207).set_body_string(multistatus(&[&early, &inside])),
+            )
+            .mount(&server)
+            .await;
+        // A failing server is reported, not papered over with a full download
+        Mock::given(method("REPORT"))
+            .and(path("/dav/calendars/user/me@example.com/Broken/"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = CalDavClient::from_config(&config).await.unwrap();
+        let start = "2024-01-10T00:00:00Z".parse().ok();
+        let end = "2024-01-20T00:00:00Z".parse().ok();
+
+        for calendar in ["Work", "Old"] {
+            let href = format!("/dav/calendars/user/me@example.com/{}/", calendar);
+            let events = client.list_events_between(&href, start, end).await.unwrap();
+            let uids: Vec<&str> = events.iter().map(|e| e.uid.as_str()).collect();
+            assert_eq!(uids, vec!["inside"], "{}", calendar);
+        }
+        let err = client
+            .list_events_between("/dav/calendars/user/me@example.com/Broken/", start, end)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("500"));
+    }
+
+    #[tokio::test]
+    async fn test_list_events_between_keeps_series_started_earlier() {
+        use wiremock::matchers::{body_string_contains, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // A server without time-range support, so the window is applied here
+        let server = MockServer::start().await;
+        Mock::given(method("REPORT"))
+            .and(body_string_contains("time-range"))
+            .respond_with(ResponseTemplate::new(501))
+            .mount(&server)
+            .await;
+        Mock::given(method("REPORT"))
+            .respond_with(ResponseTemplate::new(207).set_body_string(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:href>/dav/calendars/user/me@example.com/Work/standup.ics</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:getetag>"1"</d:getetag>
+        <cal:calendar-data>BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:standup
+SUMMARY:Standup
+DTSTART:20240101T090000Z
+DURATION:PT15M
+RRULE:FREQ=WEEKLY
+END:VEVENT
+END:VCALENDAR
+</cal:calendar-data>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/dav/calendars/user/me@example.com/Work/kickoff.ics</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:getetag>"2"</d:getetag>
+        <cal:calendar-data>BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:kickoff
+SUMMARY:Kickoff
+DTSTART:20240102T090000Z
+DURATION:PT1H
+END:VEVENT
+END:VCALENDAR
+</cal:calendar-data>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = CalDavClient::from_config(&config).await.unwrap();
+        let events = client
+            .list_events_between(
+                "/dav/calendars/user/me@example.com/Work/",
+                "2024-01-15T00:00:00Z".parse().ok(),
+                "2024-01-22T00:00:00Z".parse().ok(),
+            )
+            .await
+            .unwrap();
+        let uids: Vec<&str> = events.iter().map(|e| e.uid.as_str()).collect();
+        assert_eq!(uids, vec!["standup"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_occurrences_splits_expanded_events() {
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("REPORT"))
+            .and(path("/dav/calendars/user/me@example.com/Work/"))
+            .and(body_string_contains(
+                r#"<C:expand start="20240101T000000Z" end="20240201T000000Z"/>"#,
+            ))
+            .respond_with(ResponseTemplate::new(207).set_body_string(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:href>/dav/calendars/user/me@example.com/Work/standup.ics</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:getetag>"1"</d:getetag>
+        <cal:calendar-data>BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:standup
+RECURRENCE-ID:20240108T090000Z
+SUMMARY:Standup
+DTSTART:20240108T090000Z
+DURATION:PT15M
+END:VEVENT
+BEGIN:VEVENT
+UID:standup
+RECURRENCE-ID:20240115T090000Z
+SUMMARY:Standup
+DTSTART:20240115T090000Z
+DURATION:PT15M
+END:VEVENT
+END:VCALENDAR
+</cal:calendar-data>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = CalDavClient::from_config(&config).await.unwrap();
+        let events = client
+            .list_occurrences(
+                "/dav/calendars/user/me@example.com/Work/",
+                "2024-01-01T00:00:00Z".parse().unwrap(),
+                "2024-02-01T00:00:00Z".parse().unwrap(),
+            )
+            .await
+            .unwrap();
+        let times: Vec<String> = events
+            .iter()
+            .map(|e| format!("{}-{}", e.start.format("%d %H:%M"), e.end.format("%H:%M")))
+            .collect();
+        assert_eq!(times, vec!["08 09:00-09:15", "15 09:00-09:15"]);
+    }
+
+    #[tokio::test]
+    async fn test_occurrences_reports_when_the_server_cannot_expand() {
+        use wiremock::matchers::{body_string_contains, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("REPORT"))
+            .and(body_string_contains("<C:expand"))
+            .respond_with(ResponseTemplate::new(501))
+            .mount(&server)
+            .await;
+        Mock::given(method("REPORT"))
+            .respond_with(ResponseTemplate::new(207).set_body_string(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:href>/dav/calendars/user/me@example.com/Work/standup.ics</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:getetag>"1"</d:getetag>
+        <cal:calendar-data>BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:standup
+SUMMARY:Standup
+DTSTART:20240108T090000Z
+DURATION:PT15M
+RRULE:FREQ=WEEKLY
+END:VEVENT
+END:VCALENDAR
+</cal:calendar-data>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = CalDavClient::from_config(&config).await.unwrap();
+        let (events, expanded) = client
+            .occurrences(
+                "/dav/calendars/user/me@example.com/Work/",
+                "2024-01-01T00:00:00Z".parse().unwrap(),
+                "2024-02-01T00:00:00Z".parse().unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(!expanded);
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let occurrence = |calendar: &str, uid: &str, start: &str, end: &str| EventOccurrence {
+            calendar: calendar.to_string(),
+            event: CalendarEvent {
+                uid: uid.to_string(),
+                summary: uid.to_string(),
+                description: None,
+                start: format!("2024-01-15T{}:00Z", start).parse().unwrap(),
+                end: format!("2024-01-15T{}:00Z", end).parse().unwrap(),
+                location: None,
+                status: None,
+                all_day: false,
+                organizer: None,
+                attendees: Vec::new(),
+                alarms: Vec::new(),
+            },
+        };
+        let mut cancelled = occurrence("work", "cancelled", "09:00", "12:00");
+        cancelled.event.status = Some("CANCELLED".to_string());
+        let mut holiday = occurrence("home", "holiday", "00:00", "23:59");
+        holiday.event.all_day = true;
+
+        let conflicts = find_conflicts(vec![
+            occurrence("work", "review", "10:30", "11:30"),
+            occurrence("work", "standup", "09:00", "09:15"),
+            occurrence("home", "dentist", "10:00", "11:00"),
+            occurrence("work", "lunch", "11:30", "12:30"),
+            cancelled,
+            holiday,
+        ]);
+        let pairs: Vec<(&str, &str, String)> = conflicts
+            .iter()
+            .map(|c| {
+                (
+                    c.first.event.uid.as_str(),
+                    c.second.event.uid.as_str(),
+                    format!(
+                        "{}-{}",
+                        c.overlap_start.format("%H:%M"),
+                        c.overlap_end.format("%H:%M")
+                    ),
+                )
+            })
+            .collect();
+        // Back-to-back events (review ends as lunch starts) do not conflict
+        assert_eq!(
+            pairs,
+            vec![("dentist", "review", "10:30-11:00".to_string())]
+        );
+        assert_eq!(conflicts[0].first.calendar, "home");
+    }
+
+    #[tokio::test]
+    async fn test_update_event_patches_the_stored_event() {
+        use wiremock::matchers::{body_string_contains, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let calendar = "/dav/calendars/user/me@example.com/Default/";
+        assert_eq!(
+            CalDavClient::event_href(calendar, "standup"),
+            format!("{}standup.ics", calendar)
+        );
+        // Made by another client, so not named after the UID
+        let href = format!("{}0F276A13.ics", calendar);
+        let stored = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:standup\r\n\
+            SUMMARY:Standup\r\nDTSTART;TZID=Europe/London:20240115T100000\r\n\
+            DTEND;TZID=Europe/London:20240115T101500\r\nRRULE:FREQ=WEEKLY;BYDAY=MO\r\n\
+            EXDATE;TZID=Europe/London:20240122T100000\r\nX-APPLE-TRAVEL-ADVISORY:1\r\n\
+            BEGIN:VALARM\r\nACTION:AUDIO\r\nTRIGGER:-PT5M\r\nEND:VALARM\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:standup\r\nRECURRENCE-ID;TZID=Europe/London:20240129T100000\r\n\
+            SUMMARY:Long standup\r\nDTSTART;TZID=Europe/London:20240129T100000\r\n\
+            DTEND;TZID=Europe/London:20240129T110000\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let multistatus = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:href>{}</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:getetag>"etag-1"</d:getetag>
+        <cal:calendar-data><![CDATA[{}]]></cal:calendar-data>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#,
+            href, stored
+        );
+        Mock::given(method("REPORT"))
+            .and(path(calendar))
+            .and(body_string_contains(
+                "<C:text-match collation=\"i;octet\">standup<",
+            ))
+            .respond_with(ResponseTemplate::new(207).set_body_string(multistatus.clone()))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("REPORT"))
+            .and(path(calendar))
+            .and(body_string_contains("calendar-multiget"))
+            .respond_with(ResponseTemplate::new(207).set_body_string(multistatus))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path(href.as_str()))
+            .and(header("If-Match", "\"etag-1\""))
+            .and(body_string_contains("SUMMARY:Team standup\r\n"))
+            .and(body_string_contains(
+                "DTSTART;TZID=Europe/London:20240115T100000\r\n",
+            ))
+            .and(body_string_contains("RRULE:FREQ=WEEKLY;BYDAY=MO\r\n"))
+            .and(body_string_contains(
+                "EXDATE;TZID=Europe/London:20240122T100000\r\n",
+            ))
+            .and(body_string_contains("X-APPLE-TRAVEL-ADVISORY:1\r\n"))
+            .and(body_string_contains("ACTION:AUDIO\r\n"))
+            .and(body_string_contains("SUMMARY:Long standup\r\n"))
+            .respond_with(ResponseTemplate::new(204).insert_header("ETag", "\"etag-2\""))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = CalDavClient::from_config(&config).await.unwrap();
+
+        let found = client.find_event_href(calendar, "standup").await.unwrap();
+        assert_eq!(found.as_deref(), Some(href.as_str()));
+        let mut event = client.get_event(&href).await.unwrap();
+        event.summary = "Team standup".to_string();
+        let etag = client.update_event(&href, &event).await.unwrap();
+        assert_eq!(etag, "\"etag-2\"");
+
+        event.start += chrono::Duration::hours(1);
+        let err = client.update_event(&href, &event).await.unwrap_err();
+        assert!(err.to_string().contains("recurring event standup"));
+    }
+
+    #[test]
+    fn test_patch_vevent_rewrites_changed_properties() {
+        let stored = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:u1\r\nSUMMARY:Lunch\r\n\
+            DTSTART:20240115T120000Z\r\nDURATION:PT1H\r\nLOCATION:Cafe\r\nX-CUSTOM:kept\r\n\
+            BEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:-PT10M\r\nDESCRIPTION:Lunch\r\n\
+            END:VALARM\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let mut event = CalDavClient::parse_icalendar_event(stored.as_bytes()).unwrap();
+        event.end += chrono::Duration::minutes(30);
+        event.location = None;
+        event.alarms.clear();
+
+        let patched = patch_vevent(stored, &event, chrono_tz::UTC).unwrap();
+        let lines = unfold_lines(&patched);
+        assert!(lines.contains(&"DTSTART:20240115T120000Z".to_string()));
+        assert!(lines.contains(&"DTEND:20240115T133000Z".to_string()));
+        assert!(lines.contains(&"X-CUSTOM:kept".to_string()));
+        assert!(lines.contains(&"SUMMARY:Lunch".to_string()));
+        assert!(!patched.contains("DURATION") && !patched.contains("LOCATION"));
+        assert!(!patched.contains("VALARM"));
+    }
+
+    #[test]
+    fn test_all_day_event_round_trip() {
+        let icalendar = b"BEGIN:VCALENDAR
+VERSION:2.0
+BEGIN:VEVENT
+UID:birthday-jane
+SUMMARY:Jane's birthday
+DTSTART;VALUE=DATE:20240115
+RRULE:FREQ=YEARLY
+END:VEVENT
+END:VCALENDAR";
+
+        let event = CalDavClient::parse_icalendar_event(icalendar).unwrap();
+        assert!(event.all_day);
+        assert_eq!(event.start.to_rfc3339(), "2024-01-15T00:00:00+00:00");
+        assert_eq!(event.end.to_rfc3339(), "2024-01-16T00:00:00+00:00");
+
+        let serialized = CalDavClient::serialize_icalendar_event(&event).unwrap();
+        assert!(serialized.contains("DTSTART;VALUE=DATE:20240115\r\n"));
+        assert!(serialized.contains("DTEND;VALUE=DATE:20240116\r\n"));
+
+        let reparsed = CalDavClient::parse_icalendar_event(serialized.as_bytes()).unwrap();
+        assert!(reparsed.all_day);
+        assert_eq!(reparsed.start, event.start);
+        assert_eq!(reparsed.end, event.end);
+    }
+
+    #[test]
+    fn test_parse_icalendar_task() {
+        let icalendar = b"BEGIN:VCALENDAR
+VERSION:2.0
+BEGIN:VTODO
+UID:task-1
+SUMMARY:File taxes
+DTSTART;VALUE=DATE:20240401
+DUE;TZID=America/New_York:20240415T170000
+STATUS:IN-PROCESS
+PERCENT-COMPLETE:40
+PRIORITY:1
+END:VTODO
+END:VCALENDAR";
+
+        let task = CalDavClient::parse_icalendar_task(icalendar).unwrap();
+        assert_eq!(task.uid, "task-1");
+        assert_eq!(task.summary, "File taxes");
+        assert_eq!(
+            task.start.unwrap().to_rfc3339(),
+            "2024-04-01T00:00:00+00:00"
+        );
+        assert_eq!(task.due.unwrap().to_rfc3339(), "2024-04-15T21:00:00+00:00");
+        assert_eq!(task.status.as_deref(), Some("IN-PROCESS"));
+        assert_eq!(task.percent_complete, Some(40));
+        assert_eq!(task.priority, Some(1));
+
+        // Events are not tasks
+        assert!(CalDavClient::parse_icalendar_task(
+            b"BEGIN:VCALENDAR\nBEGIN:VEVENT\nUID:e\nEND:VEVENT\nEND:VCALENDAR"
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_task_round_trip() {
+        let task = CalendarTask {
+            uid: "task-2".to_string(),
+            summary: "Renew passport".to_string(),
+            description: None,
+            start: None,
+            due: Some(
+                DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z")
+                    .unwrap()
+                    .with_timezone(&Utc),
+            ),
+            status: Some("NEEDS-ACTION".to_string()),
+            percent_complete: Some(0),
+            priority: None,
+        };
+
+        let ical = CalDavClient::serialize_icalendar_task(&task).unwrap();
+        assert!(ical.contains("BEGIN:VTODO\r\n"));
+        assert!(ical.contains("DUE:20240601T120000Z\r\n"));
+        assert!(!ical.contains("PRIORITY"));
+
+        let parsed = CalDavClient::parse_icalendar_task(ical.as_bytes()).unwrap();
+        assert_eq!(parsed.uid, task.uid);
+        assert_eq!(parsed.due, task.due);
+        assert_eq!(parsed.status, task.status);
+        assert_eq!(parsed.percent_complete, Some(0));
+    }
+
+    #[test]
+    fn test_event_attendees_round_trip() {
+        let icalendar = b"BEGIN:VCALENDAR
+VERSION:2.0
+BEGIN:VEVENT
+UID:planning
+SUMMARY:Planning
+DTSTART:20240115T100000Z
+ORGANIZER;CN=\"Boss: Alice\":mailto:alice@example.com
+ATTENDEE;CN=Bob;ROLE=REQ-PARTICIPANT;PARTSTAT=ACCEPTED:mailto:bob@example.com
+ATTENDEE;ROLE=OPT-PARTICIPANT;PARTSTAT=NEEDS-ACTION:MAILTO:carol@example.com
+END:VEVENT
+END:VCALENDAR";
+
+        let event = CalDavClient::parse_icalendar_event(icalendar).unwrap();
+        let organizer = event.organizer.as_ref().unwrap();
+        assert_eq!(organizer.email, "alice@example.com");
+        assert_eq!(organizer.name.as_deref(), Some("Boss: Alice"));
+        assert_eq!(
+            event.attendees,
+            vec![
+                Attendee {
+                    email: "bob@example.com".to_string(),
+                    name: Some("Bob".to_string()),
+                    role: Some("REQ-PARTICIPANT".to_string()),
+                    partstat: Some("ACCEPTED".to_string()),
+                },
+                Attendee {
+                    email: "carol@example.com".to_string(),
+                    name: None,
+                    role: Some("OPT-PARTICIPANT".to_string()),
+                    partstat: Some("NEEDS-ACTION".to_string()),
+                },
+            ]
+        );
+
+        let serialized = CalDavClient::serialize_icalendar_event(&event).unwrap();
+        let lines = unfold_lines(&serialized);
+        assert!(
+            lines.contains(&"ORGANIZER;CN=\"Boss: Alice\":mailto:alice@example.com".to_string())
+        );
+        assert!(lines.contains(
+            &"ATTENDEE;CN=Bob;ROLE=REQ-PARTICIPANT;PARTSTAT=ACCEPTED:mailto:bob@example.com"
+                .to_string()
+        ));
+
+        let reparsed = CalDavClient::parse_icalendar_event(serialized.as_bytes()).unwrap();
+        assert_eq!(reparsed.attendees, event.attendees);
+        assert_eq!(reparsed.organizer.unwrap().name, organizer.name);
+    }
+
+    #[test]
+    fn test_event_alarms_round_trip() {
+        let icalendar = b"BEGIN:VCALENDAR
+VERSION:2.0
+BEGIN:VEVENT
+UID:dentist
+SUMMARY:Dentist
+DESCRIPTION:Bring insurance card
+DTSTART:20240115T100000Z
+BEGIN:VALARM
+ACTION:DISPLAY
+TRIGGER:-PT15M
+DESCRIPTION:Leave now
+END:VALARM
+BEGIN:VALARM
+ACTION:EMAIL
+TRIGGER;RELATED=START:-P1D
+DESCRIPTION:Tomorrow
+END:VALARM
+LOCATION:Main St
+END:VEVENT
+END:VCALENDAR";
+
+        let event = CalDavClient::parse_icalendar_event(icalendar).unwrap();
+        // Alarm properties stay out of the event, and parsing continues after END:VALARM
+        assert_eq!(event.description.as_deref(), Some("Bring insurance card"));
+        assert_eq!(event.location.as_deref(), Some("Main St"));
+        assert_eq!(
+            event.alarms,
+            vec![
+                Alarm::display_before(15, Some("Leave now".to_string())),
+                Alarm {
+                    trigger: "-P1D".to_string(),
+                    action: "EMAIL".to_string(),
+                    description: Some("Tomorrow".to_string()),
+                },
+            ]
+        );
+
+        let serialized = CalDavClient::serialize_icalendar_event(&event).unwrap();
+        assert!(serialized.contains(
+            "BEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:-PT15M\r\nDESCRIPTION:Leave now\r\nEND:VALARM\r\n"
+        ));
+        let reparsed = CalDavClient::parse_icalendar_event(serialized.as_bytes()).unwrap();
+        assert_eq!(reparsed.alarms, event.alarms);
+    }
+
+    #[test]
+    fn test_long_description_is_folded_and_escaped() {
+        let description = "Agenda: budget, hiring; roadmap.\n\n\
+            Please read the attached notes before the meeting — they cover the \
+            quarterly numbers in some detail.\n\nThanks!";
+        let event = CalendarEvent {
+            uid: "planning".to_string(),
+            summary: "Planning, Q3".to_string(),
+            description: Some(description.to_string()),
+            start: Utc.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap(),
+            end: Utc.with_ymd_and_hms(2024, 6, 3, 10, 0, 0).unwrap(),
+            location: None,
+            status: None,
+            all_day: false,
+            organizer: None,
+            attendees: Vec::new(),
+            alarms: Vec::new(),
+        };
+
+        let serialized = CalDavClient::serialize_icalendar_event(&event).unwrap();
+        assert!(serialized.contains("SUMMARY:Planning\\, Q3\r\n"));
+        assert!(serialized.contains("DESCRIPTION:Agenda: budget\\, hiring\\; roadmap.\\n\\nPlease"));
+        for line in serialized.split_terminator("\r\n") {
+            assert!(line.len() <= 75, "line too long: {:?}", line);
+        }
+        assert!(serialized.contains("\r\n "));
+
+        let reparsed = CalDavClient::parse_icalendar_event(serialized.as_bytes()).unwrap();
+        assert_eq!(reparsed.summary, "Planning, Q3");
+        assert_eq!(reparsed.description.as_deref(), Some(description));
+    }
+
+    #[tokio::test]
+    async fn test_list_calendars_fetches_display_properties() {
+        use wiremock::matchers::{body_string_contains, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let home = "/dav/calendars/user/me@example.com/";
+        let work = format!("{}work/", home);
+        let personal = format!("{}personal/", home);
+        let collections = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:href>{}</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype><d:collection/><cal:calendar/></d:resourcetype></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>{}</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype><d:collection/><cal:calendar/></d:resourcetype></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#,
+            work, personal
+        );
+        let property = |href: &str, prop: &str| {
+            format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:ical="http://apple.com/ns/ical/">
+  <d:response>
+    <d:href>{}</d:href>
+    <d:propstat>
+      <d:prop>{}</d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#,
+                href, prop
+            )
+        };
+
+        Mock::given(method("PROPFIND"))
+            .and(path(home))
+            .and(header("Depth", "1"))
+            .respond_with(ResponseTemplate::new(207).set_body_string(collections))
+            .mount(&server)
+            .await;
+        Mock::given(method("PROPFIND"))
+            .and(path(work.as_str()))
+            .and(body_string_contains("displayname"))
+            .respond_with(
+                ResponseTemplate::new(207)
+                    .set_body_string(property(&work, "<d:displayname>Work</d:displayname>")),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("PROPFIND"))
+            .and(path(work.as_str()))
+            .and(body_string_contains("calendar-color"))
+            .respond_with(ResponseTemplate::new(207).set_body_string(property(
+                &work,
+                "<ical:calendar-color>#FF0000</ical:calendar-color>",
+            )))
+            .mount(&server)
+            .await;
+        // The personal calendar has no properties set
+        Mock::given(method("PROPFIND"))
+            .and(path(personal.as_str()))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = CalDavClient::from_config(&config).await.unwrap();
+
+        let calendars = client.list_calendars().await.unwrap();
+        assert_eq!(calendars.len(), 2);
+        assert_eq!(calendars[0].href, work);
+        assert_eq!(calendars[0].display_name.as_deref(), Some("Work"));
+        assert_eq!(calendars[0].color.as_deref(), Some("#FF0000"));
+        assert_eq!(calendars[1].href, personal);
+        assert_eq!(calendars[1].display_name, None);
+        assert_eq!(calendars[1].color, None);
+    }
+
+    #[test]
+    fn test_parse_ical_duration() {
+        assert_eq!(
+            parse_ical_duration("PT1H30M"),
+            Some(chrono::Duration::minutes(90))
+        );
+        assert_eq!(parse_ical_duration("P1D"), Some(chrono::Duration::days(1)));
+        assert_eq!(
+            parse_ical_duration("-P1W"),
+            Some(-chrono::Duration::weeks(1))
+        );
+        assert_eq!(
+            parse_ical_duration("PT15S"),
+            Some(chrono::Duration::seconds(15))
+        );
+        assert_eq!(parse_ical_duration("20240115T100000Z"), None);
+        assert_eq!(parse_ical_duration("P1H"), None);
+    }
+
+    #[test]
+    fn test_parse_free_busy_and_merge() {
+        let data = b"BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VFREEBUSY\r\n\
+            DTSTART:20240115T000000Z\r\nDTEND:20240116T000000Z\r\n\
+            FREEBUSY;FBTYPE=BUSY:20240115T090000Z/20240115T100000Z,\r\n 20240115T093000Z/PT1H\r\n\
+            FREEBUSY;FBTYPE=FREE:20240115T120000Z/20240115T130000Z\r\n\
+            FREEBUSY;FBTYPE=BUSY-TENTATIVE:20240115T140000Z/PT30M\r\n\
+            FREEBUSY:20240115T163000Z/20240115T170000Z\r\n\
+            END:VFREEBUSY\r\nEND:VCALENDAR\r\n";
+
+        let periods = merge_busy_periods(parse_free_busy(data));
+        let at = |h, m| Utc.with_ymd_and_hms(2024, 1, 15, h, m, 0).unwrap();
+        assert_eq!(
+            periods,
+            vec![
+                BusyPeriod {
+                    start: at(9, 0),
+                    end: at(10, 30),
+                    fb_type: "BUSY".to_string(),
+                },
+                BusyPeriod {
+                    start: at(14, 0),
+                    end: at(14, 30),
+                    fb_type: "BUSY-TENTATIVE".to_string(),
+                },
+                BusyPeriod {
+                    start: at(16, 30),
+                    end: at(17, 0),
+                    fb_type: "BUSY".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_free_busy_queries_each_calendar() {
+        use wiremock::matchers::{body_string_contains, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let home = "/dav/calendars/user/me@example.com/";
+        let work = format!("{}work/", home);
+        let collections = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:href>{}</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype><d:collection/><cal:calendar/></d:resourcetype></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#,
+            work
+        );
+        Mock::given(method("PROPFIND"))
+            .and(path(home))
+            .respond_with(ResponseTemplate::new(207).set_body_string(collections))
+            .mount(&server)
+            .await;
+        Mock::given(method("REPORT"))
+            .and(path(work.as_str()))
+            .and(header("Depth", "1"))
+            .and(body_string_contains("free-busy-query"))
+            .and(body_string_contains(r#"start="20240115T000000Z""#))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "BEGIN:VCALENDAR\r\nBEGIN:VFREEBUSY\r\n\
+                 FREEBUSY:20240115T090000Z/PT1H\r\n\
+                 END:VFREEBUSY\r\nEND:VCALENDAR\r\n",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = CalDavClient::from_config(&config).await.unwrap();
+
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 0, 0, 0).unwrap();
+        let busy = client
+            .free_busy(start, start + chrono::Duration::days(1))
+            .await
+            .unwrap();
+        assert_eq!(busy.len(), 1);
+        assert_eq!(
+            busy[0].start,
+            Utc.with_ymd_and_hms(2024, 1, 15, 9, 0, 0).unwrap()
+        );
+        assert_eq!(
+            busy[0].end,
+            Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap()
+        );
+    }
 }
@@ -2,15 +2,22 @@
 //! CardDAV client implementation for contact operations.
 
 use crate::config::Config;
+use crate::content_line::{
+    escape_text, fold_line, split_content_line, split_escaped, unescape_text, unfold_lines,
+};
+use crate::dav::{parent_collection, put_resource, within};
 use anyhow::{anyhow, Result};
+use futures::{stream, StreamExt};
 use http::Uri;
 use hyper_rustls::HttpsConnectorBuilder;
 use hyper_util::client::legacy::Client;
 use hyper_util::rt::TokioExecutor;
+use jmap_client::{decode_base64, encode_base64};
 use libdav::carddav::{CreateAddressBook, FindAddressBooks, GetAddressBookResources};
-use libdav::dav::{Delete, FoundCollection, PutResource, WebDavClient};
+use libdav::dav::{Delete, FoundCollection, WebDavClient};
 use libdav::FetchedResource;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use tower_http::auth::AddAuthorization;
 
 /// A contact (VCARD)
@@ -40,6 +47,228 @@ pub struct Contact {
     pub birthday: Option<String>,
     /// Website URL
     pub url: Option<String>,
+    /// Postal addresses
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addresses: Vec<Address>,
+    /// Picture or avatar
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub photo: Option<ContactPhoto>,
+    /// Tags (vCard CATEGORIES)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+}
+
+impl Contact {
+    /// Whether the name, email, organization or notes contain `query_lower`
+    fn matches(&self, query_lower: &str) -> bool {
+        [
+            Some(&self.fn_),
+            self.ln.as_ref(),
+            self.email.as_ref(),
+            self.organization.as_ref(),
+            self.notes.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|field| field.to_lowercase().contains(query_lower))
+    }
+
+    /// Whether the contact is tagged with `category`, ignoring case
+    pub fn has_category(&self, category: &str) -> bool {
+        self.categories
+            .iter()
+            .any(|c| c.to_lowercase() == category.to_lowercase())
+    }
+
+    /// Set a single field by its JSON name. An empty value clears optional fields.
+    /// `categories` takes a comma-separated list.
+    pub fn set_field(&mut self, key: &str, value: &str) -> Result<()> {
+        if key == "categories" {
+            self.categories = value
+                .split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(String::from)
+                .collect();
+            return Ok(());
+        }
+        let value = value.to_string();
+        if key == "fn" {
+            if value.is_empty() {
+                return Err(anyhow!("Field 'fn' cannot be empty"));
+            }
+            self.fn_ = value;
+            return Ok(());
+        }
+
+        let field = match key {
+            "ln" => &mut self.ln,
+            "email" => &mut self.email,
+            "phone" => &mut self.phone,
+            "organization" => &mut self.organization,
+            "title" => &mut self.title,
+            "nickname" => &mut self.nickname,
+            "notes" => &mut self.notes,
+            "birthday" => &mut self.birthday,
+            "url" => &mut self.url,
+            _ => return Err(anyhow!("Unknown contact field: {}", key)),
+        };
+        *field = Some(value).filter(|v| !v.is_empty());
+        Ok(())
+    }
+}
+
+/// A contact's picture (vCard PHOTO, RFC 6350 §6.2.4)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContactPhoto {
+    /// An image stored in the vCard; `data` is base64 in JSON
+    Embedded {
+        /// e.g. "image/jpeg"
+        media_type: String,
+        #[serde(with = "base64_bytes")]
+        data: Vec<u8>,
+    },
+    /// A link to the image
+    Uri(String),
+}
+
+impl ContactPhoto {
+    /// Parse a PHOTO value: vCard 3.0 `ENCODING=b` with a TYPE, a vCard 4.0
+    /// `data:` URI, or any other URI
+    fn from_vcard(params: &str, value: &str) -> Option<Self> {
+        let param = |wanted: &str| {
+            params
+                .split(';')
+                .filter_map(|p| p.split_once('='))
+                .find(|(name, _)| name.eq_ignore_ascii_case(wanted))
+                .map(|(_, v)| v.trim_matches('"'))
+        };
+        let compact = |data: &str| {
+            data.chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>()
+        };
+
+        if param("ENCODING")
+            .is_some_and(|e| e.eq_ignore_ascii_case("b") || e.eq_ignore_ascii_case("base64"))
+        {
+            let media_type = match param("TYPE") {
+                Some(t) if t.contains('/') => t.to_ascii_lowercase(),
+                Some(t) => format!("image/{}", t.to_ascii_lowercase()),
+                None => "application/octet-stream".to_string(),
+            };
+            let data = decode_base64(&compact(value)).ok()?;
+            return Some(Self::Embedded { media_type, data });
+        }
+        if let Some((header, data)) = value.strip_prefix("data:").and_then(|v| v.split_once(',')) {
+            let media_type = header.strip_suffix(";base64")?;
+            let data = decode_base64(&compact(data)).ok()?;
+            return Some(Self::Embedded {
+                media_type: media_type.to_string(),
+                data,
+            });
+        }
+        (!value.is_empty()).then(|| Self::Uri(value.to_string()))
+    }
+
+    /// Render as a PHOTO content line (without the line break), folded as
+    /// RFC 6350 §3.2 asks since embedded images are long
+    fn to_vcard(&self) -> String {
+        let line = match self {
+            Self::Embedded { media_type, data } => {
+                let type_ = media_type.strip_prefix("image/").unwrap_or(media_type);
+                format!(
+                    "PHOTO;ENCODING=b;TYPE={}:{}",
+                    type_.to_ascii_uppercase(),
+                    encode_base64(data)
+                )
+            }
+            // URI values are not escaped (RFC 6350 §3.4)
+            Self::Uri(uri) => format!("PHOTO;VALUE=uri:{}", uri),
+        };
+        fold_line(&line)
+    }
+}
+
+/// Serialize bytes as a base64 string
+mod base64_bytes {
+    use jmap_client::{decode_base64, encode_base64};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode_base64(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        decode_base64(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A postal address (vCard ADR, RFC 6350 §6.3.1)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Address {
+    /// Address type from the TYPE parameter, e.g. "home" or "work"
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub po_box: Option<String>,
+    /// Extended address, e.g. apartment or suite
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extended: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub street: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub postal_code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+}
+
+impl Address {
+    /// Parse an ADR value and its parameters (the part of the key after the name)
+    fn from_vcard(params: &str, value: &str) -> Self {
+        let mut parts = split_components(value).into_iter();
+        let mut next = || parts.next().filter(|p| !p.is_empty());
+
+        Self {
+            type_: params
+                .split(';')
+                .filter_map(|p| p.split_once('='))
+                .find(|(name, _)| name.eq_ignore_ascii_case("TYPE"))
+                .map(|(_, v)| v.trim_matches('"').to_ascii_lowercase()),
+            po_box: next(),
+            extended: next(),
+            street: next(),
+            city: next(),
+            region: next(),
+            postal_code: next(),
+            country: next(),
+        }
+    }
+
+    /// Render as an ADR content line (without the line break)
+    fn to_vcard(&self) -> String {
+        let components = [
+            &self.po_box,
+            &self.extended,
+            &self.street,
+            &self.city,
+            &self.region,
+            &self.postal_code,
+            &self.country,
+        ]
+        .map(|c| escape_text(c.as_deref().unwrap_or_default()));
+
+        match &self.type_ {
+            Some(type_) => format!("ADR;TYPE={}:{}", type_, components.join(";")),
+            None => format!("ADR:{}", components.join(";")),
+        }
+    }
 }
 
 /// An address book collection
@@ -72,6 +301,18 @@ impl From<FoundCollection> for AddressBook {
     }
 }
 
+/// How many per-address-book requests are in flight at once
+const ADDRESS_BOOK_REQUEST_CONCURRENCY: usize = 4;
+
+/// A contact found by `search_all_contacts`, with the address book it is in
+#[derive(Debug, Clone, Serialize)]
+pub struct ContactMatch {
+    /// Href of the address book
+    pub book: String,
+    #[serde(flatten)]
+    pub contact: Contact,
+}
+
 /// CardDAV client wrapper
 ///
 /// Wraps the libdav CardDavClient with a simplified API specific to Fastmail.
@@ -87,12 +328,14 @@ pub struct CardDavClient {
 trait CardDavClientInner: Send + Sync {
     async fn find_address_books(&self, home_set: &Uri) -> Result<Vec<FoundCollection>>;
     async fn get_addressbook_resources(&self, href: &str) -> Result<Vec<FetchedResource>>;
+    async fn get_resource(&self, href: &str) -> Result<Option<FetchedResource>>;
     async fn delete_resource(&self, href: &str) -> Result<()>;
     async fn put_resource(
         &self,
         href: &str,
         data: String,
         content_type: &str,
+        if_match: Option<&str>,
     ) -> Result<Option<String>>;
     async fn create_address_book(&self, href: &str, display_name: &str) -> Result<()>;
 }
@@ -110,6 +353,7 @@ where
     C::Future: Send + 'static,
 {
     client: libdav::CardDavClient<C>,
+    timeout: Duration,
 }
 
 #[async_trait::async_trait]
@@ -126,20 +370,36 @@ where
     C::Future: Send + 'static,
 {
     async fn find_address_books(&self, home_set: &Uri) -> Result<Vec<FoundCollection>> {
-        let response = self.client.request(FindAddressBooks::new(home_set)).await?;
+        let response = within(
+            self.timeout,
+            self.client.request(FindAddressBooks::new(home_set)),
+        )
+        .await?;
         Ok(response.addressbooks)
     }
 
     async fn get_addressbook_resources(&self, href: &str) -> Result<Vec<FetchedResource>> {
-        let response = self
-            .client
-            .request(GetAddressBookResources::new(href))
-            .await?;
+        let response = within(
+            self.timeout,
+            self.client.request(GetAddressBookResources::new(href)),
+        )
+        .await?;
         Ok(response.resources)
     }
 
+    async fn get_resource(&self, href: &str) -> Result<Option<FetchedResource>> {
+        // addressbook-multiget on the parent collection, restricted to this one href
+        let response = within(
+            self.timeout,
+            self.client
+                .request(GetAddressBookResources::new(parent_collection(href)).with_hrefs([href])),
+        )
+        .await?;
+        Ok(response.resources.into_iter().next())
+    }
+
     async fn delete_resource(&self, href: &str) -> Result<()> {
-        self.client.request(Delete::new(href).force()).await?;
+        within(self.timeout, self.client.request(Delete::new(href).force())).await?;
         Ok(())
     }
 
@@ -148,17 +408,15 @@ where
         href: &str,
         data: String,
         content_type: &str,
+        if_match: Option<&str>,
     ) -> Result<Option<String>> {
-        let response = self
-            .client
-            .request(PutResource::new(href).create(data, content_type))
-            .await?;
-        Ok(response.etag)
+        let put = put_resource(&self.client, href, data, content_type, if_match);
+        within(self.timeout, put).await
     }
 
     async fn create_address_book(&self, href: &str, display_name: &str) -> Result<()> {
         let create_address_book = CreateAddressBook::new(href).with_display_name(display_name);
-        self.client.request(create_address_book).await?;
+        within(self.timeout, self.client.request(create_address_book)).await?;
         Ok(())
     }
 }
@@ -181,8 +439,7 @@ impl CardDavClient {
         // Build HTTP client with DAV Basic auth
         // DAV endpoints use HTTP Basic Auth with email as username and app password as password
         let dav_username = config.get_dav_username()?;
-        let dav_password = config.dav_password.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("DAV password not set. Please set FASTMAIL_DAV_PASSWORD environment variable with your Fastmail app password. Generate one at: https://www.fastmail.com/settings/passwords"))?;
+        let dav_password = config.get_dav_password()?;
         let https_client = Client::builder(TokioExecutor::new()).build(https_connector);
         let https_client = AddAuthorization::basic(https_client, dav_username, dav_password);
 
@@ -191,7 +448,10 @@ impl CardDavClient {
         let webdav_client = WebDavClient::new(uri, https_client);
         let client = libdav::CardDavClient::new(webdav_client);
 
-        let inner: Box<dyn CardDavClientInner> = Box::new(CardDavClientInnerImpl { client });
+        let inner: Box<dyn CardDavClientInner> = Box::new(CardDavClientInnerImpl {
+            client,
+            timeout: config.get_timeout(),
+        });
 
         Ok(Self {
             carddav: inner,
@@ -260,18 +520,86 @@ impl CardDavClient {
 
     /// Get a specific contact by href
     pub async fn get_contact(&self, contact_href: &str) -> Result<Contact> {
-        let resources = self.carddav.get_addressbook_resources(contact_href).await?;
+        let (contact, _) = self.get_contact_with_etag(contact_href).await?;
+        Ok(contact)
+    }
 
-        resources
-            .into_iter()
-            .find(|r| r.href == contact_href || r.href.ends_with(contact_href))
-            .and_then(|r| r.content.ok())
-            .and_then(|c| Self::parse_vcard(c.data.as_bytes()))
-            .ok_or_else(|| anyhow!("Contact not found or could not be parsed: {}", contact_href))
+    /// Get a specific contact by href along with its current ETag
+    pub async fn get_contact_with_etag(&self, contact_href: &str) -> Result<(Contact, String)> {
+        let resource = self
+            .carddav
+            .get_resource(contact_href)
+            .await?
+            .ok_or_else(|| anyhow!("Contact not found: {}", contact_href))?;
+        let content = resource
+            .content
+            .map_err(|status| anyhow!("Failed to fetch contact {}: {}", contact_href, status))?;
+        let contact = Self::parse_vcard(content.data.as_bytes())
+            .ok_or_else(|| anyhow!("Contact could not be parsed: {}", contact_href))?;
+
+        Ok((contact, content.etag))
     }
 
-    /// Create or update a contact in an address book
-    pub async fn put_contact(&self, addressbook_href: &str, contact: &Contact) -> Result<String> {
+    /// Fetch a contact's vCard exactly as stored, with its ETag
+    pub async fn get_vcard(&self, contact_href: &str) -> Result<(String, String)> {
+        let resource = self
+            .carddav
+            .get_resource(contact_href)
+            .await?
+            .ok_or_else(|| anyhow!("Contact not found: {}", contact_href))?;
+        let content = resource
+            .content
+            .map_err(|status| anyhow!("Failed to fetch contact {}: {}", contact_href, status))?;
+        Ok((content.data, content.etag))
+    }
+
+    /// Upload a vCard unchanged, keeping properties `Contact` does not model.
+    /// It is stored as `<UID>.vcf`, or under a fresh UUID when it has no UID.
+    /// Returns the new contact's href and ETag.
+    pub async fn import_vcard(
+        &self,
+        addressbook_href: &str,
+        vcard: &str,
+    ) -> Result<(String, String)> {
+        let cards = vcard
+            .lines()
+            .filter(|line| line.trim().eq_ignore_ascii_case("BEGIN:VCARD"))
+            .count();
+        if cards != 1 {
+            return Err(anyhow!(
+                "Expected exactly one vCard, found {}; import them one at a time",
+                cards
+            ));
+        }
+
+        let name = vcard_uid(vcard)
+            .map(|uid| {
+                uid.chars()
+                    .map(|c| match c {
+                        'A'..='Z' | 'a'..='z' | '0'..='9' | '.' | '_' | '-' => c,
+                        _ => '-',
+                    })
+                    .collect()
+            })
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let contact_href = format!("{}/{}.vcf", addressbook_href.trim_end_matches('/'), name);
+
+        let etag = self
+            .carddav
+            .put_resource(&contact_href, vcard.to_string(), "text/vcard", None)
+            .await?;
+
+        Ok((contact_href, etag.unwrap_or_default()))
+    }
+
+    /// Create a contact in an address book, or with `if_match` replace it if
+    /// it still has that ETag
+    pub async fn put_contact(
+        &self,
+        addressbook_href: &str,
+        contact: &Contact,
+        if_match: Option<&str>,
+    ) -> Result<String> {
         // Generate href from UID
         let contact_href = format!(
             "{}/{}.vcf",
@@ -282,12 +610,95 @@ impl CardDavClient {
 
         let etag = self
             .carddav
-            .put_resource(&contact_href, vcard, "text/vcard")
+            .put_resource(&contact_href, vcard, "text/vcard", if_match)
             .await?;
 
         Ok(etag.unwrap_or_default())
     }
 
+    /// Replace a contact's vCard, failing if it changed since `etag` was fetched.
+    /// Returns the new ETag.
+    pub async fn update_vcard(
+        &self,
+        contact_href: &str,
+        vcard: &str,
+        etag: &str,
+    ) -> Result<String> {
+        let etag = self
+            .carddav
+            .put_resource(contact_href, vcard.to_string(), "text/vcard", Some(etag))
+            .await?;
+
+        Ok(etag.unwrap_or_default())
+    }
+
+    /// Set fields (by JSON name, as `Contact::set_field` takes them) in a stored
+    /// vCard. Only the lines for those properties change, so properties `Contact`
+    /// does not model (X-*, a second EMAIL, ...) survive. Returns the patched
+    /// vCard and the contact it now describes.
+    pub fn patch_vcard(vcard: &str, fields: &[(&str, &str)]) -> Result<(String, Contact)> {
+        let mut contact = Self::parse_vcard(vcard.as_bytes())
+            .ok_or_else(|| anyhow!("Contact could not be parsed"))?;
+        let mut lines: Vec<String> = unfold_lines(vcard)
+            .into_iter()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+
+        for &(key, value) in fields {
+            contact.set_field(key, value)?;
+            let text = |value: &Option<String>| value.as_deref().map(escape_text);
+            match key {
+                "fn" => set_vcard_property(&mut lines, "FN", Some(escape_text(&contact.fn_))),
+                "ln" => {
+                    // Only the family name component of N changes
+                    let ln = escape_text(contact.ln.as_deref().unwrap_or_default());
+                    let n = match find_vcard_property(&lines, "N").last() {
+                        Some(&i) => {
+                            let (_, old) = split_content_line(&lines[i]).unwrap_or_default();
+                            let mut parts: Vec<String> = split_components(old)
+                                .iter()
+                                .map(|c| escape_text(c))
+                                .collect();
+                            parts[0] = ln;
+                            parts.join(";")
+                        }
+                        None => format!("{};{}", ln, escape_text(&contact.fn_)),
+                    };
+                    set_vcard_property(&mut lines, "N", Some(n))
+                }
+                "email" => set_vcard_property(&mut lines, "EMAIL", text(&contact.email)),
+                "phone" => set_vcard_property(&mut lines, "TEL", text(&contact.phone)),
+                "organization" => {
+                    set_vcard_property(&mut lines, "ORG", text(&contact.organization))
+                }
+                "title" => set_vcard_property(&mut lines, "TITLE", text(&contact.title)),
+                "nickname" => set_vcard_property(&mut lines, "NICKNAME", text(&contact.nickname)),
+                "notes" => set_vcard_property(&mut lines, "NOTE", text(&contact.notes)),
+                "birthday" => set_vcard_property(&mut lines, "BDAY", text(&contact.birthday)),
+                // URI values are not escaped (RFC 6350 §3.4)
+                "url" => set_vcard_property(&mut lines, "URL", contact.url.clone()),
+                "categories" => {
+                    // The parser merges every CATEGORIES line, so they are replaced as one
+                    for i in find_vcard_property(&lines, "CATEGORIES")
+                        .into_iter()
+                        .rev()
+                        .skip(1)
+                    {
+                        lines.remove(i);
+                    }
+                    let categories: Vec<String> =
+                        contact.categories.iter().map(|c| escape_text(c)).collect();
+                    let value = (!categories.is_empty()).then(|| categories.join(","));
+                    set_vcard_property(&mut lines, "CATEGORIES", value)
+                }
+                _ => return Err(anyhow!("Unknown contact field: {}", key)),
+            }?;
+        }
+
+        let vcard = lines.iter().map(|line| fold_line(line) + "\r\n").collect();
+        Ok((vcard, contact))
+    }
+
     /// Delete a contact
     pub async fn delete_contact(&self, contact_href: &str) -> Result<()> {
         self.carddav.delete_resource(contact_href).await
@@ -304,26 +715,38 @@ impl CardDavClient {
 
         let filtered = all_contacts
             .into_iter()
-            .filter(|c| {
-                c.fn_.to_lowercase().contains(&query_lower)
-                    || c.ln
-                        .as_ref()
-                        .is_some_and(|ln| ln.to_lowercase().contains(&query_lower))
-                    || c.email
-                        .as_ref()
-                        .is_some_and(|e| e.to_lowercase().contains(&query_lower))
-                    || c.organization
-                        .as_ref()
-                        .is_some_and(|o| o.to_lowercase().contains(&query_lower))
-                    || c.notes
-                        .as_ref()
-                        .is_some_and(|n| n.to_lowercase().contains(&query_lower))
-            })
+            .filter(|c| c.matches(&query_lower))
             .collect();
 
         Ok(filtered)
     }
 
+    /// Search every address book, a few at a time, in the order they are listed
+    pub async fn search_all_contacts(&self, query: &str) -> Result<Vec<ContactMatch>> {
+        let books = self.list_address_books().await?;
+
+        let results: Vec<Result<Vec<ContactMatch>>> = stream::iter(books)
+            .map(|book| async move {
+                let contacts = self.search_contacts(&book.href, query).await?;
+                Ok(contacts
+                    .into_iter()
+                    .map(|contact| ContactMatch {
+                        book: book.href.clone(),
+                        contact,
+                    })
+                    .collect())
+            })
+            .buffered(ADDRESS_BOOK_REQUEST_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut matches = Vec::new();
+        for result in results {
+            matches.extend(result?);
+        }
+        Ok(matches)
+    }
+
     /// Parse a vCard from bytes (simplified vCard 3.0 MVP implementation)
     fn parse_vcard(data: &[u8]) -> Option<Contact> {
         let content = String::from_utf8(data.to_vec()).ok()?;
@@ -340,10 +763,13 @@ impl CardDavClient {
         let mut notes = None;
         let mut birthday = None;
         let mut url = None;
+        let mut addresses = Vec::new();
+        let mut photo = None;
+        let mut categories = Vec::new();
 
         let mut in_vcard = false;
 
-        for line in content.lines() {
+        for line in unfold_lines(&content) {
             let line = line.trim();
 
             if line.starts_with("BEGIN:VCARD") {
@@ -359,30 +785,39 @@ impl CardDavClient {
                 continue;
             }
 
-            // Simple key-value parsing (ignoring folded lines for MVP)
+            // Simple key-value parsing; parameters follow the name after ';'
             if let Some((key, value)) = line.split_once(':') {
-                match key {
-                    "UID" => uid = Some(value.to_string()),
-                    "FN" => fn_ = Some(value.to_string()),
+                let (name, params) = key.split_once(';').unwrap_or((key, ""));
+                match name {
+                    "UID" => uid = Some(unescape_text(value)),
+                    "FN" => fn_ = Some(unescape_text(value)),
                     "N" => {
                         // N field: Family;Given;Additional;Prefix;Suffix
-                        let parts: Vec<&str> = value.split(';').collect();
+                        let parts = split_components(value);
                         if parts.len() >= 2 {
                             ln = if !parts[0].is_empty() {
-                                Some(parts[0].to_string())
+                                Some(parts[0].clone())
                             } else {
                                 None
                             };
                         }
                     }
-                    "EMAIL" => email = Some(value.to_string()),
-                    "TEL" => phone = Some(value.to_string()),
-                    "ORG" => organization = Some(value.to_string()),
-                    "TITLE" => title = Some(value.to_string()),
-                    "NICKNAME" => nickname = Some(value.to_string()),
-                    "NOTE" => notes = Some(value.to_string()),
-                    "BDAY" => birthday = Some(value.to_string()),
+                    "EMAIL" => email = Some(unescape_text(value)),
+                    "TEL" => phone = Some(unescape_text(value)),
+                    "ORG" => organization = Some(unescape_text(value)),
+                    "TITLE" => title = Some(unescape_text(value)),
+                    "NICKNAME" => nickname = Some(unescape_text(value)),
+                    "NOTE" => notes = Some(unescape_text(value)),
+                    "BDAY" => birthday = Some(unescape_text(value)),
                     "URL" => url = Some(value.to_string()),
+                    "ADR" => addresses.push(Address::from_vcard(params, value)),
+                    "PHOTO" => photo = ContactPhoto::from_vcard(params, value),
+                    "CATEGORIES" => categories.extend(
+                        split_escaped(value, ',')
+                            .into_iter()
+                            .map(|c| c.trim().to_string())
+                            .filter(|c| !c.is_empty()),
+                    ),
                     _ => {}
                 }
             }
@@ -404,6 +839,9 @@ impl CardDavClient {
             notes,
             birthday,
             url,
+            addresses,
+            photo,
+            categories,
         })
     }
 
@@ -411,54 +849,129 @@ impl CardDavClient {
     fn serialize_vcard(contact: &Contact) -> Result<String> {
         let mut vcard = String::from("BEGIN:VCARD\r\nVERSION:3.0\r\n");
 
-        vcard.push_str(&format!("UID:{}\r\n", contact.uid));
-        vcard.push_str(&format!("FN:{}\r\n", contact.fn_));
+        vcard.push_str(&format!("UID:{}\r\n", escape_text(&contact.uid)));
+        vcard.push_str(&format!("FN:{}\r\n", escape_text(&contact.fn_)));
 
         if let Some(ref ln) = contact.ln {
             // N field: Family;Given
-            vcard.push_str(&format!("N:{};{}\r\n", ln, contact.fn_));
+            vcard.push_str(&format!(
+                "N:{};{}\r\n",
+                escape_text(ln),
+                escape_text(&contact.fn_)
+            ));
         } else {
-            vcard.push_str(&format!("N:;{}\r\n", contact.fn_));
+            vcard.push_str(&format!("N:;{}\r\n", escape_text(&contact.fn_)));
         }
 
         if let Some(ref email) = contact.email {
-            vcard.push_str(&format!("EMAIL:{}\r\n", email));
+            vcard.push_str(&format!("EMAIL:{}\r\n", escape_text(email)));
         }
 
         if let Some(ref phone) = contact.phone {
-            vcard.push_str(&format!("TEL:{}\r\n", phone));
+            vcard.push_str(&format!("TEL:{}\r\n", escape_text(phone)));
         }
 
         if let Some(ref org) = contact.organization {
-            vcard.push_str(&format!("ORG:{}\r\n", org));
+            vcard.push_str(&format!("ORG:{}\r\n", escape_text(org)));
         }
 
         if let Some(ref title) = contact.title {
-            vcard.push_str(&format!("TITLE:{}\r\n", title));
+            vcard.push_str(&format!("TITLE:{}\r\n", escape_text(title)));
         }
 
         if let Some(ref nickname) = contact.nickname {
-            vcard.push_str(&format!("NICKNAME:{}\r\n", nickname));
+            vcard.push_str(&format!("NICKNAME:{}\r\n", escape_text(nickname)));
         }
 
         if let Some(ref notes) = contact.notes {
-            vcard.push_str(&format!("NOTE:{}\r\n", notes));
+            vcard.push_str(&format!("NOTE:{}\r\n", escape_text(notes)));
         }
 
         if let Some(ref birthday) = contact.birthday {
-            vcard.push_str(&format!("BDAY:{}\r\n", birthday));
+            vcard.push_str(&format!("BDAY:{}\r\n", escape_text(birthday)));
         }
 
         if let Some(ref url) = contact.url {
+            // URI values are not escaped (RFC 6350 §3.4)
             vcard.push_str(&format!("URL:{}\r\n", url));
         }
 
+        for address in &contact.addresses {
+            vcard.push_str(&address.to_vcard());
+            vcard.push_str("\r\n");
+        }
+
+        if !contact.categories.is_empty() {
+            let categories: Vec<String> =
+                contact.categories.iter().map(|c| escape_text(c)).collect();
+            vcard.push_str(&format!("CATEGORIES:{}\r\n", categories.join(",")));
+        }
+
+        if let Some(ref photo) = contact.photo {
+            vcard.push_str(&photo.to_vcard());
+            vcard.push_str("\r\n");
+        }
+
         vcard.push_str("END:VCARD\r\n");
 
         Ok(vcard)
     }
 }
 
+/// Split a structured value on unescaped ';' and unescape each component
+fn split_components(value: &str) -> Vec<String> {
+    split_escaped(value, ';')
+}
+
+/// Indexes of the content lines for `name`, ignoring case and any group prefix
+fn find_vcard_property(lines: &[String], name: &str) -> Vec<usize> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| {
+            split_content_line(line)
+                .map(|(key, _)| key.split(';').next().unwrap_or_default())
+                .map(|key| key.split_once('.').map_or(key, |(_, name)| name))
+                .is_some_and(|key| key.eq_ignore_ascii_case(name))
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Replace the value of the last `name` line, the one `parse_vcard` reads,
+/// keeping its group and parameters. `None` removes that line; a property the
+/// card lacks is added before END:VCARD.
+fn set_vcard_property(lines: &mut Vec<String>, name: &str, value: Option<String>) -> Result<()> {
+    match (find_vcard_property(lines, name).last(), value) {
+        (Some(&i), Some(value)) => {
+            let (key, _) = split_content_line(&lines[i]).unwrap_or_default();
+            lines[i] = format!("{}:{}", key, value);
+        }
+        (Some(&i), None) => {
+            lines.remove(i);
+        }
+        (None, Some(value)) => {
+            let end = lines
+                .iter()
+                .rposition(|line| line.trim().eq_ignore_ascii_case("END:VCARD"))
+                .ok_or_else(|| anyhow!("vCard has no END:VCARD"))?;
+            lines.insert(end, format!("{}:{}", name, value));
+        }
+        (None, None) => {}
+    }
+    Ok(())
+}
+
+/// The UID property of a vCard, if it has a non-empty one
+fn vcard_uid(vcard: &str) -> Option<String> {
+    unfold_lines(vcard).into_iter().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        let name = name.split(';').next()?;
+        (name.eq_ignore_ascii_case("UID") && !value.trim().is_empty())
+            .then(|| value.trim().to_string())
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -477,6 +990,9 @@ mod tests {
             notes: Some("Test contact".to_string()),
             birthday: Some("1990-01-15".to_string()),
             url: Some("https://example.com".to_string()),
+            addresses: Vec::new(),
+            photo: None,
+            categories: Vec::new(),
         };
 
         let vcard = CardDavClient::serialize_vcard(&contact).unwrap();
@@ -534,6 +1050,231 @@ END:VCARD";
         assert!(contact.url.is_none());
     }
 
+    #[test]
+    fn test_parse_vcard_unfolds_long_note() {
+        let vcard = b"BEGIN:VCARD\r\nVERSION:3.0\r\nUID:folded\r\nFN:Jane Doe\r\n\
+NOTE:Met at the 2024 conference in Lisbon; interested in the hosted \r\n \
+mail migration pilot and asked for a follow-up call once the pricing \r\n\t\
+sheet is ready.\r\nEMAIL:jane@example.com\r\nEND:VCARD\r\n";
+
+        let contact = CardDavClient::parse_vcard(vcard).unwrap();
+
+        assert_eq!(
+            contact.notes.as_deref(),
+            Some(
+                "Met at the 2024 conference in Lisbon; interested in the hosted \
+                 mail migration pilot and asked for a follow-up call once the pricing \
+                 sheet is ready."
+            )
+        );
+        assert_eq!(contact.email.as_deref(), Some("jane@example.com"));
+    }
+
+    #[test]
+    fn test_parse_vcard_address() {
+        let vcard = b"BEGIN:VCARD\r\nVERSION:3.0\r\nUID:adr\r\nFN:Jane Doe\r\n\
+ADR;TYPE=WORK:;Suite 400;1 Main St\\, Building B;Springfield;IL;62701;USA\r\n\
+ADR:PO Box 12;;;Lisbon;;1000-001;Portugal\r\nEND:VCARD\r\n";
+
+        let contact = CardDavClient::parse_vcard(vcard).unwrap();
+
+        assert_eq!(
+            contact.addresses,
+            vec![
+                Address {
+                    type_: Some("work".to_string()),
+                    po_box: None,
+                    extended: Some("Suite 400".to_string()),
+                    street: Some("1 Main St, Building B".to_string()),
+                    city: Some("Springfield".to_string()),
+                    region: Some("IL".to_string()),
+                    postal_code: Some("62701".to_string()),
+                    country: Some("USA".to_string()),
+                },
+                Address {
+                    po_box: Some("PO Box 12".to_string()),
+                    city: Some("Lisbon".to_string()),
+                    postal_code: Some("1000-001".to_string()),
+                    country: Some("Portugal".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_address_round_trip() {
+        let home = Address {
+            type_: Some("home".to_string()),
+            street: Some("12 Rue de la Paix; 3e".to_string()),
+            city: Some("Paris".to_string()),
+            postal_code: Some("75002".to_string()),
+            country: Some("France".to_string()),
+            ..Default::default()
+        };
+        let contact = Contact {
+            uid: "adr-round-trip".to_string(),
+            fn_: "Jean".to_string(),
+            ln: None,
+            email: None,
+            phone: None,
+            organization: None,
+            title: None,
+            nickname: None,
+            notes: None,
+            birthday: None,
+            url: None,
+            addresses: vec![home.clone(), Address::default()],
+            photo: None,
+            categories: Vec::new(),
+        };
+
+        let vcard = CardDavClient::serialize_vcard(&contact).unwrap();
+        assert!(vcard.contains("ADR;TYPE=home:;;12 Rue de la Paix\\; 3e;Paris;;75002;France\r\n"));
+        assert!(vcard.contains("ADR:;;;;;;\r\n"));
+
+        let parsed = CardDavClient::parse_vcard(vcard.as_bytes()).unwrap();
+        assert_eq!(parsed.addresses, vec![home, Address::default()]);
+    }
+
+    #[test]
+    fn test_photo_round_trip() {
+        let vcard = "BEGIN:VCARD\r\nVERSION:3.0\r\nUID:p1\r\nFN:Ada\r\n\
+            PHOTO;ENCODING=b;TYPE=JPEG:/9j/4AAQ\r\n SkZJRg==\r\nEND:VCARD\r\n";
+        let mut contact = CardDavClient::parse_vcard(vcard.as_bytes()).unwrap();
+        let jpeg = b"\xff\xd8\xff\xe0\x00\x10JFIF".to_vec();
+        assert_eq!(
+            contact.photo,
+            Some(ContactPhoto::Embedded {
+                media_type: "image/jpeg".to_string(),
+                data: jpeg,
+            })
+        );
+        let json = serde_json::to_value(&contact).unwrap();
+        assert_eq!(json["photo"]["embedded"]["data"], "/9j/4AAQSkZJRg==");
+
+        // Long images are folded and still read back the same
+        contact.photo = Some(ContactPhoto::Embedded {
+            media_type: "image/png".to_string(),
+            data: (0..=255).collect(),
+        });
+        let serialized = CardDavClient::serialize_vcard(&contact).unwrap();
+        assert!(serialized.contains("PHOTO;ENCODING=b;TYPE=PNG:"));
+        assert!(serialized.lines().all(|l| l.len() <= 75));
+        let parsed = CardDavClient::parse_vcard(serialized.as_bytes()).unwrap();
+        assert_eq!(parsed.photo, contact.photo);
+
+        let data_uri = "BEGIN:VCARD\r\nUID:p2\r\nFN:Bo\r\n\
+            PHOTO:data:image/gif;base64,R0lG\r\nEND:VCARD\r\n";
+        let parsed = CardDavClient::parse_vcard(data_uri.as_bytes()).unwrap();
+        assert_eq!(
+            parsed.photo,
+            Some(ContactPhoto::Embedded {
+                media_type: "image/gif".to_string(),
+                data: b"GIF".to_vec(),
+            })
+        );
+
+        let link = "BEGIN:VCARD\r\nUID:p3\r\nFN:Cy\r\n\
+            PHOTO;VALUE=uri:https://example.com/a.png\r\nEND:VCARD\r\n";
+        let parsed = CardDavClient::parse_vcard(link.as_bytes()).unwrap();
+        let uri = ContactPhoto::Uri("https://example.com/a.png".to_string());
+        assert_eq!(uri.to_vcard(), "PHOTO;VALUE=uri:https://example.com/a.png");
+        assert_eq!(parsed.photo, Some(uri));
+    }
+
+    #[test]
+    fn test_serialize_vcard_escapes_values() {
+        let contact = Contact {
+            uid: "escaped".to_string(),
+            fn_: "John".to_string(),
+            ln: Some("Doe, Jr.".to_string()),
+            email: None,
+            phone: None,
+            organization: Some("Acme; R&D".to_string()),
+            title: None,
+            nickname: None,
+            notes: Some("First line\nSecond line with C:\\path".to_string()),
+            birthday: None,
+            url: Some("https://example.com/a,b".to_string()),
+            addresses: Vec::new(),
+            photo: None,
+            categories: Vec::new(),
+        };
+
+        let vcard = CardDavClient::serialize_vcard(&contact).unwrap();
+        assert!(vcard.contains("N:Doe\\, Jr.;John\r\n"));
+        assert!(vcard.contains("ORG:Acme\\; R&D\r\n"));
+        assert!(vcard.contains("NOTE:First line\\nSecond line with C:\\\\path\r\n"));
+        assert!(vcard.contains("URL:https://example.com/a,b\r\n"));
+
+        let parsed = CardDavClient::parse_vcard(vcard.as_bytes()).unwrap();
+        assert_eq!(parsed.ln, contact.ln);
+        assert_eq!(parsed.organization, contact.organization);
+        assert_eq!(parsed.notes, contact.notes);
+        assert_eq!(parsed.url, contact.url);
+    }
+
+    #[test]
+    fn test_categories_split_on_unescaped_commas() {
+        let vcard = "BEGIN:VCARD\r\nUID:c1\r\nFN:Ada\r\n\
+            CATEGORIES:Friends,Work\r\nCATEGORIES:R\\,D, Chess\r\nEND:VCARD\r\n";
+        let mut contact = CardDavClient::parse_vcard(vcard.as_bytes()).unwrap();
+        assert_eq!(contact.categories, vec!["Friends", "Work", "R,D", "Chess"]);
+        assert!(contact.has_category("work") && !contact.has_category("Wor"));
+
+        let serialized = CardDavClient::serialize_vcard(&contact).unwrap();
+        assert!(serialized.contains("CATEGORIES:Friends,Work,R\\,D,Chess\r\n"));
+        let parsed = CardDavClient::parse_vcard(serialized.as_bytes()).unwrap();
+        assert_eq!(parsed.categories, contact.categories);
+
+        contact.set_field("categories", "Family, ,Club").unwrap();
+        assert_eq!(contact.categories, vec!["Family", "Club"]);
+    }
+
+    #[test]
+    fn test_patch_vcard_keeps_unmodelled_properties() {
+        let photo = format!("PHOTO;ENCODING=b;TYPE=PNG:{}", "iVBORw0KGgo".repeat(12));
+        let vcard = format!(
+            "BEGIN:VCARD\r\nVERSION:3.0\r\nUID:c1\r\nFN:Ada\r\nN:Lovelace;Ada;;;\r\n\
+             EMAIL;TYPE=home:ada@home.example\r\nitem1.EMAIL;TYPE=work:ada@work.example\r\n\
+             X-SOCIALPROFILE;TYPE=twitter:ada\r\nCATEGORIES:Friends\r\nCATEGORIES:Work\r\n\
+             NOTE:old\r\n{}\r\nEND:VCARD\r\n",
+            fold_line(&photo)
+        );
+
+        let (patched, contact) = CardDavClient::patch_vcard(
+            &vcard,
+            &[
+                ("email", "ada@new.example"),
+                ("ln", "Byron"),
+                ("notes", ""),
+                ("phone", "+44 20 7946 0000"),
+                ("categories", "Family,Chess"),
+            ],
+        )
+        .unwrap();
+
+        let lines = unfold_lines(&patched);
+        assert!(lines.contains(&"X-SOCIALPROFILE;TYPE=twitter:ada".to_string()));
+        assert!(lines.contains(&"EMAIL;TYPE=home:ada@home.example".to_string()));
+        assert!(lines.contains(&"item1.EMAIL;TYPE=work:ada@new.example".to_string()));
+        assert!(lines.contains(&"N:Byron;Ada;;;".to_string()));
+        assert!(lines.contains(&"CATEGORIES:Family,Chess".to_string()));
+        assert!(lines.contains(&photo));
+        assert!(!patched.contains("NOTE"));
+        let end = lines.iter().position(|line| line == "END:VCARD").unwrap();
+        assert_eq!(lines[end - 1], "TEL:+44 20 7946 0000");
+        assert!(patched.split("\r\n").all(|line| line.len() <= 75));
+
+        assert_eq!(contact.ln.as_deref(), Some("Byron"));
+        assert_eq!(contact.notes, None);
+        assert!(matches!(contact.photo, Some(ContactPhoto::Embedded { .. })));
+
+        let err = CardDavClient::patch_vcard(&vcard, &[("fn", "")]).unwrap_err();
+        assert!(err.to_string().contains("cannot be empty"));
+    }
+
     #[test]
     fn test_serialize_vcard_minimal() {
         let contact = Contact {
@@ -548,6 +1289,9 @@ END:VCARD";
             notes: None,
             birthday: None,
             url: None,
+            addresses: Vec::new(),
+            photo: None,
+            categories: Vec::new(),
         };
 
         let vcard = CardDavClient::serialize_vcard(&contact).unwrap();
@@ -558,4 +1302,203 @@ END:VCARD";
         assert!(vcard.contains("FN:Jane Doe"));
         assert!(vcard.contains("END:VCARD"));
     }
+
+    #[test]
+    fn test_set_field() {
+        let mut contact = Contact {
+            uid: "merge".to_string(),
+            fn_: "John".to_string(),
+            ln: Some("Doe".to_string()),
+            email: Some("john@example.com".to_string()),
+            phone: None,
+            organization: Some("Acme".to_string()),
+            title: None,
+            nickname: None,
+            notes: None,
+            birthday: None,
+            url: None,
+            addresses: vec![Address {
+                city: Some("Springfield".to_string()),
+                ..Default::default()
+            }],
+            photo: None,
+            categories: Vec::new(),
+        };
+
+        contact.set_field("email", "jdoe@example.com").unwrap();
+        contact.set_field("phone", "+1-555-0100").unwrap();
+        contact.set_field("organization", "").unwrap();
+        assert!(contact.set_field("fn", "").is_err());
+        assert!(contact.set_field("nope", "x").is_err());
+
+        assert_eq!(contact.fn_, "John");
+        assert_eq!(contact.ln.as_deref(), Some("Doe"));
+        assert_eq!(contact.email.as_deref(), Some("jdoe@example.com"));
+        assert_eq!(contact.phone.as_deref(), Some("+1-555-0100"));
+        assert_eq!(contact.organization, None);
+        assert_eq!(contact.addresses.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_contact_fetches_single_resource() {
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let book = "/dav/addressbooks/user/me@example.com/Default/";
+        let href = format!("{}abc.vcf", book);
+        let multistatus = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:card="urn:ietf:params:xml:ns:carddav">
+  <d:response>
+    <d:href>{}</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:getetag>"etag-1"</d:getetag>
+        <card:address-data>BEGIN:VCARD
+VERSION:3.0
+UID:abc
+FN:Jane
+EMAIL:jane@example.com
+END:VCARD
+</card:address-data>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#,
+            href
+        );
+        Mock::given(method("REPORT"))
+            .and(path(book))
+            .and(body_string_contains("addressbook-multiget"))
+            .and(body_string_contains(href.as_str()))
+            .respond_with(ResponseTemplate::new(207).set_body_string(multistatus))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = CardDavClient::from_config(&config).await.unwrap();
+
+        let (contact, etag) = client.get_contact_with_etag(&href).await.unwrap();
+        assert_eq!(contact.uid, "abc");
+        assert_eq!(contact.email.as_deref(), Some("jane@example.com"));
+        assert_eq!(etag, "\"etag-1\"");
+    }
+
+    #[tokio::test]
+    async fn test_search_all_contacts_annotates_the_book() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let home = "/dav/addressbooks/user/me@example.com/";
+        let books: Vec<String> = ["personal", "work"]
+            .iter()
+            .map(|name| format!("{}{}/", home, name))
+            .collect();
+        let collections = books
+            .iter()
+            .map(|href| {
+                format!(
+                    r#"<d:response><d:href>{}</d:href><d:propstat>
+      <d:prop><d:resourcetype><d:collection/><card:addressbook/></d:resourcetype></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>"#,
+                    href
+                )
+            })
+            .collect::<String>();
+        Mock::given(method("PROPFIND"))
+            .and(path(home))
+            .respond_with(ResponseTemplate::new(207).set_body_string(format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:card="urn:ietf:params:xml:ns:carddav">{}</d:multistatus>"#,
+                collections
+            )))
+            .mount(&server)
+            .await;
+        for (book, name) in books.iter().zip(["Jane Home", "Jane Work"]) {
+            let multistatus = format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:card="urn:ietf:params:xml:ns:carddav">
+  <d:response>
+    <d:href>{}c.vcf</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:getetag>"e"</d:getetag>
+        <card:address-data>BEGIN:VCARD
+VERSION:3.0
+UID:{}
+FN:{}
+END:VCARD
+</card:address-data>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#,
+                book, name, name
+            );
+            Mock::given(method("REPORT"))
+                .and(path(book.as_str()))
+                .respond_with(ResponseTemplate::new(207).set_body_string(multistatus))
+                .mount(&server)
+                .await;
+        }
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = CardDavClient::from_config(&config).await.unwrap();
+
+        let matches = client.search_all_contacts("jane").await.unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].book, books[0]);
+        assert_eq!(matches[1].contact.fn_, "Jane Work");
+
+        let matches = client.search_all_contacts("WORK").await.unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].book, books[1]);
+    }
+
+    #[tokio::test]
+    async fn test_import_vcard_uploads_it_verbatim() {
+        use wiremock::matchers::{body_string, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let book = "/dav/addressbooks/user/me@example.com/Default/";
+        let vcard = "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:urn:uuid:1234\r\nFN:Jane\r\n\
+                     CATEGORIES:friends,climbing\r\nPHOTO:https://example.com/jane.jpg\r\n\
+                     END:VCARD\r\n";
+        Mock::given(method("PUT"))
+            .and(path(format!("{}urn-uuid-1234.vcf", book)))
+            .and(header("If-None-Match", "*"))
+            .and(body_string(vcard))
+            .respond_with(ResponseTemplate::new(201).insert_header("ETag", "\"e1\""))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = CardDavClient::from_config(&config).await.unwrap();
+
+        let (href, etag) = client
+            .import_vcard(&format!("{}{}", server.uri(), book), vcard)
+            .await
+            .unwrap();
+        assert!(href.ends_with("/Default/urn-uuid-1234.vcf"));
+        assert_eq!(etag, "\"e1\"");
+
+        let two = format!("{}{}", vcard, vcard);
+        assert!(client.import_vcard(book, &two).await.is_err());
+    }
+
+    #[test]
+    fn test_vcard_uid() {
+        assert_eq!(
+            vcard_uid("BEGIN:VCARD\r\nUID;VALUE=text:abc\r\n def\r\nEND:VCARD").as_deref(),
+            Some("abcdef")
+        );
+        assert_eq!(vcard_uid("BEGIN:VCARD\r\nUID:\r\nEND:VCARD"), None);
+    }
 }
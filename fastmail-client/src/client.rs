@@ -1,71 +1,744 @@
 // fastmail-client/src/client.rs
+use crate::compose::{EmailComposeSpec, UploadedAttachment};
+use crate::config::Config;
+use crate::html_text::render_html_bodies;
+use crate::mailbox_tree::{build_mailbox_tree, MailboxNode};
 use crate::masked_email::{MaskedEmail, MaskedEmailState};
+use crate::session_cache::SessionCache;
+use crate::sync_state::EmailChanges;
+use crate::whitelist::Whitelist;
 use anyhow::{anyhow, Result};
-use jmap_client::{Email, JmapClient, Mailbox, ReqwestClient};
-use serde_json::json;
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures::Stream;
+use jmap_client::{
+    check_set_errors, BodyPart, Comparator, DestroyResult, Email, EmailAddress, EmailBodyValue,
+    EmailCreate, EmailFilterCondition, EmailImport, EmailQueryPage, EmailSubmission, Envelope,
+    Filter, Identity, JmapClient, Mailbox, MailboxFilterCondition, PushEvent, QueryChangesResponse,
+    RateLimit, ReqwestClient, SearchSnippet, Session, Thread, UpdateResult, VacationResponse,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
 
-const FASTMAIL_SESSION_URL: &str = "https://api.fastmail.com/jmap/session";
+pub(crate) const FASTMAIL_SESSION_URL: &str = "https://api.fastmail.com/jmap/session";
 const FASTMAIL_MASKED_EMAIL_CAPABILITY: &str = "https://www.fastmail.com/dev/maskedemail";
 const JMAP_CORE_CAPABILITY: &str = "urn:ietf:params:jmap:core";
+/// Retries for rate-limited or temporarily unavailable requests; see `ReqwestClient::with_retries`
+const HTTP_RETRIES: u32 = 3;
+
+/// Retrying JMAP transport with the config's request timeout and logging
+fn http_client(token: String, config: &Config) -> Result<ReqwestClient> {
+    Ok(ReqwestClient::builder()
+        .timeout(config.get_timeout())
+        .build()?
+        .with_token(token)
+        .with_retries(HTTP_RETRIES)
+        .with_verbose(config.verbose))
+}
+
+/// An HTTP client with `token`, or with the config's email and app password
+/// (Basic auth) when there is none. Also returns the credential the session
+/// cache is keyed by.
+fn jmap_http_client(token: Option<String>, config: &Config) -> Result<(ReqwestClient, String)> {
+    let Some(token) = token else {
+        let username = config.get_dav_username()?.to_string();
+        let password = config
+            .dav_password
+            .clone()
+            .ok_or_else(|| anyhow!("No API token or app password configured"))?;
+        let credential = format!("{}:{}", username, password);
+        let http = ReqwestClient::builder()
+            .timeout(config.get_timeout())
+            .build()?
+            .with_basic_auth(username, password)
+            .with_retries(HTTP_RETRIES)
+            .with_verbose(config.verbose);
+        return Ok((http, credential));
+    };
+    Ok((http_client(token.clone(), config)?, token))
+}
+
+/// One entry of the session's `accounts`, as shown by `accounts list`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountSummary {
+    pub id: String,
+    pub name: Option<String>,
+    pub is_personal: bool,
+    pub is_read_only: bool,
+    /// Whether this client acts on the account
+    pub selected: bool,
+}
 
 pub struct FastmailClient {
     inner: JmapClient<ReqwestClient>,
-    account_email: String,
 }
 
 impl FastmailClient {
     pub async fn new(token: String) -> Result<Self> {
-        let inner = JmapClient::connect(FASTMAIL_SESSION_URL, token).await?;
+        Self::new_with_session_url(token, FASTMAIL_SESSION_URL).await
+    }
 
-        // Get account email from session
-        let account_email = inner.account_email().unwrap_or_default().to_string();
+    /// Like `new`, but against any JMAP server's session URL (e.g. a self-hosted Stalwart)
+    pub async fn new_with_session_url(token: String, session_url: &str) -> Result<Self> {
+        let http = http_client(token, &Config::default())?;
+        let session = JmapClient::fetch_session(&http, session_url).await?;
+        Ok(Self {
+            inner: JmapClient::from_session(http, session)?,
+        })
+    }
+
+    /// Like `new_with_session_url`, with the session URL and request timeout from
+    /// `config`, reusing a recently cached session instead of fetching it.
+    /// Without a token, authenticates with the config's email and app password.
+    /// Callers should call `SessionCache::clear` if a request later fails with 401.
+    pub async fn new_cached(token: Option<String>, config: &Config) -> Result<Self> {
+        let (http, session) = Self::cached_session(token, config).await?;
+        Ok(Self {
+            inner: JmapClient::from_session(http, session)?,
+        })
+    }
 
+    /// Like `new_cached`, but acting on `account_id` from the session's
+    /// accounts (e.g. a shared or delegated account) instead of the primary one
+    pub async fn with_account_id(
+        token: Option<String>,
+        config: &Config,
+        account_id: &str,
+    ) -> Result<Self> {
+        let (http, session) = Self::cached_session(token, config).await?;
         Ok(Self {
-            inner,
-            account_email,
+            inner: JmapClient::from_session_with_account(http, session, account_id)?,
         })
     }
 
+    async fn cached_session(
+        token: Option<String>,
+        config: &Config,
+    ) -> Result<(ReqwestClient, Session)> {
+        let (http, credential) = jmap_http_client(token, config)?;
+        let session_url = config.get_session_url();
+        let session_url = session_url.as_str();
+        let session = match SessionCache::load(&credential, session_url) {
+            Some(session) => session,
+            None => {
+                let session = JmapClient::fetch_session(&http, session_url).await?;
+                // A cache that can't be written only costs the next invocation a round-trip
+                let _ = SessionCache::store(&credential, session_url, &session);
+                session
+            }
+        };
+        Ok((http, session))
+    }
+
     pub fn account_id(&self) -> &str {
         self.inner.account_id()
     }
 
-    pub fn account_email(&self) -> &str {
-        &self.account_email
+    /// The account's email address: from the session when it names one,
+    /// otherwise the default sending identity's, fetched on demand. Shared
+    /// accounts may have neither.
+    pub async fn account_email(&self) -> Result<String> {
+        if let Some(email) = self.inner.account_email() {
+            return Ok(email.to_string());
+        }
+        default_identity_email(&self.inner.identity_get_all().await?)
+            .ok_or_else(|| anyhow!("Could not determine the account email address"))
+    }
+
+    /// Every account this login can access, sorted by id
+    pub fn accounts(&self) -> Vec<AccountSummary> {
+        let mut accounts: Vec<AccountSummary> = self
+            .inner
+            .session()
+            .accounts
+            .iter()
+            .map(|(id, data)| AccountSummary {
+                id: id.clone(),
+                name: data.name.clone(),
+                is_personal: data.is_personal.unwrap_or(false),
+                is_read_only: data.is_read_only.unwrap_or(false),
+                selected: id == self.account_id(),
+            })
+            .collect();
+        accounts.sort_by(|a, b| a.id.cmp(&b.id));
+        accounts
+    }
+
+    /// Rate limit reported on the most recent API response
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.inner.rate_limit()
     }
 
     // Delegate to JmapClient
 
-    pub async fn list_emails(&self, mailbox: Option<&str>, limit: usize) -> Result<Vec<Email>> {
-        let ids = match mailbox {
-            Some(name) => {
-                let mailbox_id = self.resolve_mailbox_id(name).await?;
-                self.inner
-                    .email_query_in_mailbox(&mailbox_id, limit)
-                    .await?
-            }
-            None => self.inner.email_query(limit).await?,
+    /// List emails, newest first unless a sort order is given, fetching only
+    /// `properties` when given. Query and fetch happen in a single JMAP request.
+    pub async fn list_emails(
+        &self,
+        mailbox: Option<&str>,
+        sort: Option<Vec<Comparator>>,
+        position: Option<usize>,
+        limit: usize,
+        properties: Option<Vec<String>>,
+    ) -> Result<(EmailQueryPage, Vec<Email>)> {
+        let filter = self.mailbox_filter(mailbox).await?;
+        self.inner
+            .email_query_and_get(filter, sort, position, limit, properties)
+            .await
+    }
+
+    /// Where emails were added to or removed from a `list_emails` result since
+    /// `since_query_state`; mailbox and sort must match the original listing
+    pub async fn list_email_changes(
+        &self,
+        mailbox: Option<&str>,
+        sort: Option<Vec<Comparator>>,
+        since_query_state: &str,
+    ) -> Result<QueryChangesResponse> {
+        let filter = self.mailbox_filter(mailbox).await?;
+        self.inner
+            .email_query_changes(since_query_state, filter, sort, None)
+            .await
+    }
+
+    async fn mailbox_filter(
+        &self,
+        mailbox: Option<&str>,
+    ) -> Result<Option<Filter<EmailFilterCondition>>> {
+        Ok(match mailbox {
+            Some(name) => Some(
+                EmailFilterCondition {
+                    in_mailbox: Some(self.resolve_mailbox_id(name).await?),
+                    ..Default::default()
+                }
+                .into(),
+            ),
+            None => None,
+        })
+    }
+
+    /// Count emails, optionally only those in a mailbox and/or not yet read,
+    /// without fetching any of them
+    pub async fn count_emails(&self, mailbox: Option<&str>, unread: bool) -> Result<usize> {
+        let in_mailbox = match mailbox {
+            Some(name) => Some(self.resolve_mailbox_id(name).await?),
+            None => None,
         };
-        self.inner.email_get(&ids).await
+        let filter = EmailFilterCondition {
+            in_mailbox,
+            not_keyword: unread.then(|| "$seen".to_string()),
+            ..Default::default()
+        };
+        self.inner.email_count(Some(filter.into())).await
+    }
+
+    /// Search emails matching a filter (a single condition or an AND/OR combination)
+    pub async fn search_emails(
+        &self,
+        filter: impl Into<Filter<EmailFilterCondition>>,
+        limit: usize,
+    ) -> Result<Vec<Email>> {
+        let (_, emails) = self
+            .inner
+            .email_query_and_get(Some(filter.into()), None, None, limit, None)
+            .await?;
+        Ok(emails)
+    }
+
+    /// Highlighted subject/preview snippets for emails found with `filter`.
+    /// The filter must be the one used for the search so the server marks the same terms.
+    pub async fn search_snippets(
+        &self,
+        email_ids: &[String],
+        filter: &Filter<EmailFilterCondition>,
+    ) -> Result<Vec<SearchSnippet>> {
+        self.inner
+            .search_snippet_get(email_ids, Some(serde_json::to_value(filter)?))
+            .await
     }
 
     pub async fn get_email(&self, id: &str) -> Result<Email> {
         self.inner.get_email(id).await
     }
 
+    /// Get several emails in one request, in the order given; any missing id is an error
+    pub async fn get_emails(&self, ids: &[String]) -> Result<Vec<Email>> {
+        let mut emails = self.inner.email_get(ids, None).await?;
+        ids.iter()
+            .map(|id| {
+                emails
+                    .iter()
+                    .position(|e| e.id == *id)
+                    .map(|pos| emails.swap_remove(pos))
+                    .ok_or_else(|| anyhow!("Email not found: {}", id))
+            })
+            .collect()
+    }
+
     /// Get email with body content included (delegates to JmapClient)
     pub async fn get_email_with_body(&self, id: &str) -> Result<Email> {
         self.inner.get_email_with_body(id).await
     }
 
+    /// All emails in the thread containing `email_id`, oldest first
+    pub async fn get_thread(&self, email_id: &str) -> Result<(Thread, Vec<Email>)> {
+        self.inner.thread_emails(email_id).await
+    }
+
+    /// List the attachments of an email
+    pub async fn list_attachments(&self, email_id: &str) -> Result<Vec<BodyPart>> {
+        let email = self.inner.get_email(email_id).await?;
+        Ok(email.attachments.unwrap_or_default())
+    }
+
+    /// Save one attachment's raw bytes to `out`, returning the number of bytes written
+    pub async fn download_attachment(
+        &self,
+        email_id: &str,
+        part_id: &str,
+        out: &Path,
+    ) -> Result<usize> {
+        let part = self
+            .list_attachments(email_id)
+            .await?
+            .into_iter()
+            .find(|p| p.part_id == part_id)
+            .ok_or_else(|| anyhow!("Attachment {} not found in email {}", part_id, email_id))?;
+        let blob_id = part
+            .blob_id
+            .as_deref()
+            .ok_or_else(|| anyhow!("Attachment {} has no blob", part_id))?;
+
+        let bytes = self
+            .inner
+            .download_blob_content_bytes(
+                blob_id,
+                part.name.as_deref().unwrap_or("attachment"),
+                &part.type_,
+            )
+            .await?;
+        std::fs::write(out, &bytes)?;
+        Ok(bytes.len())
+    }
+
+    /// Save the original RFC 5322 message, exactly as stored, to `out`.
+    /// Returns the number of bytes written.
+    pub async fn export_email(&self, email_id: &str, out: &Path) -> Result<usize> {
+        let email = self.inner.get_email(email_id).await?;
+        let blob_id = email
+            .blob_id
+            .as_deref()
+            .ok_or_else(|| anyhow!("Email {} has no blobId to export", email_id))?;
+
+        let bytes = self
+            .inner
+            .download_blob_content_bytes(blob_id, &format!("{}.eml", email_id), "message/rfc822")
+            .await?;
+        std::fs::write(out, &bytes)?;
+        Ok(bytes.len())
+    }
+
+    /// Parse a blob as an email without storing it, with all body values.
+    /// `properties` defaults to the headers, body structure and body values.
+    pub async fn parse_email(
+        &self,
+        blob_id: &str,
+        properties: Option<Vec<String>>,
+    ) -> Result<Value> {
+        let properties = properties.unwrap_or_else(|| {
+            [
+                "messageId",
+                "from",
+                "to",
+                "cc",
+                "subject",
+                "sentAt",
+                "headers",
+                "bodyStructure",
+                "bodyValues",
+            ]
+            .map(String::from)
+            .to_vec()
+        });
+        let mut parsed = self
+            .inner
+            .email_parse(
+                &[blob_id.to_string()],
+                Some(properties),
+                None,
+                None,
+                None,
+                Some(true),
+                None,
+            )
+            .await?;
+        parsed
+            .remove(blob_id)
+            .ok_or_else(|| anyhow!("Blob {} could not be parsed as an email", blob_id))
+    }
+
+    /// Stream push notifications for the given types (e.g. `["Email", "Mailbox"]`).
+    /// Reconnects on its own; see `JmapClient::event_source`.
+    pub fn watch(&self, types: &[&str]) -> Result<impl Stream<Item = Result<PushEvent>> + '_> {
+        self.inner.event_source(types)
+    }
+
+    /// Emails created since `since_state`, with the new Email state.
+    /// Follows `hasMoreChanges` so that no page of changes is skipped.
+    pub async fn new_emails_since(&self, since_state: &str) -> Result<(Vec<Email>, String)> {
+        let changes = self.email_changes_since(since_state).await?;
+        let emails = if changes.created.is_empty() {
+            Vec::new()
+        } else {
+            self.inner.email_get(&changes.created, None).await?
+        };
+        Ok((emails, changes.new_state))
+    }
+
+    /// Current Email state, to use as a baseline for `email_changes_since`
+    pub async fn email_state(&self) -> Result<String> {
+        self.inner.email_state().await
+    }
+
+    /// All Email changes since `since_state`, following `hasMoreChanges` until caught up
+    pub async fn email_changes_since(&self, since_state: &str) -> Result<EmailChanges> {
+        let mut changes = EmailChanges {
+            old_state: since_state.to_string(),
+            new_state: since_state.to_string(),
+            ..Default::default()
+        };
+        loop {
+            let page = self.inner.email_changes(&changes.new_state, None).await?;
+            let progressed = page.new_state != changes.new_state;
+            changes.merge(page.created, page.updated, page.destroyed);
+            changes.new_state = page.new_state;
+            // Stop if the server claims more changes but did not advance the state
+            if !page.has_more_changes || !progressed {
+                return Ok(changes);
+            }
+        }
+    }
+
     /// Upload binary data using RFC 8620 uploadUrl (delegates to JmapClient)
     /// Returns the blobId
     pub async fn upload_blob(&self, data: &[u8], type_: &str) -> Result<String> {
         self.inner.upload_blob_content(data, type_).await
     }
 
-    pub async fn delete_emails(&self, ids: Vec<String>) -> Result<()> {
-        self.inner.email_delete(&ids).await
+    /// Upload a file through the uploadUrl without loading it into memory.
+    /// Returns the blobId
+    pub async fn upload_blob_file(&self, path: &Path, type_: &str) -> Result<String> {
+        self.inner.upload_blob_file(path, type_).await
+    }
+
+    /// Delete emails, split into batches the server accepts.
+    /// Ids the server refused are listed in `not_destroyed`.
+    pub async fn delete_emails(&self, ids: Vec<String>) -> Result<DestroyResult> {
+        self.inner.email_delete_batched(&ids).await
+    }
+
+    /// Import a raw RFC 5322 message (an .eml file) into a mailbox.
+    /// The file goes up through the uploadUrl and is then added with Email/import.
+    pub async fn import_email(
+        &self,
+        path: &Path,
+        mailbox: &str,
+        keywords: &[String],
+        received_at: Option<DateTime<Utc>>,
+    ) -> Result<Email> {
+        let mailbox_id = self.resolve_mailbox_id(mailbox).await?;
+        let blob_id = self.upload_blob_file(path, "message/rfc822").await?;
+
+        self.inner
+            .email_import(EmailImport {
+                blob_id,
+                mailbox_ids: HashMap::from([(mailbox_id, true)]),
+                keywords: (!keywords.is_empty())
+                    .then(|| keywords.iter().map(|k| (k.clone(), true)).collect()),
+                received_at: received_at.map(|at| at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            })
+            .await
+    }
+
+    /// Move emails into a single mailbox, removing them from all others,
+    /// in as few Email/set calls as the server allows.
+    /// Ids the server refused are listed in `not_updated`.
+    pub async fn move_emails(&self, ids: &[String], to_mailbox: &str) -> Result<UpdateResult> {
+        let mailbox_id = self.resolve_mailbox_id(to_mailbox).await?;
+        let patch = json!({ "mailboxIds": { mailbox_id: true } });
+        self.inner.email_update_batched(ids, patch).await
+    }
+
+    /// Mark emails as read or unread via the `$seen` keyword
+    pub async fn mark_read(&self, ids: &[String], read: bool) -> Result<UpdateResult> {
+        self.inner
+            .email_set_keyword_batched(ids, "$seen", read)
+            .await
+    }
+
+    /// Flag or unflag emails via the `$flagged` keyword
+    pub async fn mark_flagged(&self, ids: &[String], flagged: bool) -> Result<UpdateResult> {
+        self.inner
+            .email_set_keyword_batched(ids, "$flagged", flagged)
+            .await
+    }
+
+    /// Return the recipients that are not on the allow list stored at `whitelist`
+    pub fn check_recipients_allowed(
+        &self,
+        whitelist: &Path,
+        recipients: &[&str],
+    ) -> Result<Vec<String>> {
+        Ok(Whitelist::load_from(whitelist)?.blocked(recipients))
+    }
+
+    /// Compose a plain-text email and submit it from `from` (an identity id or email),
+    /// or the primary identity when not given.
+    /// With `send_at`, delivery is held until then and can be cancelled via `cancel_submission`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_email(
+        &self,
+        to: &[String],
+        cc: &[String],
+        bcc: &[String],
+        subject: &str,
+        body: &str,
+        send_at: Option<DateTime<Utc>>,
+        from: Option<&str>,
+    ) -> Result<EmailSubmission> {
+        let email = text_email(addresses(to), addresses(cc), addresses(bcc), subject, body);
+        self.submit_email(email, send_at, from).await
+    }
+
+    /// Upload the spec's attachment files and build the email from it
+    pub async fn compose_from_spec(&self, spec: &EmailComposeSpec) -> Result<EmailCreate> {
+        spec.validate()?;
+
+        let mut attachments = Vec::new();
+        for path in &spec.attachments {
+            let data = std::fs::read(path)
+                .map_err(|e| anyhow!("Cannot read attachment {}: {}", path.display(), e))?;
+            let type_ = mime_guess::from_path(path)
+                .first_or_octet_stream()
+                .to_string();
+            let blob_id = self.blob_upload_bytes(&data, Some(&type_)).await?;
+            attachments.push(UploadedAttachment {
+                blob_id,
+                name: path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "attachment".to_string()),
+                type_,
+            });
+        }
+        spec.to_email_create(attachments)
+    }
+
+    /// Find the sending identity matching `selector` by id or email (case-insensitive)
+    pub async fn resolve_identity(&self, selector: &str) -> Result<Identity> {
+        let identities = self.inner.identity_get_all().await?;
+        select_identity(identities, selector)
+    }
+
+    /// Build a reply to an existing email, threading it via In-Reply-To/References
+    pub async fn compose_reply(
+        &self,
+        email_id: &str,
+        body: &str,
+        reply_all: bool,
+    ) -> Result<EmailCreate> {
+        let original = self.inner.get_email(email_id).await?;
+        let own: Vec<String> = self
+            .inner
+            .identity_get_all()
+            .await?
+            .into_iter()
+            .map(|i| i.email)
+            .chain(self.inner.account_email().map(str::to_string))
+            .collect();
+
+        let (to, cc) = reply_recipients(&original, reply_all, &own);
+        if to.is_empty() {
+            return Err(anyhow!("Email {} has no sender to reply to", email_id));
+        }
+
+        let mut email = text_email(
+            to,
+            cc,
+            Vec::new(),
+            &prefix_subject(
+                original.subject.as_deref().unwrap_or_default(),
+                "Re:",
+                REPLY_PREFIXES,
+            ),
+            body,
+        );
+        email.in_reply_to = original.message_id.clone();
+        email.references = original.message_id.as_ref().map(|ids| {
+            let mut references = original.references.clone().unwrap_or_default();
+            references.extend(ids.iter().cloned());
+            references
+        });
+        Ok(email)
+    }
+
+    /// Build a forward of an existing email with the original text quoted below `body`
+    pub async fn compose_forward(
+        &self,
+        email_id: &str,
+        to: &[String],
+        body: &str,
+    ) -> Result<EmailCreate> {
+        let mut original = self.inner.get_email_with_body(email_id).await?;
+        // An HTML-only original has its text/html part in textBody; quote it as text
+        render_html_bodies(&mut original);
+
+        let text = format!(
+            "{}\n\n---------- Forwarded message ----------\n\
+             From: {}\nDate: {}\nSubject: {}\nTo: {}\n\n{}",
+            body,
+            format_addresses(original.from.as_deref()),
+            original
+                .sent_at
+                .as_deref()
+                .or(original.received_at.as_deref())
+                .unwrap_or_default(),
+            original.subject.as_deref().unwrap_or_default(),
+            format_addresses(original.to.as_deref()),
+            quote(&body_text(&original)),
+        );
+
+        let mut email = text_email(
+            addresses(to),
+            Vec::new(),
+            Vec::new(),
+            &prefix_subject(
+                original.subject.as_deref().unwrap_or_default(),
+                "Fwd:",
+                FORWARD_PREFIXES,
+            ),
+            text.trim_start(),
+        );
+        email.references = original.message_id.clone();
+        Ok(email)
+    }
+
+    /// Save a draft in Drafts and submit it, having the server file it in Sent
+    /// as a sent email. Scheduled emails are filed at once too, so a released
+    /// message is never left behind as a draft; `cancel_submission` restores it.
+    /// `from` selects the sending identity; the first identity is used otherwise.
+    pub async fn submit_email(
+        &self,
+        mut email: EmailCreate,
+        send_at: Option<DateTime<Utc>>,
+        from: Option<&str>,
+    ) -> Result<EmailSubmission> {
+        let identity = match from {
+            Some(selector) => self.resolve_identity(selector).await?,
+            None => self
+                .inner
+                .identity_get_all()
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow!("No sending identity available"))?,
+        };
+        let drafts = self.mailbox_by_role("drafts").await?;
+
+        email.from = Some(vec![EmailAddress {
+            email: identity.email.clone(),
+            name: Some(identity.name.clone()).filter(|n| !n.is_empty()),
+        }]);
+        email.mailbox_ids = HashMap::from([(drafts.id, true)]);
+        email.keywords = Some(HashMap::from([
+            ("$draft".to_string(), true),
+            ("$seen".to_string(), true),
+        ]));
+
+        // A held submission needs an explicit envelope to carry HOLDUNTIL
+        let envelope = send_at.map(|_| Envelope {
+            mail_from: EmailAddress {
+                email: identity.email.clone(),
+                name: None,
+            },
+            rcpt_to: [&email.to, &email.cc, &email.bcc]
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|a| EmailAddress {
+                    email: a.email.clone(),
+                    name: None,
+                })
+                .collect(),
+        });
+
+        // Filed in Sent by the server in the same request as the submission, so
+        // a sent email is never reported as failed because filing it failed
+        let sent = self.mailbox_by_role("sent").await?;
+        let file_in_sent = json!({
+            "mailboxIds": { sent.id: true },
+            "keywords/$draft": null,
+            "keywords/$seen": true,
+        });
+
+        let draft = self.inner.email_create(email).await?;
+        let submission = self
+            .inner
+            .email_submission_create(
+                &identity.id,
+                &draft.id,
+                envelope,
+                send_at,
+                Some(file_in_sent),
+            )
+            .await?;
+
+        Ok(submission)
+    }
+
+    /// Cancel a submission that has not been released yet and move its email
+    /// from Sent back to Drafts as a draft, ready to be edited
+    pub async fn cancel_submission(&self, id: &str) -> Result<()> {
+        let submission = self
+            .inner
+            .email_submission_get(&[id.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Submission not found: {}", id))?;
+        self.inner.email_submission_cancel(id).await?;
+
+        let drafts = self.mailbox_by_role("drafts").await?;
+        let result = self
+            .inner
+            .email_update_batched(
+                std::slice::from_ref(&submission.email_id),
+                json!({
+                    "mailboxIds": { drafts.id: true },
+                    "keywords/$draft": true,
+                }),
+            )
+            .await?;
+        if let Some(error) = result.not_updated.values().next() {
+            return Err(anyhow!(
+                "Submission {} was cancelled, but email {} could not be moved back to Drafts: {}",
+                id,
+                submission.email_id,
+                error
+            ));
+        }
+        Ok(())
+    }
+
+    async fn mailbox_by_role(&self, role: &str) -> Result<Mailbox> {
+        self.inner
+            .mailbox_get_all()
+            .await?
+            .into_iter()
+            .find(|m| m.role.as_deref() == Some(role))
+            .ok_or_else(|| anyhow!("No mailbox with role: {}", role))
     }
 
     pub async fn list_masked_emails(&self) -> Result<Vec<MaskedEmail>> {
@@ -117,6 +790,7 @@ impl FastmailClient {
                 }),
             )
             .await?;
+        check_set_errors(&args, "notCreated", "create masked email")?;
 
         let created = args
             .get("created")
@@ -127,8 +801,19 @@ impl FastmailClient {
         Ok(email)
     }
 
+    /// Map a masked email address to its id; anything without `@` is taken as an id already
+    pub async fn resolve_masked_email_id(&self, id_or_email: &str) -> Result<String> {
+        if !id_or_email.contains('@') {
+            return Ok(id_or_email.to_string());
+        }
+        let emails = self.list_masked_emails().await?;
+        find_masked_email(&emails, id_or_email).map(|e| e.id.clone())
+    }
+
     pub async fn set_masked_email_state(&self, id: &str, state: MaskedEmailState) -> Result<()> {
-        self.inner
+        let id = self.resolve_masked_email_id(id).await?;
+        let args = self
+            .inner
             .call_method_with_using(
                 &[JMAP_CORE_CAPABILITY, FASTMAIL_MASKED_EMAIL_CAPABILITY],
                 "MaskedEmail/set",
@@ -138,20 +823,93 @@ impl FastmailClient {
                 }),
             )
             .await?;
-        Ok(())
+        check_set_errors(&args, "notUpdated", "update masked email")
     }
 
-    async fn resolve_mailbox_id(&self, mailbox_name: &str) -> Result<String> {
-        let mailboxes = self.inner.mailbox_get_all().await?;
-        let mailbox = mailboxes
-            .into_iter()
-            .find(|m| m.name == mailbox_name)
-            .ok_or_else(|| anyhow!("Mailbox not found: {}", mailbox_name))?;
-        Ok(mailbox.id)
+    /// Enable a masked email that is still pending its first use
+    pub async fn confirm_masked_email(&self, id_or_email: &str) -> Result<String> {
+        let emails = self.list_masked_emails().await?;
+        let masked = if id_or_email.contains('@') {
+            find_masked_email(&emails, id_or_email)?
+        } else {
+            emails
+                .iter()
+                .find(|e| e.id == id_or_email)
+                .ok_or_else(|| anyhow!("Masked email not found: {}", id_or_email))?
+        };
+        if masked.state != MaskedEmailState::Pending {
+            return Err(anyhow!(
+                "Masked email {} is {}, not pending",
+                masked.email,
+                masked.state
+            ));
+        }
+
+        self.set_masked_email_state(&masked.id, MaskedEmailState::Enabled)
+            .await?;
+        Ok(masked.id.clone())
     }
 
-    pub async fn list_mailboxes(&self, filter: Option<&str>) -> Result<Vec<Mailbox>> {
-        let mut mailboxes = self.inner.mailbox_get_all().await?;
+    /// Change a masked email's description and/or domain; fields left as None are kept
+    pub async fn update_masked_email(
+        &self,
+        id: &str,
+        description: Option<&str>,
+        for_domain: Option<&str>,
+    ) -> Result<()> {
+        let patch = masked_email_patch(description, for_domain);
+        if patch.as_object().is_some_and(|p| p.is_empty()) {
+            return Err(anyhow!("Nothing to update"));
+        }
+        let id = self.resolve_masked_email_id(id).await?;
+
+        let args = self
+            .inner
+            .call_method_with_using(
+                &[JMAP_CORE_CAPABILITY, FASTMAIL_MASKED_EMAIL_CAPABILITY],
+                "MaskedEmail/set",
+                json!({
+                    "accountId": self.account_id(),
+                    "update": { id: patch },
+                }),
+            )
+            .await?;
+        check_set_errors(&args, "notUpdated", "update masked email")
+    }
+
+    /// Id of the mailbox with this id or name
+    pub async fn resolve_mailbox_id(&self, id_or_name: &str) -> Result<String> {
+        Ok(self.find_mailbox(id_or_name).await?.id)
+    }
+
+    /// Find a mailbox by id, or else by name
+    pub async fn find_mailbox(&self, id_or_name: &str) -> Result<Mailbox> {
+        select_mailbox(self.inner.mailbox_get_all().await?, id_or_name)
+    }
+
+    /// Ids of every email in a mailbox, however many pages that takes
+    pub async fn mailbox_email_ids(&self, mailbox_id: &str) -> Result<Vec<String>> {
+        self.inner.email_query_all_in_mailbox(mailbox_id).await
+    }
+
+    /// List mailboxes, optionally only those with a role (e.g. "trash") and/or
+    /// whose name contains `filter`
+    pub async fn list_mailboxes(
+        &self,
+        filter: Option<&str>,
+        role: Option<&str>,
+    ) -> Result<Vec<Mailbox>> {
+        let mut mailboxes = match role {
+            Some(role) => {
+                self.inner
+                    .mailbox_query_and_get(MailboxFilterCondition {
+                        role: Some(role.to_lowercase()),
+                        ..Default::default()
+                    })
+                    .await?
+            }
+            None => self.inner.mailbox_get_all().await?,
+        };
 
         if let Some(pattern) = filter {
             let pattern_lower = pattern.to_lowercase();
@@ -161,6 +919,12 @@ impl FastmailClient {
         Ok(mailboxes)
     }
 
+    /// All mailboxes nested under their parents
+    pub async fn mailbox_tree(&self) -> Result<Vec<MailboxNode>> {
+        let mailboxes = self.inner.mailbox_get_all().await?;
+        Ok(build_mailbox_tree(mailboxes))
+    }
+
     pub async fn create_mailbox(&self, name: &str) -> Result<Mailbox> {
         self.inner.mailbox_create(name).await
     }
@@ -169,22 +933,129 @@ impl FastmailClient {
         self.inner.mailbox_delete(id).await
     }
 
-    /// Check if server supports Blob capability
-    pub fn has_blob_capability(&self) -> bool {
-        self.inner.has_capability("urn:ietf:params:jmap:blob")
+    /// Rename a mailbox, returning it as updated
+    pub async fn rename_mailbox(&self, id: &str, name: &str) -> Result<Mailbox> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("Mailbox name must not be empty"));
+        }
+        let mailboxes = self.inner.mailbox_get_all().await?;
+        let mailbox = find_mailbox(&mailboxes, id)?;
+        if mailboxes
+            .iter()
+            .any(|m| m.id != id && m.parent_id == mailbox.parent_id && m.name == name)
+        {
+            return Err(anyhow!("A mailbox named '{}' already exists there", name));
+        }
+
+        self.inner
+            .mailbox_update(id, Some(name), None, None, None)
+            .await?;
+        self.inner.mailbox_get(id).await
     }
 
-    /// Get Blob capability details if available
-    pub fn blob_capability(&self) -> Option<jmap_client::BlobCapability> {
+    /// Move a mailbox under `parent_id`, or to the top level when None
+    pub async fn move_mailbox(&self, id: &str, parent_id: Option<&str>) -> Result<Mailbox> {
+        let mailboxes = self.inner.mailbox_get_all().await?;
+        find_mailbox(&mailboxes, id)?;
+
+        if let Some(parent_id) = parent_id {
+            // Walk up from the new parent; reaching the mailbox itself would create a cycle
+            let mut ancestor = Some(find_mailbox(&mailboxes, parent_id)?);
+            while let Some(current) = ancestor {
+                if current.id == id {
+                    return Err(anyhow!(
+                        "Cannot move mailbox {} inside itself or one of its children",
+                        id
+                    ));
+                }
+                ancestor = current
+                    .parent_id
+                    .as_deref()
+                    .and_then(|p| mailboxes.iter().find(|m| m.id == p));
+            }
+        }
+
         self.inner
-            .session()
+            .mailbox_update(id, None, Some(parent_id), None, None)
+            .await?;
+        self.inner.mailbox_get(id).await
+    }
+
+    /// The account's vacation response (auto-reply)
+    pub async fn get_vacation_response(&self) -> Result<VacationResponse> {
+        self.inner.vacation_response_get().await
+    }
+
+    /// Turn the vacation response on. A subject or text left unset keeps its
+    /// current value, but a date left unset is cleared, so a window from an
+    /// earlier absence does not stop it from replying.
+    pub async fn enable_vacation_response(
+        &self,
+        subject: Option<&str>,
+        text_body: Option<&str>,
+        from_date: Option<&str>,
+        to_date: Option<&str>,
+    ) -> Result<VacationResponse> {
+        self.inner
+            .vacation_response_set(
+                Some(true),
+                Some(from_date),
+                Some(to_date),
+                subject,
+                text_body,
+                None,
+            )
+            .await?;
+        self.inner.vacation_response_get().await
+    }
+
+    /// Turn the vacation response off, leaving its message in place for next time
+    pub async fn disable_vacation_response(&self) -> Result<VacationResponse> {
+        self.inner
+            .vacation_response_set(Some(false), None, None, None, None, None)
+            .await?;
+        self.inner.vacation_response_get().await
+    }
+
+    /// Send `data` through Core/echo (RFC 8620 §4) and return what came back
+    pub async fn echo(&self, data: serde_json::Value) -> Result<serde_json::Value> {
+        self.inner.core_echo(data).await
+    }
+
+    /// Check if a capability URI is supported, by the account or the server.
+    /// Fastmail advertises some capabilities only in the session-level map.
+    pub fn has_capability(&self, uri: &str) -> bool {
+        self.capability(uri).is_some()
+    }
+
+    /// A capability's details, preferring the account-level entry over the session-level one
+    fn capability(&self, uri: &str) -> Option<&Value> {
+        let session = self.inner.session();
+        session
             .accounts
             .get(self.inner.account_id())
             .and_then(|acc| acc.account_capabilities.as_ref())
-            .and_then(|caps| caps.get("urn:ietf:params:jmap:blob"))
+            .and_then(|caps| caps.get(uri))
+            .or_else(|| session.capabilities.get(uri))
+    }
+
+    /// Check if server supports Blob capability
+    pub fn has_blob_capability(&self) -> bool {
+        self.has_capability("urn:ietf:params:jmap:blob")
+    }
+
+    /// Get Blob capability details if available
+    pub fn blob_capability(&self) -> Option<jmap_client::BlobCapability> {
+        self.capability("urn:ietf:params:jmap:blob")
             .and_then(|v| serde_json::from_value(v.clone()).ok())
     }
 
+    /// Largest single upload through the uploadUrl, if the server says
+    pub fn max_size_upload(&self) -> Option<u64> {
+        self.inner.max_size_upload()
+    }
+
     // Blob operations (RFC 9404) - forwarding methods to JmapClient
 
     /// Upload binary data as a blob
@@ -193,6 +1064,24 @@ impl FastmailClient {
         self.inner.blob_upload_bytes(bytes, type_).await
     }
 
+    /// Server-computed digest of a blob, e.g. `algorithm` "sha-256" (RFC 9404 §4.2)
+    pub async fn blob_digest(&self, id: &str, algorithm: &str) -> Result<Option<String>> {
+        let results = self
+            .inner
+            .blob_get(
+                &[id.to_string()],
+                Some(vec![format!("digest:{}", algorithm)]),
+                None,
+                None,
+            )
+            .await?;
+        let result = results
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Blob not found: {}", id))?;
+        Ok(result.digest(algorithm).cloned())
+    }
+
     /// Get blob content as raw bytes
     pub async fn blob_get_bytes(&self, id: &str) -> Result<Vec<u8>> {
         self.inner.blob_get_bytes(id).await
@@ -222,42 +1111,25 @@ impl FastmailClient {
 
     /// Check if server supports Principals capability
     pub fn has_principals_capability(&self) -> bool {
-        self.inner.has_capability("urn:ietf:params:jmap:principals")
+        self.has_capability("urn:ietf:params:jmap:principals")
     }
 
     /// Get Principals capability details if available
     pub fn principals_capability(&self) -> Option<jmap_client::PrincipalsAccountCapability> {
-        self.inner
-            .session()
-            .accounts
-            .get(self.inner.account_id())
-            .and_then(|acc| acc.account_capabilities.as_ref())
-            .and_then(|caps| caps.get("urn:ietf:params:jmap:principals"))
+        self.capability("urn:ietf:params:jmap:principals")
             .and_then(|v| serde_json::from_value(v.clone()).ok())
     }
 
     /// Get owner capability (for finding principal account)
     pub fn owner_capability(&self) -> Option<jmap_client::PrincipalsOwnerCapability> {
-        self.inner
-            .session()
-            .accounts
-            .get(self.inner.account_id())
-            .and_then(|acc| acc.account_capabilities.as_ref())
-            .and_then(|caps| caps.get("urn:ietf:params:jmap:principals:owner"))
+        self.capability("urn:ietf:params:jmap:principals:owner")
             .and_then(|v| serde_json::from_value(v.clone()).ok())
     }
 
     /// Get current user's Principal ID
     pub fn current_principal_id(&self) -> Option<String> {
-        self.inner
-            .session()
-            .accounts
-            .get(self.inner.account_id())
-            .and_then(|acc| acc.account_capabilities.as_ref())
-            .and_then(|caps| caps.get("urn:ietf:params:jmap:principals"))
-            .and_then(|v| v.get("currentUserPrincipalId"))
-            .and_then(|v| v.as_str())
-            .map(String::from)
+        self.principals_capability()
+            .and_then(|cap| cap.current_user_principal_id)
     }
 
     /// List all Principals
@@ -297,6 +1169,210 @@ impl FastmailClient {
     }
 }
 
+pub(crate) fn addresses(list: &[String]) -> Vec<EmailAddress> {
+    list.iter()
+        .map(|email| EmailAddress {
+            email: email.clone(),
+            name: None,
+        })
+        .collect()
+}
+
+/// A single-part text/plain email; empty address lists are left unset
+fn text_email(
+    to: Vec<EmailAddress>,
+    cc: Vec<EmailAddress>,
+    bcc: Vec<EmailAddress>,
+    subject: &str,
+    body: &str,
+) -> EmailCreate {
+    let non_empty = |list: Vec<EmailAddress>| (!list.is_empty()).then_some(list);
+
+    EmailCreate {
+        to: non_empty(to),
+        cc: non_empty(cc),
+        bcc: non_empty(bcc),
+        subject: Some(subject.to_string()),
+        text_body: Some(vec![BodyPart {
+            part_id: "body".to_string(),
+            type_: "text/plain".to_string(),
+            ..Default::default()
+        }]),
+        body_values: Some(HashMap::from([(
+            "body".to_string(),
+            EmailBodyValue {
+                value: body.to_string(),
+                is_encoding_problem: false,
+                is_truncated: false,
+            },
+        )])),
+        ..Default::default()
+    }
+}
+
+/// Subject prefixes mail clients use for replies, in English and other languages
+const REPLY_PREFIXES: &[&str] = &[
+    "re", "aw", "sv", "antw", "odp", "vs", "ynt", "rif", "回复", "答复",
+];
+
+/// Subject prefixes mail clients use for forwards, in English and other languages
+const FORWARD_PREFIXES: &[&str] = &[
+    "fwd", "fw", "wg", "tr", "rv", "enc", "doorst", "pd", "vb", "vl", "转发",
+];
+
+/// Prefix a subject with `prefix` ("Re:" or "Fwd:") unless it already starts
+/// with one of `known`, compared without case
+fn prefix_subject(subject: &str, prefix: &str, known: &[&str]) -> String {
+    let existing = subject
+        .split_once([':', '：'])
+        .map(|(start, _)| start.trim().to_lowercase());
+    if existing.is_some_and(|start| known.contains(&start.as_str())) {
+        subject.to_string()
+    } else {
+        format!("{} {}", prefix, subject)
+    }
+}
+
+/// Work out To and Cc for a reply, leaving out our own addresses
+fn reply_recipients(
+    original: &Email,
+    reply_all: bool,
+    own: &[String],
+) -> (Vec<EmailAddress>, Vec<EmailAddress>) {
+    let mut seen: Vec<String> = own.iter().map(|e| e.to_lowercase()).collect();
+    let mut take = |list: Option<&Vec<EmailAddress>>| -> Vec<EmailAddress> {
+        let mut out = Vec::new();
+        for addr in list.into_iter().flatten() {
+            let key = addr.email.to_lowercase();
+            if !seen.contains(&key) {
+                seen.push(key);
+                out.push(addr.clone());
+            }
+        }
+        out
+    };
+
+    let to = take(original.reply_to.as_ref().or(original.from.as_ref()));
+    let cc = if reply_all {
+        let mut cc = take(original.to.as_ref());
+        cc.extend(take(original.cc.as_ref()));
+        cc
+    } else {
+        Vec::new()
+    };
+    (to, cc)
+}
+
+fn format_addresses(list: Option<&[EmailAddress]>) -> String {
+    list.unwrap_or_default()
+        .iter()
+        .map(|a| match &a.name {
+            Some(name) => format!("{} <{}>", name, a.email),
+            None => a.email.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Plain-text body of an email, falling back to its preview
+fn body_text(email: &Email) -> String {
+    let text: Vec<&str> = email
+        .text_body
+        .iter()
+        .flatten()
+        .filter_map(|part| {
+            email
+                .body_values
+                .as_ref()?
+                .get(&part.part_id)?
+                .get("value")?
+                .as_str()
+        })
+        .collect();
+
+    if text.is_empty() {
+        email.preview.clone().unwrap_or_default()
+    } else {
+        text.join("\n")
+    }
+}
+
+fn quote(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("> {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Email of the default identity: the one that cannot be deleted, else the first
+fn default_identity_email(identities: &[Identity]) -> Option<String> {
+    identities
+        .iter()
+        .find(|i| !i.may_delete)
+        .or_else(|| identities.first())
+        .map(|i| i.email.clone())
+}
+
+/// Find a masked email by its address (case-insensitive)
+fn find_masked_email<'a>(emails: &'a [MaskedEmail], address: &str) -> Result<&'a MaskedEmail> {
+    emails
+        .iter()
+        .find(|e| e.email.eq_ignore_ascii_case(address))
+        .ok_or_else(|| anyhow!("No masked email with address {}", address))
+}
+
+/// MaskedEmail/set update patch holding only the fields being changed
+fn masked_email_patch(description: Option<&str>, for_domain: Option<&str>) -> Value {
+    let mut patch = serde_json::Map::new();
+    if let Some(description) = description {
+        patch.insert("description".to_string(), json!(description));
+    }
+    if let Some(for_domain) = for_domain {
+        patch.insert("forDomain".to_string(), json!(for_domain));
+    }
+    Value::Object(patch)
+}
+
+/// The mailbox whose id is `id_or_name`, or else the one with that name
+fn select_mailbox(mut mailboxes: Vec<Mailbox>, id_or_name: &str) -> Result<Mailbox> {
+    let by_id = mailboxes.iter().position(|m| m.id == id_or_name);
+    by_id
+        .or_else(|| mailboxes.iter().position(|m| m.name == id_or_name))
+        .map(|pos| mailboxes.swap_remove(pos))
+        .ok_or_else(|| anyhow!("Mailbox not found: {}", id_or_name))
+}
+
+/// Pick the identity whose id or email matches `selector`
+fn select_identity(identities: Vec<Identity>, selector: &str) -> Result<Identity> {
+    let available = identities
+        .iter()
+        .map(|i| format!("{} ({})", i.email, i.id))
+        .collect::<Vec<_>>()
+        .join(", ");
+    identities
+        .into_iter()
+        .find(|i| i.id == selector || i.email.eq_ignore_ascii_case(selector))
+        .ok_or_else(|| {
+            if available.is_empty() {
+                anyhow!("No sending identity available")
+            } else {
+                anyhow!(
+                    "No identity matches '{}'; available: {}",
+                    selector,
+                    available
+                )
+            }
+        })
+}
+
+/// Look up a mailbox by ID in a full listing
+fn find_mailbox<'a>(mailboxes: &'a [Mailbox], id: &str) -> Result<&'a Mailbox> {
+    mailboxes
+        .iter()
+        .find(|m| m.id == id)
+        .ok_or_else(|| anyhow!("Mailbox not found: {}", id))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -308,4 +1384,587 @@ mod tests {
             "https://api.fastmail.com/jmap/session"
         );
     }
+
+    #[test]
+    fn test_prefix_subject() {
+        assert_eq!(prefix_subject("Lunch", "Re:", REPLY_PREFIXES), "Re: Lunch");
+        assert_eq!(
+            prefix_subject("RE: Lunch", "Re:", REPLY_PREFIXES),
+            "RE: Lunch"
+        );
+        assert_eq!(
+            prefix_subject("Lunch", "Fwd:", FORWARD_PREFIXES),
+            "Fwd: Lunch"
+        );
+        assert_eq!(
+            prefix_subject("FW: Lunch", "Fwd:", FORWARD_PREFIXES),
+            "FW: Lunch"
+        );
+        assert_eq!(
+            prefix_subject("fw:Lunch", "Fwd:", FORWARD_PREFIXES),
+            "fw:Lunch"
+        );
+        assert_eq!(
+            prefix_subject("AW: Mittagessen", "Re:", REPLY_PREFIXES),
+            "AW: Mittagessen"
+        );
+        assert_eq!(
+            prefix_subject("WG: Mittagessen", "Fwd:", FORWARD_PREFIXES),
+            "WG: Mittagessen"
+        );
+        assert_eq!(
+            prefix_subject("回复：午餐", "Re:", REPLY_PREFIXES),
+            "回复：午餐"
+        );
+        // A reply being forwarded is still prefixed
+        assert_eq!(
+            prefix_subject("Re: Lunch", "Fwd:", FORWARD_PREFIXES),
+            "Fwd: Re: Lunch"
+        );
+        // Only a leading word counts as a prefix
+        assert_eq!(
+            prefix_subject("Agenda: Q3 review", "Re:", REPLY_PREFIXES),
+            "Re: Agenda: Q3 review"
+        );
+    }
+
+    /// A client for `session` that never goes to the network
+    fn client_for_session(session: Value) -> FastmailClient {
+        let session: Session = serde_json::from_value(session).unwrap();
+        FastmailClient {
+            inner: JmapClient::from_session(ReqwestClient::new(), session).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_has_capability_checks_session_level() {
+        let client = client_for_session(json!({
+            "capabilities": {
+                "urn:ietf:params:jmap:core": {},
+                "urn:ietf:params:jmap:blob": {
+                    "maxSizeBlobSet": 1000,
+                    "maxDataSources": 64,
+                    "supportedTypeNames": ["Email"],
+                    "supportedDigestAlgorithms": ["sha"]
+                },
+                "urn:ietf:params:jmap:principals": {}
+            },
+            "accounts": {
+                "u1": {
+                    "name": "alice@fastmail.com",
+                    "isPersonal": true,
+                    "accountCapabilities": { "urn:ietf:params:jmap:mail": {} }
+                }
+            },
+            "primaryAccounts": {},
+            "apiUrl": "https://api.fastmail.com/jmap/api/"
+        }));
+
+        assert!(client.has_capability("urn:ietf:params:jmap:mail"));
+        assert!(client.has_blob_capability());
+        assert!(client.has_principals_capability());
+        assert!(!client.has_capability("urn:ietf:params:jmap:submission"));
+        assert_eq!(client.blob_capability().unwrap().max_data_sources, 64);
+    }
+
+    #[tokio::test]
+    async fn test_construction_does_not_need_an_account_email() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "capabilities": { "urn:ietf:params:jmap:core": {} },
+                "accounts": {
+                    "s1": {
+                        "name": "Team mailbox",
+                        "isPersonal": false,
+                        "accountCapabilities": { "urn:ietf:params:jmap:mail": {} }
+                    }
+                },
+                "primaryAccounts": { "urn:ietf:params:jmap:mail": "s1" },
+                "apiUrl": format!("{}/api/", server.uri())
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "methodResponses": [["Identity/get", { "list": [] }, "0"]],
+                "sessionState": "s"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = FastmailClient::new_with_session_url("token".to_string(), &server.uri())
+            .await
+            .unwrap();
+        assert_eq!(client.account_id(), "s1");
+        assert!(client.account_email().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_new_emails_since_follows_has_more_changes() {
+        use wiremock::matchers::{body_string_contains, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let changes = |old: &str, new: &str, more: bool, created: &str| {
+            json!({
+                "methodResponses": [["Email/changes", {
+                    "accountId": "u1",
+                    "oldState": old,
+                    "newState": new,
+                    "hasMoreChanges": more,
+                    "created": [created],
+                    "updated": [],
+                    "destroyed": []
+                }, "0"]],
+                "sessionState": "s"
+            })
+        };
+        Mock::given(method("POST"))
+            .and(body_string_contains(r#""sinceState":"e1""#))
+            .respond_with(ResponseTemplate::new(200).set_body_json(changes("e1", "e2", true, "m1")))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains(r#""sinceState":"e2""#))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(changes("e2", "e3", false, "m2")),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains(r#""ids":["m1","m2"]"#))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "methodResponses": [["Email/get", {
+                    "accountId": "u1",
+                    "state": "e3",
+                    "list": [{ "id": "m1" }, { "id": "m2" }],
+                    "notFound": []
+                }, "0"]],
+                "sessionState": "s"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = client_for_session(json!({
+            "capabilities": { "urn:ietf:params:jmap:core": {} },
+            "accounts": {
+                "u1": {
+                    "name": "alice@fastmail.com",
+                    "isPersonal": true,
+                    "accountCapabilities": { "urn:ietf:params:jmap:mail": {} }
+                }
+            },
+            "primaryAccounts": {},
+            "apiUrl": format!("{}/api/", server.uri())
+        }));
+        let (emails, state) = client.new_emails_since("e1").await.unwrap();
+        let ids: Vec<&str> = emails.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["m1", "m2"]);
+        assert_eq!(state, "e3");
+    }
+
+    #[tokio::test]
+    async fn test_compose_forward_quotes_html_only_originals_as_text() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "methodResponses": [["Email/get", {
+                    "accountId": "u1",
+                    "state": "e1",
+                    "list": [{
+                        "id": "m1",
+                        "subject": "Lunch",
+                        "textBody": [{ "partId": "1", "type": "text/html" }],
+                        "bodyValues": {
+                            "1": { "value": "<div><p>See you at <b>noon</b></p></div>" }
+                        }
+                    }],
+                    "notFound": []
+                }, "0"]],
+                "sessionState": "s"
+            })))
+            .mount(&server)
+            .await;
+
+        let client = client_for_session(json!({
+            "capabilities": { "urn:ietf:params:jmap:core": {} },
+            "accounts": {
+                "u1": {
+                    "name": "alice@fastmail.com",
+                    "isPersonal": true,
+                    "accountCapabilities": { "urn:ietf:params:jmap:mail": {} }
+                }
+            },
+            "primaryAccounts": {},
+            "apiUrl": format!("{}/api/", server.uri())
+        }));
+        let email = client
+            .compose_forward("m1", &["bob@example.com".to_string()], "FYI")
+            .await
+            .unwrap();
+        let body = serde_json::to_value(&email).unwrap()["bodyValues"]["body"]["value"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(body.contains("> See you at noon"), "{}", body);
+        assert!(!body.contains('<'), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_send_is_filed_in_sent_and_cancel_restores_the_draft() {
+        use wiremock::matchers::{body_string_contains, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let respond = |name: &str, args: Value| {
+            ResponseTemplate::new(200).set_body_json(json!({
+                "methodResponses": [[name, args, "0"]],
+                "sessionState": "s"
+            }))
+        };
+        Mock::given(method("POST"))
+            .and(body_string_contains("Identity/get"))
+            .respond_with(respond(
+                "Identity/get",
+                json!({ "list": [{ "id": "i1", "name": "", "email": "alice@fastmail.com" }] }),
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("Mailbox/get"))
+            .respond_with(respond(
+                "Mailbox/get",
+                json!({ "list": [
+                    { "id": "mb-drafts", "name": "Drafts", "role": "drafts" },
+                    { "id": "mb-sent", "name": "Sent", "role": "sent" }
+                ] }),
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains(r#""create":{"new""#))
+            .respond_with(respond(
+                "Email/set",
+                json!({ "created": { "new": { "id": "m1" } } }),
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("HOLDUNTIL"))
+            .and(body_string_contains(
+                r##""onSuccessUpdateEmail":{"#sub":{"keywords/$draft":null,"keywords/$seen":true,"mailboxIds":{"mb-sent":true}}}"##,
+            ))
+            .respond_with(respond(
+                "EmailSubmission/set",
+                json!({ "created": { "sub": { "id": "s1", "identityId": "i1", "emailId": "m1" } } }),
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("EmailSubmission/get"))
+            .respond_with(respond(
+                "EmailSubmission/get",
+                json!({ "list": [{ "id": "s1", "identityId": "i1", "emailId": "m1" }] }),
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains(r#""undoStatus":"canceled""#))
+            .respond_with(respond(
+                "EmailSubmission/set",
+                json!({ "updated": { "s1": null } }),
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains(
+                r#""update":{"m1":{"keywords/$draft":true,"mailboxIds":{"mb-drafts":true}}}"#,
+            ))
+            .respond_with(respond("Email/set", json!({ "updated": { "m1": null } })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = client_for_session(json!({
+            "capabilities": { "urn:ietf:params:jmap:core": {} },
+            "accounts": {
+                "u1": {
+                    "name": "alice@fastmail.com",
+                    "isPersonal": true,
+                    "accountCapabilities": { "urn:ietf:params:jmap:mail": {} }
+                }
+            },
+            "primaryAccounts": {},
+            "apiUrl": format!("{}/api/", server.uri())
+        }));
+        let email = text_email(
+            addresses(&["bob@example.com".to_string()]),
+            Vec::new(),
+            Vec::new(),
+            "Reminder",
+            "Tomorrow",
+        );
+        let send_at = "2030-01-01T09:00:00Z".parse().ok();
+        let submission = client.submit_email(email, send_at, None).await.unwrap();
+        assert_eq!(submission.email_id, "m1");
+        client.cancel_submission("s1").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_attachment_names_the_blob() {
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "methodResponses": [["Email/get", {
+                    "accountId": "u1",
+                    "state": "e1",
+                    "list": [{
+                        "id": "m1",
+                        "attachments": [
+                            { "partId": "2", "blobId": "B2", "type": "text/plain", "name": "notes v2.txt" },
+                            { "partId": "3", "blobId": "B3", "type": "image/png" }
+                        ]
+                    }],
+                    "notFound": []
+                }, "0"]],
+                "sessionState": "s"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/download/u1/B2/notes%20v2.txt"))
+            .and(query_param("type", "text/plain"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("hello"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        // An attachment without a name is fetched as "attachment"
+        Mock::given(method("GET"))
+            .and(path("/download/u1/B3/attachment"))
+            .and(query_param("type", "image/png"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0x89, b'P', b'N', b'G']))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = client_for_session(json!({
+            "capabilities": { "urn:ietf:params:jmap:core": {} },
+            "accounts": {
+                "u1": {
+                    "name": "alice@fastmail.com",
+                    "isPersonal": true,
+                    "accountCapabilities": { "urn:ietf:params:jmap:mail": {} }
+                }
+            },
+            "primaryAccounts": {},
+            "apiUrl": format!("{}/api/", server.uri()),
+            "downloadUrl": format!("{}/download/{{accountId}}/{{blobId}}/{{name}}?type={{type}}", server.uri())
+        }));
+
+        let dir = std::env::temp_dir().join(format!("fastmail-attachments-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let text = dir.join("notes.txt");
+        assert_eq!(
+            client.download_attachment("m1", "2", &text).await.unwrap(),
+            5
+        );
+        assert_eq!(std::fs::read_to_string(&text).unwrap(), "hello");
+        let image = dir.join("image.png");
+        assert_eq!(
+            client.download_attachment("m1", "3", &image).await.unwrap(),
+            4
+        );
+        assert!(client.download_attachment("m1", "9", &image).await.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_check_recipients_allowed() {
+        let client = client_for_session(json!({
+            "capabilities": {},
+            "accounts": { "u1": { "name": "alice@fastmail.com", "isPersonal": true } },
+            "primaryAccounts": {},
+            "apiUrl": "https://api.fastmail.com/jmap/api/"
+        }));
+        let dir = std::env::temp_dir().join(format!("fastmail-allow-list-{}", std::process::id()));
+        let path = dir.join("allowed-recipients.json");
+        Whitelist::load_from(&path)
+            .unwrap()
+            .add("bob@example.com".to_string())
+            .unwrap();
+
+        let blocked = client
+            .check_recipients_allowed(&path, &["bob@example.com", "eve@example.com"])
+            .unwrap();
+        assert_eq!(blocked, vec!["eve@example.com"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_principals_capabilities() {
+        let client = client_for_session(json!({
+            "capabilities": { "urn:ietf:params:jmap:principals": {} },
+            "accounts": {
+                "u1": {
+                    "name": "alice@fastmail.com",
+                    "isPersonal": true,
+                    "accountCapabilities": {
+                        "urn:ietf:params:jmap:principals": { "currentUserPrincipalId": "p1" },
+                        "urn:ietf:params:jmap:principals:owner": {
+                            "accountIdForPrincipal": "u1",
+                            "principalId": "p1"
+                        }
+                    }
+                }
+            },
+            "primaryAccounts": {},
+            "apiUrl": "https://api.fastmail.com/jmap/api/"
+        }));
+
+        assert_eq!(client.current_principal_id().as_deref(), Some("p1"));
+        assert_eq!(
+            client.owner_capability().unwrap().account_id_for_principal,
+            "u1"
+        );
+    }
+
+    #[test]
+    fn test_reply_recipients() {
+        let original: Email = serde_json::from_value(json!({
+            "id": "m1",
+            "from": [{"email": "alice@example.com"}],
+            "to": [{"email": "me@example.com"}, {"email": "bob@example.com"}],
+            "cc": [{"email": "Alice@example.com"}, {"email": "carol@example.com"}]
+        }))
+        .unwrap();
+        let own = vec!["me@example.com".to_string()];
+
+        let (to, cc) = reply_recipients(&original, false, &own);
+        assert_eq!(to.len(), 1);
+        assert_eq!(to[0].email, "alice@example.com");
+        assert!(cc.is_empty());
+
+        let (_, cc) = reply_recipients(&original, true, &own);
+        let cc: Vec<_> = cc.iter().map(|a| a.email.as_str()).collect();
+        assert_eq!(cc, vec!["bob@example.com", "carol@example.com"]);
+    }
+
+    #[test]
+    fn test_default_identity_email() {
+        let identities: Vec<Identity> = serde_json::from_value(json!([
+            { "id": "i2", "name": "Alias", "email": "alias@example.com", "mayDelete": true },
+            { "id": "i1", "name": "Alice", "email": "alice@fastmail.com", "mayDelete": false }
+        ]))
+        .unwrap();
+        assert_eq!(
+            default_identity_email(&identities).as_deref(),
+            Some("alice@fastmail.com")
+        );
+        assert_eq!(
+            default_identity_email(&identities[..1]).as_deref(),
+            Some("alias@example.com")
+        );
+        assert_eq!(default_identity_email(&[]), None);
+    }
+
+    #[test]
+    fn test_select_mailbox_prefers_id() {
+        let mailboxes: Vec<Mailbox> = serde_json::from_value(json!([
+            { "id": "Archive", "name": "Old stuff" },
+            { "id": "mb2", "name": "Archive" },
+            { "id": "mb3", "name": "Receipts" }
+        ]))
+        .unwrap();
+        assert_eq!(
+            select_mailbox(mailboxes.clone(), "Archive").unwrap().name,
+            "Old stuff"
+        );
+        assert_eq!(
+            select_mailbox(mailboxes.clone(), "Receipts").unwrap().id,
+            "mb3"
+        );
+        assert!(select_mailbox(mailboxes, "Spam").is_err());
+    }
+
+    #[test]
+    fn test_select_identity() {
+        let identities = || -> Vec<Identity> {
+            serde_json::from_value(json!([
+                { "id": "i1", "name": "Alice", "email": "alice@fastmail.com" },
+                { "id": "i2", "name": "Alias", "email": "alias@example.com" }
+            ]))
+            .unwrap()
+        };
+        assert_eq!(
+            select_identity(identities(), "i2").unwrap().email,
+            "alias@example.com"
+        );
+        assert_eq!(
+            select_identity(identities(), "Alias@Example.com")
+                .unwrap()
+                .id,
+            "i2"
+        );
+
+        let err = select_identity(identities(), "bob@example.com")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("alice@fastmail.com (i1)"));
+        assert!(err.contains("alias@example.com (i2)"));
+    }
+
+    #[test]
+    fn test_masked_email_patch_only_has_given_fields() {
+        assert_eq!(
+            masked_email_patch(Some("Shopping"), None),
+            json!({"description": "Shopping"})
+        );
+        assert_eq!(
+            masked_email_patch(None, Some("https://example.com")),
+            json!({"forDomain": "https://example.com"})
+        );
+        assert_eq!(masked_email_patch(None, None), json!({}));
+    }
+
+    #[test]
+    fn test_find_masked_email_by_address() {
+        let emails: Vec<MaskedEmail> = serde_json::from_value(json!([{
+            "id": "me1",
+            "email": "abc.123@fastmail.com",
+            "state": "enabled",
+            "forDomain": "https://example.com",
+            "description": "",
+            "createdAt": "2024-01-01T00:00:00Z",
+            "createdBy": "cli"
+        }]))
+        .unwrap();
+        assert_eq!(
+            find_masked_email(&emails, "ABC.123@fastmail.com")
+                .unwrap()
+                .id,
+            "me1"
+        );
+        assert_eq!(
+            find_masked_email(&emails, "nope@fastmail.com")
+                .unwrap_err()
+                .to_string(),
+            "No masked email with address nope@fastmail.com"
+        );
+    }
 }
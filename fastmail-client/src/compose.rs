@@ -0,0 +1,203 @@
+// fastmail-client/src/compose.rs
+//! Structured description of an outgoing email, as read by `mail send --json`
+
+use crate::client::addresses;
+use anyhow::{anyhow, Result};
+use jmap_client::{EmailBodyPart, EmailBodyValue, EmailCreate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Everything needed to send one email
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmailComposeSpec {
+    pub to: Vec<String>,
+    #[serde(default)]
+    pub cc: Vec<String>,
+    #[serde(default)]
+    pub bcc: Vec<String>,
+    pub subject: String,
+    #[serde(default)]
+    pub text_body: Option<String>,
+    #[serde(default)]
+    pub html_body: Option<String>,
+    /// Local files to upload and attach
+    #[serde(default)]
+    pub attachments: Vec<PathBuf>,
+}
+
+/// A file already uploaded as a blob
+#[derive(Debug, Clone)]
+pub struct UploadedAttachment {
+    pub blob_id: String,
+    pub name: String,
+    pub type_: String,
+}
+
+impl EmailComposeSpec {
+    /// Check the spec can be sent before anything is uploaded
+    pub fn validate(&self) -> Result<()> {
+        if self.to.is_empty() && self.cc.is_empty() && self.bcc.is_empty() {
+            return Err(anyhow!("At least one recipient is required"));
+        }
+        if self.text_body.is_none() && self.html_body.is_none() {
+            return Err(anyhow!("One of text_body or html_body is required"));
+        }
+        Ok(())
+    }
+
+    /// All recipient addresses
+    pub fn recipients(&self) -> Vec<&str> {
+        self.to
+            .iter()
+            .chain(&self.cc)
+            .chain(&self.bcc)
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Build the email as a bodyStructure (RFC 8621 §4.6): text and HTML become
+    /// multipart/alternative, and attachments wrap that in multipart/mixed
+    pub fn to_email_create(&self, attachments: Vec<UploadedAttachment>) -> Result<EmailCreate> {
+        self.validate()?;
+
+        let mut body_values = HashMap::new();
+        let mut alternatives = Vec::new();
+        for (part_id, type_, value) in [
+            ("text", "text/plain", &self.text_body),
+            ("html", "text/html", &self.html_body),
+        ] {
+            if let Some(value) = value {
+                body_values.insert(
+                    part_id.to_string(),
+                    EmailBodyValue {
+                        value: value.clone(),
+                        is_encoding_problem: false,
+                        is_truncated: false,
+                    },
+                );
+                alternatives.push(EmailBodyPart {
+                    part_id: Some(part_id.to_string()),
+                    type_: Some(type_.to_string()),
+                    ..Default::default()
+                });
+            }
+        }
+
+        let mut body = if alternatives.len() == 1 {
+            alternatives.remove(0)
+        } else {
+            multipart("multipart/alternative", alternatives)
+        };
+        if !attachments.is_empty() {
+            let mut parts = vec![body];
+            parts.extend(attachments.into_iter().map(|a| EmailBodyPart {
+                blob_id: Some(a.blob_id),
+                name: Some(a.name),
+                type_: Some(a.type_),
+                disposition: Some("attachment".to_string()),
+                ..Default::default()
+            }));
+            body = multipart("multipart/mixed", parts);
+        }
+
+        let non_empty = |list: &[String]| (!list.is_empty()).then(|| addresses(list));
+        Ok(EmailCreate {
+            to: non_empty(&self.to),
+            cc: non_empty(&self.cc),
+            bcc: non_empty(&self.bcc),
+            subject: Some(self.subject.clone()),
+            body_structure: Some(body),
+            body_values: Some(body_values),
+            ..Default::default()
+        })
+    }
+}
+
+fn multipart(type_: &str, sub_parts: Vec<EmailBodyPart>) -> EmailBodyPart {
+    EmailBodyPart {
+        type_: Some(type_.to_string()),
+        sub_parts: Some(sub_parts),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_spec_requires_a_body() {
+        let spec: EmailComposeSpec = serde_json::from_value(json!({
+            "to": ["a@example.com"],
+            "subject": "Hi"
+        }))
+        .unwrap();
+        assert!(spec.validate().is_err());
+        assert!(serde_json::from_value::<EmailComposeSpec>(json!({
+            "to": [], "subject": "Hi", "body": "typo"
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_spec_with_html_and_attachment() {
+        let spec: EmailComposeSpec = serde_json::from_value(json!({
+            "to": ["a@example.com"],
+            "subject": "Report",
+            "text_body": "See attached",
+            "html_body": "<p>See attached</p>"
+        }))
+        .unwrap();
+        let email = spec
+            .to_email_create(vec![UploadedAttachment {
+                blob_id: "B1".to_string(),
+                name: "report.pdf".to_string(),
+                type_: "application/pdf".to_string(),
+            }])
+            .unwrap();
+
+        let value = serde_json::to_value(&email).unwrap();
+        let structure = &value["bodyStructure"];
+        assert_eq!(structure["type"], "multipart/mixed");
+        assert_eq!(structure["subParts"][0]["type"], "multipart/alternative");
+        assert_eq!(structure["subParts"][0]["subParts"][1]["partId"], "html");
+        assert_eq!(structure["subParts"][1]["blobId"], "B1");
+        assert_eq!(structure["subParts"][1]["disposition"], "attachment");
+        assert_eq!(value["bodyValues"]["text"]["value"], "See attached");
+        assert!(value.get("textBody").is_none());
+    }
+
+    #[test]
+    fn test_attachment_part_of_a_plain_text_email() {
+        let spec: EmailComposeSpec = serde_json::from_value(json!({
+            "to": ["a@example.com"],
+            "subject": "Photo",
+            "text_body": "Here it is"
+        }))
+        .unwrap();
+        let email = spec
+            .to_email_create(vec![UploadedAttachment {
+                blob_id: "B2".to_string(),
+                name: "photo.jpg".to_string(),
+                type_: "image/jpeg".to_string(),
+            }])
+            .unwrap();
+
+        let value = serde_json::to_value(&email).unwrap();
+        let structure = &value["bodyStructure"];
+        assert_eq!(structure["type"], "multipart/mixed");
+        assert_eq!(structure["subParts"][0]["partId"], "text");
+        assert_eq!(
+            structure["subParts"][1],
+            json!({
+                "blobId": "B2",
+                "name": "photo.jpg",
+                "type": "image/jpeg",
+                "disposition": "attachment"
+            })
+        );
+    }
+}
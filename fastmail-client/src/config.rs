@@ -1,9 +1,33 @@
 // fastmail-client/src/config.rs
+use crate::client::FASTMAIL_SESSION_URL;
 use anyhow::{anyhow, Result};
+use chrono_tz::Tz;
 use directories::BaseDirs;
+use jmap_client::encode_base64;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Request timeout when the config does not set one
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Dotted keys readable and writable with `Config::get_key` / `Config::set_key`
+const CONFIG_KEYS: &[&str] = &[
+    "account.email",
+    "account.timezone",
+    "account_id",
+    "safety.require_new_recipient_flag",
+    "safety.require_confirm",
+    "dav_endpoints.caldav",
+    "dav_endpoints.carddav",
+    "dav_endpoints.webdav",
+    "session_url",
+    "timeout",
+    "default_account",
+];
 
 #[derive(Debug, Deserialize, Serialize, Default)]
 pub struct Config {
@@ -11,7 +35,8 @@ pub struct Config {
     pub account: AccountConfig,
     #[serde(default)]
     pub safety: SafetyConfig,
-    /// Account ID for DAV operations (retrieved from JMAP session)
+    /// Primary mail account ID from the JMAP session, recorded by `setup`.
+    /// DAV paths use the account email, not this.
     #[serde(default)]
     pub account_id: Option<String>,
     /// Authentication token for JMAP API
@@ -23,11 +48,43 @@ pub struct Config {
     /// DAV endpoint configuration
     #[serde(default)]
     pub dav_endpoints: Option<DavEndpoints>,
+    /// JMAP session URL, for servers other than Fastmail (e.g. Stalwart)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_url: Option<String>,
+    /// Seconds before a JMAP or DAV request is abandoned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Log JMAP request and response bodies to stderr (`--verbose`); never saved
+    #[serde(skip)]
+    pub verbose: bool,
+    /// Account used when `--account` is not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_account: Option<String>,
+    /// Named logins, each with its own credentials (`[accounts.<name>]`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub accounts: BTreeMap<String, AccountProfile>,
+    /// File this config is saved to (`--config`); the platform config dir when unset
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+/// Credentials for one Fastmail login
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct AccountProfile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dav_password: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Default)]
 pub struct AccountConfig {
     pub email: Option<String>,
+    /// IANA timezone (e.g. "Europe/London") for calendar times without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -66,6 +123,10 @@ pub struct DavEndpoints {
     pub webdav: String,
 }
 
+fn default_session_url() -> String {
+    FASTMAIL_SESSION_URL.to_string()
+}
+
 fn default_caldav_url() -> String {
     "https://caldav.fastmail.com".to_string()
 }
@@ -90,39 +151,113 @@ impl Default for DavEndpoints {
 
 impl Config {
     pub fn load() -> Result<Self> {
-        let config_dir = Self::config_dir()?;
+        Self::load_from(None)
+    }
+
+    /// Like `load`, reading `path` instead of the platform config dir when given
+    pub fn load_from(path: Option<&Path>) -> Result<Self> {
+        let mut config = Self::load_file_from(path)?;
+
+        // Allow DAV credentials to be overridden by environment variables
+        if let Ok(dav_password) = std::env::var("FASTMAIL_DAV_PASSWORD") {
+            config.dav_password = Some(dav_password);
+        }
 
-        if !config_dir.exists() {
-            fs::create_dir_all(&config_dir)?;
+        // Allow email to be overridden by environment variable
+        if let Ok(email) = std::env::var("FASTMAIL_EMAIL") {
+            config.account.email = Some(email);
         }
 
-        let config_path = config_dir.join("config.toml");
+        // Allow the JMAP server to be overridden by environment variable
+        if let Ok(session_url) = std::env::var("FASTMAIL_SESSION_URL") {
+            config.session_url = Some(session_url);
+        }
+
+        Ok(config)
+    }
+
+    /// Load config.toml as written, without environment overrides, so that
+    /// saving it back does not persist them
+    pub fn load_file() -> Result<Self> {
+        Self::load_file_from(None)
+    }
+
+    /// Like `load_file`, reading `path` instead of the platform config dir when given
+    pub fn load_file_from(path: Option<&Path>) -> Result<Self> {
+        let config_path = match path {
+            Some(path) => path.to_path_buf(),
+            None => Self::config_dir()?.join("config.toml"),
+        };
 
         if !config_path.exists() {
-            let default = Self::default();
+            let default = Self {
+                path: Some(config_path),
+                ..Default::default()
+            };
             default.save()?;
             return Ok(default);
         }
 
         let content = fs::read_to_string(&config_path)?;
-        let mut config: Config = toml::from_str(&content)?;
+        let mut config: Config = toml::from_str(&content)
+            .map_err(|e| anyhow!("Invalid config {}: {}", config_path.display(), e))?;
+        config.path = Some(config_path);
+        Ok(config)
+    }
 
-        // Allow DAV credentials to be overridden by environment variables
-        if let Ok(dav_password) = std::env::var("FASTMAIL_DAV_PASSWORD") {
-            config.dav_password = Some(dav_password);
-        }
+    /// Load the config with the credentials of `account`, or of the default
+    /// account, in place of the top-level ones. A named account takes
+    /// precedence over FASTMAIL_* environment variables; the default does not.
+    pub fn load_account(account: Option<&str>) -> Result<Self> {
+        Self::load()?.with_account(account)
+    }
 
-        // Allow email to be overridden by environment variable
-        if let Ok(email) = std::env::var("FASTMAIL_EMAIL") {
-            config.account.email = Some(email);
+    /// Apply the credentials of `account` as `load_account` does
+    pub fn with_account(mut self, account: Option<&str>) -> Result<Self> {
+        let Some(profile) = self.account_profile(account)?.map(|(_, p)| p.clone()) else {
+            return Ok(self);
+        };
+
+        let env_wins = |var: &str| account.is_none() && std::env::var_os(var).is_some();
+        if profile.email.is_some() && !env_wins("FASTMAIL_EMAIL") {
+            self.account.email = profile.email;
+        }
+        if profile.dav_password.is_some() && !env_wins("FASTMAIL_DAV_PASSWORD") {
+            self.dav_password = profile.dav_password;
         }
+        if let Some(token) = profile.token {
+            self.token = token;
+        }
+        Ok(self)
+    }
 
-        Ok(config)
+    /// The profile named `account`, falling back to `default_account`
+    pub fn account_profile(
+        &self,
+        account: Option<&str>,
+    ) -> Result<Option<(&str, &AccountProfile)>> {
+        let Some(name) = account.or(self.default_account.as_deref()) else {
+            return Ok(None);
+        };
+        match self.accounts.get_key_value(name) {
+            Some((name, profile)) => Ok(Some((name.as_str(), profile))),
+            None if self.accounts.is_empty() => Err(anyhow!(
+                "Unknown account '{}': no [accounts.<name>] sections in config.toml",
+                name
+            )),
+            None => Err(anyhow!(
+                "Unknown account '{}'; configured accounts: {}",
+                name,
+                self.accounts.keys().cloned().collect::<Vec<_>>().join(", ")
+            )),
+        }
     }
 
     pub fn save(&self) -> Result<()> {
-        let config_dir = Self::config_dir()?;
-        let config_path = config_dir.join("config.toml");
+        let config_path = self.file_path()?;
+        if let Some(dir) = config_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
 
         let content = toml::to_string_pretty(self)?;
         fs::write(&config_path, content)?;
@@ -139,26 +274,175 @@ impl Config {
         Ok(())
     }
 
-    fn config_dir() -> Result<PathBuf> {
+    /// The file this config is saved to
+    pub fn file_path(&self) -> Result<PathBuf> {
+        match &self.path {
+            Some(path) => Ok(path.clone()),
+            None => Ok(Self::config_dir()?.join("config.toml")),
+        }
+    }
+
+    /// The recipient allow list, kept next to the config file
+    pub fn whitelist_path(&self) -> Result<PathBuf> {
+        Ok(self.file_path()?.with_file_name("allowed-recipients.json"))
+    }
+
+    pub(crate) fn config_dir() -> Result<PathBuf> {
         let base_dirs =
             BaseDirs::new().ok_or_else(|| anyhow!("Cannot determine config directory"))?;
         Ok(base_dirs.config_dir().join("fastmail-cli"))
     }
 
+    /// Every credential in the config, for masking in error messages and logs.
+    /// DAV passwords are included as the Basic auth value a server might echo.
+    pub fn secrets(&self) -> Vec<String> {
+        let profiles = self.accounts.values().map(|profile| {
+            (
+                profile.email.as_deref().or(self.account_email()),
+                profile.token.as_deref(),
+                profile.dav_password.as_deref(),
+            )
+        });
+        let top_level = (
+            self.account_email(),
+            Some(self.token.as_str()),
+            self.dav_password.as_deref(),
+        );
+
+        let mut secrets = Vec::new();
+        for (email, token, dav_password) in std::iter::once(top_level).chain(profiles) {
+            secrets.extend(token.filter(|t| !t.is_empty()).map(str::to_string));
+            if let Some(password) = dav_password.filter(|p| !p.is_empty()) {
+                secrets.push(password.to_string());
+                if let Some(email) = email {
+                    secrets.push(encode_base64(format!("{}:{}", email, password).as_bytes()));
+                }
+            }
+        }
+        secrets
+    }
+
+    /// Effective value of a dotted key such as `safety.require_confirm`,
+    /// with defaults filled in
+    pub fn get_key(&self, key: &str) -> Result<Value> {
+        Ok(match key {
+            "account.email" => json!(self.account.email),
+            "account.timezone" => json!(self.account.timezone),
+            "account_id" => json!(self.account_id),
+            "safety.require_new_recipient_flag" => json!(self.safety.require_new_recipient_flag),
+            "safety.require_confirm" => json!(self.safety.require_confirm),
+            "dav_endpoints.caldav" => json!(self.get_caldav_url()),
+            "dav_endpoints.carddav" => json!(self.get_carddav_url()),
+            "dav_endpoints.webdav" => json!(self.get_webdav_url()),
+            "session_url" => json!(self.get_session_url()),
+            "timeout" => json!(self.get_timeout().as_secs()),
+            "default_account" => json!(self.default_account),
+            _ => return Err(unknown_key(key)),
+        })
+    }
+
+    /// Parse `value` for a dotted key and store it. An empty value clears
+    /// an optional key.
+    pub fn set_key(&mut self, key: &str, value: &str) -> Result<()> {
+        let optional = |value: &str| (!value.is_empty()).then(|| value.to_string());
+        match key {
+            "account.email" => self.account.email = optional(value),
+            "account.timezone" => {
+                if !value.is_empty() && value.parse::<Tz>().is_err() {
+                    return Err(anyhow!("{}: unknown timezone '{}'", key, value));
+                }
+                self.account.timezone = optional(value);
+            }
+            "account_id" => self.account_id = optional(value),
+            "safety.require_new_recipient_flag" => {
+                self.safety.require_new_recipient_flag = parse_bool(key, value)?
+            }
+            "safety.require_confirm" => self.safety.require_confirm = parse_bool(key, value)?,
+            "dav_endpoints.caldav" | "dav_endpoints.carddav" | "dav_endpoints.webdav" => {
+                let url = parse_url(key, value)?;
+                let endpoints = self.dav_endpoints.get_or_insert_with(DavEndpoints::default);
+                match key {
+                    "dav_endpoints.caldav" => endpoints.caldav = url,
+                    "dav_endpoints.carddav" => endpoints.carddav = url,
+                    _ => endpoints.webdav = url,
+                }
+            }
+            "session_url" => {
+                self.session_url = match value {
+                    "" => None,
+                    _ => Some(parse_url(key, value)?),
+                }
+            }
+            "timeout" => {
+                self.timeout = match value {
+                    "" => None,
+                    _ => match value.parse::<u64>() {
+                        Ok(secs) if secs > 0 => Some(secs),
+                        _ => {
+                            return Err(anyhow!(
+                                "{}: expected a positive number of seconds, got '{}'",
+                                key,
+                                value
+                            ))
+                        }
+                    },
+                }
+            }
+            "default_account" => {
+                if !value.is_empty() {
+                    self.account_profile(Some(value))?;
+                }
+                self.default_account = optional(value);
+            }
+            _ => return Err(unknown_key(key)),
+        }
+        Ok(())
+    }
+
     pub fn account_email(&self) -> Option<&str> {
         self.account.email.as_deref()
     }
 
+    /// Timezone for floating calendar times, defaulting to UTC
+    pub fn timezone(&self) -> Result<Tz> {
+        match self.account.timezone.as_deref() {
+            Some(name) => name
+                .parse()
+                .map_err(|_| anyhow!("Unknown timezone in config: {}", name)),
+            None => Ok(Tz::UTC),
+        }
+    }
+
     /// Get the username for DAV authentication (email address)
     /// DAV endpoints use HTTP Basic Auth with email as username
     pub fn get_dav_username(&self) -> Result<&str> {
         self.account.email.as_deref().ok_or_else(|| {
             anyhow::anyhow!(
-                "DAV username (email) not set. Please set FASTMAIL_EMAIL environment variable"
+                "DAV username (email) not set. Run `fastmail setup` or set the FASTMAIL_EMAIL environment variable"
+            )
+        })
+    }
+
+    /// Get the app password for DAV authentication. Fastmail's CalDAV, CardDAV
+    /// and WebDAV servers only accept app passwords, not API tokens.
+    pub fn get_dav_password(&self) -> Result<&str> {
+        self.dav_password.as_deref().ok_or_else(|| {
+            anyhow!(
+                "DAV password not set. Please set FASTMAIL_DAV_PASSWORD environment variable with your Fastmail app password. Generate one at: https://www.fastmail.com/settings/passwords"
             )
         })
     }
 
+    /// Get the JMAP session URL, defaulting to Fastmail's
+    pub fn get_session_url(&self) -> String {
+        self.session_url.clone().unwrap_or_else(default_session_url)
+    }
+
+    /// Get the request timeout, defaulting to 30 seconds
+    pub fn get_timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS))
+    }
+
     /// Get the CalDAV base URL
     pub fn get_caldav_url(&self) -> String {
         self.dav_endpoints
@@ -183,3 +467,195 @@ impl Config {
             .unwrap_or_else(default_webdav_url)
     }
 }
+
+fn unknown_key(key: &str) -> anyhow::Error {
+    if matches!(key, "token" | "dav_password") || key.starts_with("accounts.") {
+        return anyhow!(
+            "{} holds credentials; use `fastmail setup` or the FASTMAIL_* environment variables",
+            key
+        );
+    }
+    anyhow!(
+        "Unknown config key '{}'; expected one of: {}",
+        key,
+        CONFIG_KEYS.join(", ")
+    )
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    value
+        .parse()
+        .map_err(|_| anyhow!("{}: expected true or false, got '{}'", key, value))
+}
+
+/// An absolute http(s) URL
+fn parse_url(key: &str, value: &str) -> Result<String> {
+    let uri: http::Uri = value
+        .parse()
+        .map_err(|e| anyhow!("{}: invalid URL '{}': {}", key, value, e))?;
+    if !matches!(uri.scheme_str(), Some("http" | "https")) || uri.host().is_none() {
+        return Err(anyhow!("{}: expected an http(s) URL, got '{}'", key, value));
+    }
+    Ok(value.to_string())
+}
+
+#[cfg(test)]
+impl Config {
+    /// Config pointing every DAV endpoint at a local mock server
+    pub(crate) fn for_mock_server(uri: &str) -> Self {
+        Self {
+            account: AccountConfig {
+                email: Some("me@example.com".to_string()),
+                timezone: None,
+            },
+            dav_password: Some("app-password".to_string()),
+            dav_endpoints: Some(DavEndpoints {
+                caldav: uri.to_string(),
+                carddav: uri.to_string(),
+                webdav: uri.to_string(),
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_account_profiles() {
+        let config: Config = toml::from_str(
+            r#"
+            default_account = "personal"
+
+            [accounts.personal]
+            token = "fmu1-personal"
+
+            [accounts.work]
+            email = "me@work.example"
+            token = "fmu1-work"
+            dav_password = "app-password"
+            "#,
+        )
+        .unwrap();
+
+        let (name, profile) = config.account_profile(None).unwrap().unwrap();
+        assert_eq!(name, "personal");
+        assert_eq!(profile.token.as_deref(), Some("fmu1-personal"));
+
+        let (_, work) = config.account_profile(Some("work")).unwrap().unwrap();
+        assert_eq!(work.dav_password.as_deref(), Some("app-password"));
+
+        let err = config.account_profile(Some("home")).unwrap_err();
+        assert!(err.to_string().contains("personal, work"));
+        assert!(Config::default().account_profile(None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_session_url_defaults_to_fastmail() {
+        assert_eq!(
+            Config::default().get_session_url(),
+            "https://api.fastmail.com/jmap/session"
+        );
+        let config: Config =
+            toml::from_str(r#"session_url = "https://mail.example.com/.well-known/jmap""#).unwrap();
+        assert_eq!(
+            config.get_session_url(),
+            "https://mail.example.com/.well-known/jmap"
+        );
+    }
+
+    #[test]
+    fn test_secrets_include_basic_auth_credentials() {
+        let config: Config = toml::from_str(
+            r#"
+            token = "fmu1-top"
+            dav_password = "pw1"
+
+            [account]
+            email = "me@example.com"
+
+            [accounts.work]
+            token = "fmu1-work"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.secrets(),
+            ["fmu1-top", "pw1", "bWVAZXhhbXBsZS5jb206cHcx", "fmu1-work"]
+        );
+        assert!(Config::default().secrets().is_empty());
+    }
+
+    #[test]
+    fn test_timeout_defaults_to_30_seconds() {
+        assert_eq!(Config::default().get_timeout(), Duration::from_secs(30));
+        let config: Config = toml::from_str("timeout = 5").unwrap();
+        assert_eq!(config.get_timeout(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_set_and_get_keys() {
+        let mut config = Config::default();
+        config.set_key("safety.require_confirm", "false").unwrap();
+        config
+            .set_key("dav_endpoints.caldav", "https://dav.example.com")
+            .unwrap();
+        config.set_key("timeout", "10").unwrap();
+        config.set_key("account.email", "me@example.com").unwrap();
+
+        assert_eq!(config.get_key("safety.require_confirm").unwrap(), false);
+        assert_eq!(
+            config.get_key("dav_endpoints.caldav").unwrap(),
+            "https://dav.example.com"
+        );
+        assert_eq!(
+            config.get_key("dav_endpoints.carddav").unwrap(),
+            "https://carddav.fastmail.com"
+        );
+        assert_eq!(config.get_key("timeout").unwrap(), 10);
+
+        config.set_key("account.email", "").unwrap();
+        assert!(config.get_key("account.email").unwrap().is_null());
+        config.set_key("timeout", "").unwrap();
+        assert_eq!(config.get_key("timeout").unwrap(), 30);
+    }
+
+    #[test]
+    fn test_set_key_validates_values() {
+        let mut config = Config::default();
+        assert!(config.set_key("safety.require_confirm", "yes").is_err());
+        assert!(config.set_key("timeout", "0").is_err());
+        assert!(config.set_key("session_url", "not a url").is_err());
+        assert!(config.set_key("dav_endpoints.webdav", "ftp://x").is_err());
+        assert!(config.set_key("account.timezone", "Mars/Base").is_err());
+        assert!(config.set_key("default_account", "work").is_err());
+        assert!(config.set_key("safety.unknown", "true").is_err());
+
+        let err = config.set_key("token", "fmu1-secret").unwrap_err();
+        assert!(err.to_string().contains("fastmail setup"));
+        assert!(config.get_key("accounts.work.token").is_err());
+        assert!(config.safety.require_confirm && config.timeout.is_none());
+    }
+
+    #[test]
+    fn test_load_and_save_at_a_custom_path() {
+        let dir = std::env::temp_dir().join(format!("fastmail-cli-config-{}", std::process::id()));
+        let path = dir.join("staging.toml");
+
+        let mut config = Config::load_file_from(Some(&path)).unwrap();
+        assert!(path.exists());
+        config.set_key("timeout", "7").unwrap();
+        config.save().unwrap();
+
+        let loaded = Config::load_from(Some(&path)).unwrap();
+        assert_eq!(loaded.timeout, Some(7));
+        assert_eq!(loaded.file_path().unwrap(), path);
+        assert_eq!(
+            loaded.whitelist_path().unwrap(),
+            dir.join("allowed-recipients.json")
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
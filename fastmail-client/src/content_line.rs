@@ -0,0 +1,161 @@
+// fastmail-client/src/content_line.rs
+//! Content-line helpers shared by the vCard (RFC 6350) and iCalendar
+//! (RFC 5545) code: folding, unfolding and TEXT escaping work the same in both.
+
+/// Longest content line allowed before folding, in octets
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Break one content line (without its line break) into lines of at most
+/// MAX_LINE_OCTETS joined by CRLF and a space. UTF-8 characters are never split.
+pub(crate) fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / 37);
+    let mut rest = line;
+    // The first line has the full width; continuations lose one octet to the space
+    let mut limit = MAX_LINE_OCTETS;
+    while rest.len() > limit {
+        let mut cut = limit;
+        while !rest.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        folded.push_str(&rest[..cut]);
+        folded.push_str("\r\n ");
+        rest = &rest[cut..];
+        limit = MAX_LINE_OCTETS - 1;
+    }
+    folded.push_str(rest);
+    folded
+}
+
+/// Fold every CRLF-terminated line of `content` with `fold_line`
+pub(crate) fn fold_lines(content: &str) -> String {
+    let mut folded = String::with_capacity(content.len());
+    for line in content.split_terminator("\r\n") {
+        folded.push_str(&fold_line(line));
+        folded.push_str("\r\n");
+    }
+    folded
+}
+
+/// Join folded content lines: a line break followed by a space or tab
+/// continues the previous line, and the break plus that one whitespace
+/// character are removed.
+pub(crate) fn unfold_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(previous)) => previous.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Escape a TEXT value or component (RFC 5545 §3.3.11, RFC 6350 §3.4)
+pub(crate) fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Undo escape_text; unknown escapes keep the escaped character
+pub(crate) fn unescape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => out.push('\n'),
+                Some(escaped) => out.push(escaped),
+                None => {}
+            },
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Split on unescaped `separator` and unescape each part
+pub(crate) fn split_escaped(value: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => parts.last_mut().unwrap().push('\n'),
+                Some(escaped) => parts.last_mut().unwrap().push(escaped),
+                None => {}
+            },
+            c if c == separator => parts.push(String::new()),
+            _ => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+/// Split a content line into key (name and parameters) and value at the first
+/// ':' that is not inside a quoted parameter value
+pub(crate) fn split_content_line(line: &str) -> Option<(&str, &str)> {
+    let mut in_quotes = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ':' if !in_quotes => return Some((&line[..i], &line[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_lines_keeps_utf8_characters_whole() {
+        let line = format!("SUMMARY:{}\r\n", "é".repeat(60));
+        let folded = fold_lines(&line);
+        assert!(folded.split_terminator("\r\n").all(|l| l.len() <= 75));
+        assert_eq!(unfold_lines(&folded).concat(), line.trim_end());
+    }
+
+    #[test]
+    fn test_fold_line_leaves_short_lines_alone() {
+        assert_eq!(fold_line("FN:Jane"), "FN:Jane");
+        let long = format!("NOTE:{}", "x".repeat(100));
+        let folded = fold_line(&long);
+        assert_eq!(folded.split("\r\n ").next().unwrap().len(), 75);
+        assert_eq!(unfold_lines(&folded), vec![long]);
+    }
+
+    #[test]
+    fn test_unfold_accepts_tabs_and_bare_newlines() {
+        assert_eq!(
+            unfold_lines("NOTE:one\n\ttwo\r\n three\r\nFN:x"),
+            vec!["NOTE:onetwothree", "FN:x"]
+        );
+    }
+
+    #[test]
+    fn test_escape_round_trip() {
+        let value = "a;b,c\\d\ne";
+        assert_eq!(escape_text(value), "a\\;b\\,c\\\\d\\ne");
+        assert_eq!(unescape_text(&escape_text(value)), value);
+        assert_eq!(
+            split_escaped("Work,Friends\\, old", ','),
+            vec!["Work", "Friends, old"]
+        );
+    }
+
+    #[test]
+    fn test_split_content_line_skips_quoted_colons() {
+        assert_eq!(
+            split_content_line("DTSTART;X-A=\"a:b\":20260101"),
+            Some(("DTSTART;X-A=\"a:b\"", "20260101"))
+        );
+        assert_eq!(split_content_line("no value"), None);
+    }
+}
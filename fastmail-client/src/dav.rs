@@ -1,15 +1,20 @@
 // fastmail-client/src/dav.rs
 use crate::config::Config;
 use anyhow::Result;
-use http::Uri;
+use http::{StatusCode, Uri};
 use hyper_rustls::HttpsConnectorBuilder;
 use hyper_util::client::legacy::Client;
 use hyper_util::rt::TokioExecutor;
 use libdav::dav::{
-    Delete, FindCollections, FoundCollection, GetProperty, ListResources, ListedResource,
-    PutResource, WebDavClient,
+    Delete, FindCollections, FoundCollection, GetProperty, ListedResource, Propfind, PutResource,
+    WebDavClient, WebDavError,
 };
+use libdav::names;
+use libdav::xmlutils::{check_multistatus, parse_statusline, validate_xml_response};
+use libdav::ResourceType;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
 use tower_http::auth::AddAuthorization;
 
 /// Generic DAV resource metadata
@@ -47,13 +52,25 @@ pub struct DavClient {
 /// This allows us to hide the complex type parameters from the public API.
 #[async_trait::async_trait]
 pub trait DavClientInner: Send + Sync {
-    async fn list_resources(&self, href: &str) -> Result<Vec<ListedResource>>;
+    async fn list_resources(&self, href: &str, depth: libdav::Depth)
+        -> Result<Vec<ListedResource>>;
+    async fn is_collection(&self, href: &str) -> Result<bool>;
+    async fn get_resource(&self, href: &str) -> Result<Vec<u8>>;
+    async fn create_collection(&self, href: &str) -> Result<()>;
+    async fn transfer(
+        &self,
+        method: &str,
+        href: &str,
+        destination: &str,
+        overwrite: bool,
+    ) -> Result<()>;
     async fn delete_resource(&self, href: &str) -> Result<()>;
     async fn put_resource(
         &self,
         href: &str,
         data: String,
         content_type: &str,
+        if_match: Option<&str>,
     ) -> Result<Option<String>>;
     async fn find_collections(&self, uri: &Uri) -> Result<Vec<FoundCollection>>;
     async fn get_property(
@@ -77,6 +94,7 @@ where
     C::Future: Send + 'static,
 {
     client: WebDavClient<C>,
+    timeout: Duration,
 }
 
 impl<C> Clone for DavClientInnerImpl<C>
@@ -94,6 +112,7 @@ where
     fn clone(&self) -> Self {
         Self {
             client: self.client.clone(),
+            timeout: self.timeout,
         }
     }
 }
@@ -111,13 +130,152 @@ where
     C::Error: Into<Box<dyn std::error::Error + Send + Sync>> + std::error::Error + Send + Sync,
     C::Future: Send + 'static,
 {
-    async fn list_resources(&self, href: &str) -> Result<Vec<ListedResource>> {
-        let response = self.client.request(ListResources::new(href)).await?;
-        Ok(response.resources)
+    async fn list_resources(
+        &self,
+        href: &str,
+        depth: libdav::Depth,
+    ) -> Result<Vec<ListedResource>> {
+        // libdav's ListResources is fixed at Depth: 1, so issue the PROPFIND directly
+        let propfind = Propfind::new(href)
+            .with_properties(&[
+                &names::RESOURCETYPE,
+                &names::GETCONTENTTYPE,
+                &names::GETETAG,
+            ])
+            .with_depth(depth);
+        let response = within(self.timeout, self.client.request(propfind)).await?;
+        let doc = response.xml_tree()?;
+
+        let mut resources = Vec::new();
+        for node in doc
+            .descendants()
+            .filter(|node| node.tag_name() == names::RESPONSE)
+        {
+            let text = |name: &libdav::PropertyName| {
+                node.descendants()
+                    .find(|n| n.tag_name() == *name)
+                    .and_then(|n| n.text())
+                    .map(|t| t.trim().to_string())
+            };
+            let Some(resource_href) = text(&names::HREF) else {
+                continue;
+            };
+            let resource_type = node
+                .descendants()
+                .find(|n| n.tag_name() == names::RESOURCETYPE);
+
+            resources.push(ListedResource {
+                href: resource_href,
+                status: text(&names::STATUS).and_then(|status| parse_statusline(&status).ok()),
+                content_type: text(&names::GETCONTENTTYPE),
+                etag: text(&names::GETETAG),
+                resource_type: ResourceType {
+                    is_collection: resource_type.is_some_and(|r| {
+                        r.descendants().any(|n| n.tag_name() == names::COLLECTION)
+                    }),
+                    is_calendar: resource_type
+                        .is_some_and(|r| r.descendants().any(|n| n.tag_name() == names::CALENDAR)),
+                    is_address_book: resource_type.is_some_and(|r| {
+                        r.descendants().any(|n| n.tag_name() == names::ADDRESSBOOK)
+                    }),
+                },
+            });
+        }
+
+        // At Depth: 1 and deeper, list the members rather than the collection itself
+        if depth != libdav::Depth::Zero {
+            let requested = decode_href(href);
+            resources.retain(|r| decode_href(&r.href) != requested);
+        }
+
+        Ok(resources)
+    }
+
+    async fn is_collection(&self, href: &str) -> Result<bool> {
+        let propfind = Propfind::new(href)
+            .with_properties(&[&names::RESOURCETYPE])
+            .with_depth(libdav::Depth::Zero);
+        let response = within(self.timeout, self.client.request(propfind)).await?;
+        let is_collection = response
+            .xml_tree()?
+            .descendants()
+            .any(|node| node.tag_name() == names::COLLECTION);
+        Ok(is_collection)
+    }
+
+    async fn get_resource(&self, href: &str) -> Result<Vec<u8>> {
+        let request = http::Request::get(self.client.relative_uri(href)?).body(String::new())?;
+        let (parts, body) = within(self.timeout, self.client.request_raw(request)).await?;
+        if !parts.status.is_success() {
+            return Err(anyhow::anyhow!("GET {} failed: {}", href, parts.status));
+        }
+        Ok(body.to_vec())
+    }
+
+    async fn create_collection(&self, href: &str) -> Result<()> {
+        let request = http::Request::builder()
+            .method("MKCOL")
+            .uri(self.client.relative_uri(href)?)
+            .body(String::new())?;
+        let (parts, _) = within(self.timeout, self.client.request_raw(request)).await?;
+        match parts.status {
+            StatusCode::CREATED => Ok(()),
+            StatusCode::METHOD_NOT_ALLOWED => {
+                Err(anyhow::anyhow!("Collection already exists: {}", href))
+            }
+            StatusCode::CONFLICT => Err(anyhow::anyhow!(
+                "Parent collection does not exist: {}",
+                parent_collection(href)
+            )),
+            status => Err(anyhow::anyhow!("MKCOL {} failed: {}", href, status)),
+        }
+    }
+
+    async fn transfer(
+        &self,
+        method: &str,
+        href: &str,
+        destination: &str,
+        overwrite: bool,
+    ) -> Result<()> {
+        let request = http::Request::builder()
+            .method(method)
+            .uri(self.client.relative_uri(href)?)
+            .header("Destination", destination)
+            .header("Overwrite", if overwrite { "T" } else { "F" })
+            .body(String::new())?;
+        let (parts, body) = within(self.timeout, self.client.request_raw(request)).await?;
+        match parts.status {
+            StatusCode::CREATED | StatusCode::NO_CONTENT => Ok(()),
+            // Some members of a collection could not be copied or moved
+            StatusCode::MULTI_STATUS => {
+                let doc = validate_xml_response(&parts, &body)?;
+                check_multistatus(doc.root_element()).map_err(|e| {
+                    anyhow::anyhow!(
+                        "{} {} -> {} partially failed: {}",
+                        method,
+                        href,
+                        destination,
+                        e
+                    )
+                })
+            }
+            StatusCode::PRECONDITION_FAILED => Err(anyhow::anyhow!(
+                "Destination already exists: {} (use --overwrite to replace it)",
+                destination
+            )),
+            status => Err(anyhow::anyhow!(
+                "{} {} -> {} failed: {}",
+                method,
+                href,
+                destination,
+                status
+            )),
+        }
     }
 
     async fn delete_resource(&self, href: &str) -> Result<()> {
-        self.client.request(Delete::new(href).force()).await?;
+        within(self.timeout, self.client.request(Delete::new(href).force())).await?;
         Ok(())
     }
 
@@ -126,16 +284,14 @@ where
         href: &str,
         data: String,
         content_type: &str,
+        if_match: Option<&str>,
     ) -> Result<Option<String>> {
-        let response = self
-            .client
-            .request(PutResource::new(href).create(data, content_type))
-            .await?;
-        Ok(response.etag)
+        let put = put_resource(&self.client, href, data, content_type, if_match);
+        within(self.timeout, put).await
     }
 
     async fn find_collections(&self, uri: &Uri) -> Result<Vec<FoundCollection>> {
-        let response = self.client.request(FindCollections::new(uri)).await?;
+        let response = within(self.timeout, self.client.request(FindCollections::new(uri))).await?;
         Ok(response.collections)
     }
 
@@ -144,10 +300,11 @@ where
         href: &str,
         property: &libdav::PropertyName<'_, '_>,
     ) -> Result<Option<String>> {
-        let response = self
-            .client
-            .request(GetProperty::new(href, property))
-            .await?;
+        let response = within(
+            self.timeout,
+            self.client.request(GetProperty::new(href, property)),
+        )
+        .await?;
         Ok(response.value)
     }
 
@@ -188,8 +345,7 @@ impl DavClient {
         // Build HTTP client with DAV Basic auth
         // DAV endpoints use HTTP Basic Auth with email as username and app password as password
         let dav_username = config.get_dav_username()?;
-        let dav_password = config.dav_password.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("DAV password not set. Please set FASTMAIL_DAV_PASSWORD environment variable with your Fastmail app password. Generate one at: https://www.fastmail.com/settings/passwords"))?;
+        let dav_password = config.get_dav_password()?;
         let https_client = Client::builder(TokioExecutor::new()).build(https_connector);
         let https_client = AddAuthorization::basic(https_client, dav_username, dav_password);
 
@@ -197,7 +353,10 @@ impl DavClient {
         let uri = service_url.parse()?;
         let client = WebDavClient::new(uri, https_client);
 
-        let inner: Box<dyn DavClientInner> = Box::new(DavClientInnerImpl { client });
+        let inner: Box<dyn DavClientInner> = Box::new(DavClientInnerImpl {
+            client,
+            timeout: config.get_timeout(),
+        });
 
         Ok(Self {
             client: inner,
@@ -212,15 +371,15 @@ impl DavClient {
         self.client.clone_client()
     }
 
-    /// List resources at a given path
+    /// List resources at a given path.
+    /// Depth 0 returns the resource itself, 1 its members, 2 or more everything below it.
     pub async fn list(&self, path: &str, depth: u8) -> Result<Vec<DavResource>> {
         let href = self.build_href(path)?;
 
-        // Note: libdav's ListResources hardcodes Depth::One
-        // The depth parameter is kept for API compatibility but not fully utilized
-        let _depth = depth; // Silenced unused warning
-
-        let resources = self.client.list_resources(&href).await?;
+        let resources = self
+            .client
+            .list_resources(&href, depth_from_u8(depth))
+            .await?;
 
         Ok(resources.into_iter().map(DavResource::from).collect())
     }
@@ -229,7 +388,10 @@ impl DavClient {
     pub async fn get_properties(&self, path: &str) -> Result<DavResource> {
         let href = self.build_href(path)?;
 
-        let resources = self.client.list_resources(&href).await?;
+        let resources = self
+            .client
+            .list_resources(&href, libdav::Depth::Zero)
+            .await?;
 
         resources
             .into_iter()
@@ -239,11 +401,9 @@ impl DavClient {
     }
 
     /// Create a collection (MKCOL)
-    /// Note: libdav 0.10 doesn't have direct MKCOL support
-    pub async fn create_collection(&self, _path: &str) -> Result<()> {
-        Err(anyhow::anyhow!(
-            "Create collection not yet implemented - libdav 0.10 doesn't expose MKCOL"
-        ))
+    pub async fn create_collection(&self, path: &str) -> Result<()> {
+        let href = self.build_href(path)?;
+        self.client.create_collection(&href).await
     }
 
     /// Delete a resource
@@ -252,43 +412,59 @@ impl DavClient {
         self.client.delete_resource(&href).await
     }
 
-    /// Upload/put a resource
-    pub async fn put(&self, path: &str, content: &[u8], content_type: &str) -> Result<String> {
+    /// Upload/put a resource. With `if_match`, only replace it if its ETag is
+    /// still that value; without, only create it if it does not exist.
+    pub async fn put(
+        &self,
+        path: &str,
+        content: &[u8],
+        content_type: &str,
+        if_match: Option<&str>,
+    ) -> Result<String> {
         let href = self.build_href(path)?;
 
         // Convert bytes to string for libdav
         let data = String::from_utf8(content.to_vec())
             .map_err(|e| anyhow::anyhow!("Content is not valid UTF-8: {}", e))?;
 
-        let etag = self.client.put_resource(&href, data, content_type).await?;
+        let etag = self
+            .client
+            .put_resource(&href, data, content_type, if_match)
+            .await?;
 
         Ok(etag.unwrap_or_default())
     }
 
     /// Get resource content
-    /// Note: Returns empty vec since ListResources doesn't return actual content
     pub async fn get(&self, path: &str) -> Result<Vec<u8>> {
         let href = self.build_href(path)?;
 
-        let resources = self.client.list_resources(&href).await?;
-
-        let _resource = resources
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("Resource not found: {}", path))?;
+        if self.client.is_collection(&href).await? {
+            return Err(anyhow::anyhow!(
+                "'{}' is a collection, not a file; use `files list` to see its contents",
+                path
+            ));
+        }
 
-        // ListResources doesn't return actual content, just metadata
-        Ok(vec![])
+        self.client.get_resource(&href).await
     }
 
-    /// Copy a resource (not directly supported by libdav)
-    pub async fn copy(&self, _from: &str, _to: &str, _overwrite: bool) -> Result<()> {
-        Err(anyhow::anyhow!("Copy operation not yet implemented"))
+    /// Copy a resource (COPY)
+    pub async fn copy(&self, from: &str, to: &str, overwrite: bool) -> Result<()> {
+        let href = self.build_href(from)?;
+        let destination = self.build_uri(to)?;
+        self.client
+            .transfer("COPY", &href, &destination.to_string(), overwrite)
+            .await
     }
 
-    /// Move a resource (not directly supported by libdav)
-    pub async fn move_resource(&self, _from: &str, _to: &str, _overwrite: bool) -> Result<()> {
-        Err(anyhow::anyhow!("Move operation not yet implemented"))
+    /// Move a resource (MOVE)
+    pub async fn move_resource(&self, from: &str, to: &str, overwrite: bool) -> Result<()> {
+        let href = self.build_href(from)?;
+        let destination = self.build_uri(to)?;
+        self.client
+            .transfer("MOVE", &href, &destination.to_string(), overwrite)
+            .await
     }
 
     /// Find collections at a given path
@@ -309,21 +485,13 @@ impl DavClient {
 
     /// Build href as String
     fn build_href(&self, path: &str) -> Result<String> {
-        let path = path.trim_start_matches('/');
-
-        let full = if path.is_empty() {
-            self.base_url.clone()
-        } else {
-            format!("{}{}", self.base_url.trim_end_matches('/'), path)
-        };
-
-        Ok(full)
+        let base: Uri = self.base_url.parse()?;
+        Ok(join_path(base.path(), path))
     }
 
     /// Build href as Uri (for operations that require Uri)
     fn build_uri(&self, path: &str) -> Result<Uri> {
-        let href = self.build_href(path)?;
-        Ok(href.parse()?)
+        Ok(join_path(&self.base_url, path).parse()?)
     }
 }
 
@@ -346,6 +514,108 @@ impl DavService {
     }
 }
 
+/// A DAV request that did not finish, response body included, within the timeout
+#[derive(Debug)]
+pub enum DavTransportError {
+    TimedOut(Duration),
+}
+
+impl std::fmt::Display for DavTransportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TimedOut(timeout) => {
+                write!(f, "request timed out after {}s", timeout.as_secs_f32())
+            }
+        }
+    }
+}
+
+impl std::error::Error for DavTransportError {}
+
+/// A write with `If-Match` was refused (412 Precondition Failed) because the
+/// resource changed on the server after its ETag was read
+#[derive(Debug)]
+pub struct ResourceChanged {
+    pub href: String,
+}
+
+impl std::fmt::Display for ResourceChanged {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} changed on the server since its ETag was read; fetch it again and retry",
+            self.href
+        )
+    }
+}
+
+impl std::error::Error for ResourceChanged {}
+
+/// PUT a resource, replacing it only if it still has ETag `if_match`, or
+/// creating it only if it does not exist yet when there is no ETag.
+/// Returns the new ETag if the server sent one.
+pub(crate) async fn put_resource<C>(
+    client: &WebDavClient<C>,
+    href: &str,
+    data: String,
+    content_type: &str,
+    if_match: Option<&str>,
+) -> Result<Option<String>>
+where
+    C: tower_service::Service<
+            http::Request<String>,
+            Response = http::Response<hyper::body::Incoming>,
+        > + Send
+        + Sync
+        + 'static,
+    C::Error: Into<Box<dyn std::error::Error + Send + Sync>> + std::error::Error + Send + Sync,
+    C::Future: Send + 'static,
+{
+    let response = match if_match {
+        Some(etag) => {
+            client
+                .request(PutResource::new(href).update(data, content_type, etag))
+                .await
+        }
+        None => {
+            client
+                .request(PutResource::new(href).create(data, content_type))
+                .await
+        }
+    };
+    match response {
+        Ok(response) => Ok(response.etag),
+        Err(WebDavError::BadStatusCode(StatusCode::PRECONDITION_FAILED)) if if_match.is_some() => {
+            Err(ResourceChanged {
+                href: href.to_string(),
+            }
+            .into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Whether a DAV operation failed because a request timed out
+pub fn is_dav_timeout(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| matches!(cause.downcast_ref(), Some(DavTransportError::TimedOut(_))))
+}
+
+/// Await one libdav call within `timeout`. libdav reads the response body
+/// inside the call, so a server that stalls mid-body is cut off too.
+pub(crate) async fn within<T, E>(
+    timeout: Duration,
+    call: impl Future<Output = std::result::Result<T, E>>,
+) -> Result<T>
+where
+    E: Into<anyhow::Error>,
+{
+    match tokio::time::timeout(timeout, call).await {
+        Ok(result) => result.map_err(Into::into),
+        Err(_) => Err(DavTransportError::TimedOut(timeout).into()),
+    }
+}
+
 /// Convert u8 depth to libdav depth
 ///
 /// The orphan rule prevents implementing `From<u8>` for `libdav::Depth` directly.
@@ -358,6 +628,48 @@ pub fn depth_from_u8(value: u8) -> libdav::Depth {
     }
 }
 
+/// Append a user-supplied path to a base, with exactly one '/' between them
+fn join_path(base: &str, path: &str) -> String {
+    format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+/// Percent-decode an href and drop any trailing '/', for comparing hrefs
+/// that servers may encode differently (e.g. `@` vs `%40`)
+fn decode_href(href: &str) -> String {
+    let bytes = href.trim_end_matches('/').as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// The collection containing a resource, e.g. `/book/` for `/book/contact.vcf`
+pub(crate) fn parent_collection(href: &str) -> &str {
+    match href.trim_end_matches('/').rfind('/') {
+        Some(idx) => &href[..=idx],
+        None => href,
+    }
+}
+
 /// Wrapper type to enable From<u8> conversion for Depth
 ///
 /// Due to Rust's orphan rule, we cannot implement `From<u8> for libdav::Depth`.
@@ -428,4 +740,313 @@ mod tests {
             "https://dav.fastmail.com/files/testuser/"
         );
     }
+
+    #[tokio::test]
+    async fn test_slow_server_times_out() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::any())
+            .respond_with(ResponseTemplate::new(207).set_delay(Duration::from_secs(5)))
+            .mount(&server)
+            .await;
+
+        let config = Config {
+            timeout: Some(0),
+            ..Config::for_mock_server(&server.uri())
+        };
+        let client = DavClient::from_config(&config, DavService::Files)
+            .await
+            .unwrap();
+        let err = client.list("", 1).await.unwrap_err();
+        assert!(is_dav_timeout(&err), "{:#}", err);
+        assert!(!is_dav_timeout(&anyhow::anyhow!("403 Forbidden")));
+    }
+
+    #[tokio::test]
+    async fn test_stalled_response_body_times_out() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Send the headers and part of the body, then go quiet
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 4096];
+            let _ = socket.read(&mut request).await;
+            let _ = socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\npartial")
+                .await;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        let config = Config {
+            timeout: Some(1),
+            ..Config::for_mock_server(&format!("http://{}", address))
+        };
+        let client = DavClient::from_config(&config, DavService::Files)
+            .await
+            .unwrap();
+        let err = client.get("notes.txt").await.unwrap_err();
+        assert!(is_dav_timeout(&err), "{:#}", err);
+    }
+
+    #[test]
+    fn test_decode_href() {
+        assert_eq!(
+            decode_href("/files/me%40example.com/My%20Docs/"),
+            "/files/me@example.com/My Docs"
+        );
+        assert_eq!(decode_href("/100%/"), "/100%");
+    }
+
+    #[test]
+    fn test_parent_collection() {
+        assert_eq!(
+            parent_collection("/dav/addressbooks/user/me/Default/abc.vcf"),
+            "/dav/addressbooks/user/me/Default/"
+        );
+        assert_eq!(parent_collection("/book/sub/"), "/book/");
+    }
+
+    #[test]
+    fn test_join_path() {
+        assert_eq!(
+            join_path("/files/me/", "/docs/a.txt"),
+            "/files/me/docs/a.txt"
+        );
+        assert_eq!(join_path("/files/me", "docs/"), "/files/me/docs/");
+        assert_eq!(join_path("/files/me/", ""), "/files/me/");
+    }
+
+    #[tokio::test]
+    async fn test_get_downloads_files_and_rejects_collections() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        fn propfind(href: &str, resource_type: &str) -> String {
+            format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response>
+    <d:href>{}</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype>{}</d:resourcetype></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#,
+                href, resource_type
+            )
+        }
+
+        let server = MockServer::start().await;
+        let file = "/files/me@example.com/notes.bin";
+        let dir = "/files/me@example.com/docs/";
+        Mock::given(method("PROPFIND"))
+            .and(path(file))
+            .respond_with(ResponseTemplate::new(207).set_body_string(propfind(file, "")))
+            .mount(&server)
+            .await;
+        Mock::given(method("PROPFIND"))
+            .and(path(dir))
+            .respond_with(
+                ResponseTemplate::new(207).set_body_string(propfind(dir, "<d:collection/>")),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(file))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0u8, 159, 146, 150]))
+            .mount(&server)
+            .await;
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = DavClient::from_config(&config, DavService::Files)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            client.get("notes.bin").await.unwrap(),
+            vec![0u8, 159, 146, 150]
+        );
+        let err = client.get("docs/").await.unwrap_err();
+        assert!(err.to_string().contains("is a collection"));
+    }
+
+    #[tokio::test]
+    async fn test_create_collection_maps_status() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        for (dir, status) in [("new", 201), ("existing", 405), ("orphan/child", 409)] {
+            Mock::given(method("MKCOL"))
+                .and(path(format!("/files/me@example.com/{}/", dir)))
+                .respond_with(ResponseTemplate::new(status))
+                .mount(&server)
+                .await;
+        }
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = DavClient::from_config(&config, DavService::Files)
+            .await
+            .unwrap();
+
+        client.create_collection("new/").await.unwrap();
+        let err = client.create_collection("existing/").await.unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        let err = client.create_collection("orphan/child/").await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Parent collection does not exist: /files/me@example.com/orphan/"));
+    }
+
+    #[tokio::test]
+    async fn test_put_with_if_match_reports_changed_resources() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/files/me@example.com/new.txt"))
+            .and(header("If-None-Match", "*"))
+            .respond_with(ResponseTemplate::new(201).insert_header("ETag", "\"v1\""))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/files/me@example.com/notes.txt"))
+            .and(header("If-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(204).insert_header("ETag", "\"v2\""))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/files/me@example.com/notes.txt"))
+            .and(header("If-Match", "\"v0\""))
+            .respond_with(ResponseTemplate::new(412))
+            .mount(&server)
+            .await;
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = DavClient::from_config(&config, DavService::Files)
+            .await
+            .unwrap();
+
+        let etag = client.put("new.txt", b"hi", "text/plain", None).await;
+        assert_eq!(etag.unwrap(), "\"v1\"");
+        let etag = client
+            .put("notes.txt", b"hi", "text/plain", Some("\"v1\""))
+            .await;
+        assert_eq!(etag.unwrap(), "\"v2\"");
+        let err = client
+            .put("notes.txt", b"hi", "text/plain", Some("\"v0\""))
+            .await
+            .unwrap_err();
+        let changed = err.downcast_ref::<ResourceChanged>().unwrap();
+        assert_eq!(changed.href, "/files/me@example.com/notes.txt");
+    }
+
+    #[tokio::test]
+    async fn test_copy_and_move_send_destination_and_overwrite() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let destination = format!("{}/files/me@example.com/b.txt", server.uri());
+        Mock::given(method("COPY"))
+            .and(path("/files/me@example.com/a.txt"))
+            .and(header("Destination", destination.as_str()))
+            .and(header("Overwrite", "F"))
+            .respond_with(ResponseTemplate::new(412))
+            .mount(&server)
+            .await;
+        Mock::given(method("MOVE"))
+            .and(path("/files/me@example.com/a.txt"))
+            .and(header("Destination", destination.as_str()))
+            .and(header("Overwrite", "T"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+        Mock::given(method("COPY"))
+            .and(path("/files/me@example.com/docs/"))
+            .respond_with(ResponseTemplate::new(207).set_body_string(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response>
+    <d:href>/files/me@example.com/backup/locked.txt</d:href>
+    <d:status>HTTP/1.1 423 Locked</d:status>
+  </d:response>
+</d:multistatus>"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = DavClient::from_config(&config, DavService::Files)
+            .await
+            .unwrap();
+
+        let err = client.copy("a.txt", "b.txt", false).await.unwrap_err();
+        assert!(err.to_string().contains("Destination already exists"));
+        client.move_resource("a.txt", "b.txt", true).await.unwrap();
+        let err = client.copy("docs/", "backup/", true).await.unwrap_err();
+        assert!(err.to_string().contains("partially failed"));
+    }
+
+    #[tokio::test]
+    async fn test_list_honours_depth() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        fn response(href: &str, resource_type: &str) -> String {
+            format!(
+                r#"<d:response>
+    <d:href>{}</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype>{}</d:resourcetype></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>"#,
+                href, resource_type
+            )
+        }
+        let multistatus = |responses: &[String]| {
+            format!(
+                r#"<?xml version="1.0" encoding="utf-8"?><d:multistatus xmlns:d="DAV:">{}</d:multistatus>"#,
+                responses.concat()
+            )
+        };
+
+        let server = MockServer::start().await;
+        let root = response("/files/me%40example.com/docs/", "<d:collection/>");
+        let child = response("/files/me%40example.com/docs/a.txt", "");
+        let nested = response("/files/me%40example.com/docs/sub/b.txt", "");
+        for (depth, body) in [
+            ("0", multistatus(std::slice::from_ref(&root))),
+            ("1", multistatus(&[root.clone(), child.clone()])),
+            ("infinity", multistatus(&[root, child, nested])),
+        ] {
+            Mock::given(method("PROPFIND"))
+                .and(path("/files/me@example.com/docs/"))
+                .and(header("Depth", depth))
+                .respond_with(ResponseTemplate::new(207).set_body_string(body))
+                .mount(&server)
+                .await;
+        }
+
+        let config = Config::for_mock_server(&server.uri());
+        let client = DavClient::from_config(&config, DavService::Files)
+            .await
+            .unwrap();
+
+        let itself = client.list("docs/", 0).await.unwrap();
+        assert_eq!(itself.len(), 1);
+        assert!(itself[0].is_collection);
+
+        let members = client.list("docs/", 1).await.unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].href, "/files/me%40example.com/docs/a.txt");
+
+        assert_eq!(client.list("docs/", u8::MAX).await.unwrap().len(), 2);
+    }
 }
@@ -0,0 +1,285 @@
+// fastmail-client/src/html_text.rs
+//! Plain-text rendering of HTML-only email bodies for the terminal
+
+use jmap_client::Email;
+
+/// Tags whose content is never shown
+const HIDDEN_TAGS: &[&str] = &["head", "script", "style", "title"];
+/// Tags that start on a new line
+const LINE_TAGS: &[&str] = &["br", "div", "tr", "table", "ul", "ol", "section", "article"];
+/// Tags set off by a blank line
+const PARAGRAPH_TAGS: &[&str] = &["p", "h1", "h2", "h3", "h4", "h5", "h6", "blockquote", "hr"];
+
+/// Replace the values of text/html parts in `textBody` with plain text.
+/// JMAP only puts HTML in textBody when there is no text/plain alternative.
+pub fn render_html_bodies(email: &mut Email) {
+    let Some(values) = email.body_values.as_mut() else {
+        return;
+    };
+    for part in email.text_body.iter().flatten() {
+        if !part.type_.eq_ignore_ascii_case("text/html") {
+            continue;
+        }
+        if let Some(value) = values
+            .get_mut(&part.part_id)
+            .and_then(|v| v.get_mut("value"))
+        {
+            if let Some(html) = value.as_str() {
+                *value = html_to_text(html).into();
+            }
+        }
+    }
+}
+
+/// Strip tags and decode entities, keeping paragraph and line breaks.
+/// Links are shown as "text (url)".
+pub fn html_to_text(html: &str) -> String {
+    let mut out = String::new();
+    let mut hidden: Option<String> = None;
+    let mut link: Option<(String, usize)> = None;
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let is_tag = rest.starts_with('<')
+            && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!');
+        if let Some(end) = is_tag.then(|| rest.find('>')).flatten() {
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            let closing = tag.starts_with('/');
+            let name = tag
+                .trim_start_matches('/')
+                .split(|c: char| c.is_whitespace() || c == '/')
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+
+            if let Some(open) = &hidden {
+                if closing && *open == name {
+                    hidden = None;
+                }
+                continue;
+            }
+            if HIDDEN_TAGS.contains(&name.as_str()) && !closing {
+                hidden = Some(name);
+                continue;
+            }
+
+            match name.as_str() {
+                "a" if !closing => link = attribute(tag, "href").map(|href| (href, out.len())),
+                "a" => {
+                    if let Some((href, start)) = link.take() {
+                        let text = out.get(start..).unwrap_or_default().trim();
+                        if !href.starts_with('#') && text != href && !text.is_empty() {
+                            out.push_str(&format!(" ({})", href));
+                        } else if text.is_empty() {
+                            out.push_str(&href);
+                        }
+                    }
+                }
+                "li" if !closing => {
+                    newline(&mut out);
+                    out.push_str("- ");
+                }
+                name if PARAGRAPH_TAGS.contains(&name) => {
+                    newline(&mut out);
+                    if !out.is_empty() {
+                        out.push('\n');
+                    }
+                }
+                name if LINE_TAGS.contains(&name) || name == "li" => {
+                    if name == "br" {
+                        out.push('\n');
+                    } else {
+                        newline(&mut out);
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        // A '<' that does not start a tag is just text
+        let end = rest
+            .char_indices()
+            .skip(1)
+            .find(|&(_, c)| c == '<')
+            .map_or(rest.len(), |(i, _)| i);
+        if hidden.is_none() {
+            push_text(&mut out, &decode_entities(&rest[..end]));
+        }
+        rest = &rest[end..];
+    }
+
+    tidy(&out)
+}
+
+/// Append text with runs of HTML whitespace collapsed to one space
+fn push_text(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if c.is_whitespace() && c != '\u{a0}' {
+            if !out.is_empty() && !out.ends_with([' ', '\n']) {
+                out.push(' ');
+            }
+        } else {
+            out.push(if c == '\u{a0}' { ' ' } else { c });
+        }
+    }
+}
+
+/// End the current line, unless already at the start of one
+fn newline(out: &mut String) {
+    let trimmed = out.trim_end_matches(' ').len();
+    out.truncate(trimmed);
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Trim every line and allow at most one blank line in a row
+fn tidy(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// Value of `name` in a tag's attributes, quoted or not
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find(name) {
+        let start = from + pos;
+        from = start + name.len();
+        let preceded = lower[..start].ends_with(|c: char| c.is_whitespace());
+        let Some(value) = lower[from..].trim_start().strip_prefix('=') else {
+            continue;
+        };
+        if !preceded {
+            continue;
+        }
+        let offset = tag.len() - value.trim_start().len();
+        let value = &tag[offset..];
+        let raw = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+            _ => value.split_whitespace().next().unwrap_or_default(),
+        };
+        return Some(decode_entities(raw));
+    }
+    None
+}
+
+/// Decode named, decimal and hex character references
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| {
+                let entity = &rest[1..end + 1];
+                entity_char(entity).map(|c| (c, end + 2))
+            });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn entity_char(entity: &str) -> Option<char> {
+    if let Some(number) = entity.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match entity {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "euro" => '€',
+        "pound" => '£',
+        "bull" => '•',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_text_keeps_structure() {
+        let html = "<html><head><title>News</title><style>p{}</style></head><body>\
+            <h1>Weekly&nbsp;update</h1>\n<p>Hello &amp; welcome,<br>friends.</p>\
+            <!-- tracking --><ul><li>One</li><li>Two</li></ul>\
+            <p>Read <a href=\"https://example.com/post?a=1&amp;b=2\">the post</a> \
+            or visit <a href='https://example.com'>https://example.com</a>.</p>\
+            <p>2 < 3 — ok</p></body></html>";
+        assert_eq!(
+            html_to_text(html),
+            "Weekly update\n\nHello & welcome,\nfriends.\n\n- One\n- Two\n\n\
+             Read the post (https://example.com/post?a=1&b=2) or visit https://example.com.\n\n\
+             2 < 3 — ok"
+        );
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("&lt;b&gt; &#39;x&#x27; &unknown; & more"),
+            "<b> 'x' &unknown; & more"
+        );
+    }
+
+    #[test]
+    fn test_render_html_bodies_only_touches_html_parts() {
+        let mut email: Email = serde_json::from_value(serde_json::json!({
+            "id": "m1",
+            "textBody": [{"partId": "1", "type": "text/html"}],
+            "bodyValues": {"1": {"value": "<p>Hi <b>there</b></p>"}, "2": {"value": "<p>x</p>"}}
+        }))
+        .unwrap();
+        render_html_bodies(&mut email);
+        let values = email.body_values.unwrap();
+        assert_eq!(values["1"]["value"], "Hi there");
+        assert_eq!(values["2"]["value"], "<p>x</p>");
+    }
+}
@@ -1,21 +1,43 @@
 pub mod caldav;
 pub mod carddav;
 pub mod client;
+pub mod compose;
 pub mod config;
+mod content_line;
 pub mod dav;
+pub mod html_text;
+pub mod mailbox_tree;
 pub mod masked_email;
+pub mod session_cache;
+pub mod sync_state;
 pub mod whitelist;
 
-pub use caldav::{CalDavClient, Calendar, CalendarEvent};
-pub use carddav::{AddressBook, CardDavClient, Contact};
-pub use client::FastmailClient;
-pub use config::{AccountConfig, Config, DavEndpoints};
-pub use dav::{depth_from_u8, DavClient, DavResource, DavService, DepthValue};
+pub use caldav::{
+    Alarm, Attendee, BusyPeriod, CalDavClient, Calendar, CalendarEvent, CalendarTask,
+    EventConflict, EventConflicts, EventOccurrence,
+};
+pub use carddav::{Address, AddressBook, CardDavClient, Contact, ContactMatch, ContactPhoto};
+pub use client::{AccountSummary, FastmailClient};
+pub use compose::{EmailComposeSpec, UploadedAttachment};
+pub use config::{AccountConfig, AccountProfile, Config, DavEndpoints};
+pub use dav::{
+    depth_from_u8, is_dav_timeout, DavClient, DavResource, DavService, DavTransportError,
+    DepthValue, ResourceChanged,
+};
+pub use mailbox_tree::MailboxNode;
 pub use masked_email::{MaskedEmail, MaskedEmailState};
+pub use session_cache::SessionCache;
+pub use sync_state::{EmailChanges, SyncState};
 pub use whitelist::Whitelist;
 
 // Re-export from jmap-client
-pub use jmap_client::{BlobCapability, Mailbox};
+pub use jmap_client::redact;
+pub use jmap_client::{
+    BlobCapability, BodyPart, Comparator, DestroyResult, Email, EmailAddress, EmailCreate,
+    EmailFilterCondition, EmailQueryPage, Filter, FilterOperator, HttpError, JmapError, Mailbox,
+    PushEvent, QueryChangesResponse, RateLimit, SearchSnippet, Thread, UpdateResult,
+    VacationResponse,
+};
 // Sharing types
 pub use jmap_client::{
     Entity, Principal, PrincipalFilterCondition, PrincipalType, PrincipalsAccountCapability,
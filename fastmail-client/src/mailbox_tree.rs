@@ -0,0 +1,120 @@
+// fastmail-client/src/mailbox_tree.rs
+//! Nesting of the flat JMAP mailbox list by `parentId`
+
+use jmap_client::Mailbox;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// A mailbox and the mailboxes nested under it
+#[derive(Debug, Clone, Serialize)]
+pub struct MailboxNode {
+    #[serde(flatten)]
+    pub mailbox: Mailbox,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<MailboxNode>,
+}
+
+/// Nest mailboxes under their parents, ordered by sortOrder then name.
+/// Mailboxes whose parent was not returned (or that sit in a parent cycle)
+/// are attached at the root so nothing is dropped.
+pub fn build_mailbox_tree(mailboxes: Vec<Mailbox>) -> Vec<MailboxNode> {
+    let ids: HashSet<String> = mailboxes.iter().map(|m| m.id.clone()).collect();
+
+    let mut roots = Vec::new();
+    let mut children: HashMap<String, Vec<Mailbox>> = HashMap::new();
+    for mailbox in mailboxes {
+        match mailbox.parent_id.clone() {
+            Some(parent) if ids.contains(&parent) => {
+                children.entry(parent).or_default().push(mailbox)
+            }
+            _ => roots.push(mailbox),
+        }
+    }
+
+    let mut tree = attach_children(roots, &mut children);
+
+    // Anything left over is only reachable through a cycle; break it at the root
+    while let Some(parent) = children.keys().next().cloned() {
+        let orphans = children.remove(&parent).unwrap_or_default();
+        tree.extend(attach_children(orphans, &mut children));
+    }
+
+    sort_nodes(&mut tree);
+    tree
+}
+
+fn attach_children(
+    mailboxes: Vec<Mailbox>,
+    children: &mut HashMap<String, Vec<Mailbox>>,
+) -> Vec<MailboxNode> {
+    let mut nodes: Vec<MailboxNode> = mailboxes
+        .into_iter()
+        .map(|mailbox| {
+            let nested = children.remove(&mailbox.id).unwrap_or_default();
+            MailboxNode {
+                children: attach_children(nested, children),
+                mailbox,
+            }
+        })
+        .collect();
+    sort_nodes(&mut nodes);
+    nodes
+}
+
+fn sort_nodes(nodes: &mut [MailboxNode]) {
+    nodes.sort_by(|a, b| {
+        (a.mailbox.sort_order, &a.mailbox.name).cmp(&(b.mailbox.sort_order, &b.mailbox.name))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mailbox(id: &str, name: &str, parent_id: Option<&str>) -> Mailbox {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": name,
+            "parentId": parent_id,
+        }))
+        .unwrap()
+    }
+
+    fn names(nodes: &[MailboxNode]) -> Vec<&str> {
+        nodes.iter().map(|n| n.mailbox.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_build_mailbox_tree_nests_children() {
+        let tree = build_mailbox_tree(vec![
+            mailbox("3", "Receipts", Some("2")),
+            mailbox("1", "Inbox", None),
+            mailbox("2", "Archive", None),
+            mailbox("4", "2024", Some("3")),
+        ]);
+
+        assert_eq!(names(&tree), vec!["Archive", "Inbox"]);
+        assert_eq!(names(&tree[0].children), vec!["Receipts"]);
+        assert_eq!(names(&tree[0].children[0].children), vec!["2024"]);
+        assert!(tree[1].children.is_empty());
+    }
+
+    #[test]
+    fn test_build_mailbox_tree_keeps_orphans_and_cycles() {
+        let tree = build_mailbox_tree(vec![
+            mailbox("1", "Inbox", None),
+            mailbox("2", "Shared child", Some("missing")),
+            mailbox("3", "Loop A", Some("4")),
+            mailbox("4", "Loop B", Some("3")),
+        ]);
+
+        let mut count = 0;
+        let mut stack: Vec<&MailboxNode> = tree.iter().collect();
+        while let Some(node) = stack.pop() {
+            count += 1;
+            stack.extend(&node.children);
+        }
+        assert_eq!(count, 4);
+        assert!(names(&tree).contains(&"Shared child"));
+    }
+}
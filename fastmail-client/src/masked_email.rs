@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 /// Masked Email (Fastmail extension)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,3 +28,14 @@ pub enum MaskedEmailState {
     Disabled,
     Deleted,
 }
+
+impl fmt::Display for MaskedEmailState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MaskedEmailState::Pending => "pending",
+            MaskedEmailState::Enabled => "enabled",
+            MaskedEmailState::Disabled => "disabled",
+            MaskedEmailState::Deleted => "deleted",
+        })
+    }
+}
@@ -0,0 +1,116 @@
+// fastmail-client/src/session_cache.rs
+use crate::config::Config;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use jmap_client::Session;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+/// How long a cached session is reused before it is fetched again
+const SESSION_TTL_MINUTES: i64 = 15;
+
+/// JMAP session stored on disk so repeated CLI invocations skip the session request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionCache {
+    /// Fingerprint of the token the session was fetched with
+    token_id: u64,
+    /// Session URL it was fetched from
+    #[serde(default)]
+    session_url: String,
+    fetched_at: DateTime<Utc>,
+    session: Session,
+}
+
+impl SessionCache {
+    fn new(token: &str, session_url: &str, session: Session) -> Self {
+        Self {
+            token_id: token_fingerprint(token),
+            session_url: session_url.to_string(),
+            fetched_at: Utc::now(),
+            session,
+        }
+    }
+
+    /// Return the cached session if it was fetched for `token` from
+    /// `session_url` within the TTL
+    pub fn load(token: &str, session_url: &str) -> Option<Session> {
+        let content = fs::read_to_string(Self::path().ok()?).ok()?;
+        let cache: Self = serde_json::from_str(&content).ok()?;
+        (cache.session_url == session_url && cache.is_fresh(token, Utc::now()))
+            .then_some(cache.session)
+    }
+
+    /// Write the session to the cache file (owner read/write only)
+    pub fn store(token: &str, session_url: &str, session: &Session) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let content = serde_json::to_string(&Self::new(token, session_url, session.clone()))?;
+        fs::write(&path, content)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(&path)?.permissions();
+            perms.set_mode(0o600);
+            fs::set_permissions(&path, perms)?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove the cached session, e.g. after the server rejected the token
+    pub fn clear() -> Result<()> {
+        let path = Self::path()?;
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn is_fresh(&self, token: &str, now: DateTime<Utc>) -> bool {
+        self.token_id == token_fingerprint(token)
+            && now - self.fetched_at < Duration::minutes(SESSION_TTL_MINUTES)
+    }
+
+    fn path() -> Result<PathBuf> {
+        Ok(Config::config_dir()?.join("session.json"))
+    }
+}
+
+/// Identify a token without writing it to disk
+fn token_fingerprint(token: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    token.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_fresh() {
+        let cache = SessionCache::new("token-a", "https://jmap.example", Session::default());
+        let now = cache.fetched_at;
+
+        assert!(cache.is_fresh("token-a", now + Duration::minutes(5)));
+        assert!(!cache.is_fresh("token-b", now + Duration::minutes(5)));
+        assert!(!cache.is_fresh("token-a", now + Duration::minutes(SESSION_TTL_MINUTES)));
+    }
+
+    #[test]
+    fn test_cache_does_not_contain_token() {
+        let cache = SessionCache::new("fmu1-secret", "https://jmap.example", Session::default());
+        let json = serde_json::to_string(&cache).unwrap();
+
+        assert!(!json.contains("fmu1-secret"));
+        let parsed: SessionCache = serde_json::from_str(&json).unwrap();
+        assert!(parsed.is_fresh("fmu1-secret", Utc::now()));
+    }
+}
@@ -0,0 +1,141 @@
+// fastmail-client/src/sync_state.rs
+use crate::config::Config;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Last seen JMAP Email state per account, kept between `mail sync` runs,
+/// and the query state of each `mail list` query per account
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    #[serde(default)]
+    email_states: HashMap<String, String>,
+    #[serde(default)]
+    query_states: HashMap<String, HashMap<String, String>>,
+}
+
+impl SyncState {
+    /// Load the saved states; a missing file means nothing has been synced yet
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn email_state(&self, account_id: &str) -> Option<&str> {
+        self.email_states.get(account_id).map(String::as_str)
+    }
+
+    pub fn set_email_state(&mut self, account_id: &str, state: String) {
+        self.email_states.insert(account_id.to_string(), state);
+    }
+
+    /// Forget the state so the next sync starts from a new baseline
+    pub fn clear_email_state(&mut self, account_id: &str) {
+        self.email_states.remove(account_id);
+    }
+
+    /// Query state last seen for `query`, a key describing its filter and sort
+    pub fn query_state(&self, account_id: &str, query: &str) -> Option<&str> {
+        self.query_states
+            .get(account_id)
+            .and_then(|queries| queries.get(query))
+            .map(String::as_str)
+    }
+
+    pub fn set_query_state(&mut self, account_id: &str, query: &str, state: String) {
+        self.query_states
+            .entry(account_id.to_string())
+            .or_default()
+            .insert(query.to_string(), state);
+    }
+
+    fn path() -> Result<PathBuf> {
+        Ok(Config::config_dir()?.join("sync-state.json"))
+    }
+}
+
+/// Email ids that changed between two states
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EmailChanges {
+    pub old_state: String,
+    pub new_state: String,
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub destroyed: Vec<String>,
+}
+
+impl EmailChanges {
+    /// Fold in the next page of changes so each id is reported once:
+    /// created-then-destroyed ids disappear and created ids are not also "updated"
+    pub fn merge(&mut self, created: Vec<String>, updated: Vec<String>, destroyed: Vec<String>) {
+        for id in created {
+            if !self.created.contains(&id) {
+                self.created.push(id);
+            }
+        }
+        for id in updated {
+            if !self.created.contains(&id) && !self.updated.contains(&id) {
+                self.updated.push(id);
+            }
+        }
+        for id in destroyed {
+            if let Some(pos) = self.created.iter().position(|c| *c == id) {
+                self.created.remove(pos);
+                continue;
+            }
+            self.updated.retain(|u| *u != id);
+            if !self.destroyed.contains(&id) {
+                self.destroyed.push(id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_merge_changes_across_pages() {
+        let mut changes = EmailChanges::default();
+        changes.merge(ids(&["a", "b"]), ids(&["x"]), ids(&[]));
+        changes.merge(ids(&["c"]), ids(&["a", "x", "y"]), ids(&["b", "y", "z"]));
+
+        assert_eq!(changes.created, ids(&["a", "c"]));
+        assert_eq!(changes.updated, ids(&["x"]));
+        assert_eq!(changes.destroyed, ids(&["y", "z"]));
+    }
+
+    #[test]
+    fn test_sync_state_round_trip() {
+        let mut state = SyncState::default();
+        state.set_email_state("acc1", "s1".to_string());
+        let json = serde_json::to_string(&state).unwrap();
+        let loaded: SyncState = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.email_state("acc1"), Some("s1"));
+        assert_eq!(loaded.email_state("acc2"), None);
+
+        // Files written before query states existed still load
+        let old: SyncState = serde_json::from_str(r#"{"email_states":{"acc1":"s1"}}"#).unwrap();
+        assert_eq!(old.query_state("acc1", "inbox"), None);
+    }
+}
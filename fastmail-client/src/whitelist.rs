@@ -3,11 +3,14 @@ use anyhow::{anyhow, Result};
 use directories::BaseDirs;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Deserialize, Serialize, Default)]
 pub struct Whitelist {
     pub allowed_recipients: Vec<String>,
+    /// File the list was loaded from and is saved to
+    #[serde(skip)]
+    path: PathBuf,
 }
 
 impl Whitelist {
@@ -16,18 +19,23 @@ impl Whitelist {
             BaseDirs::new().ok_or_else(|| anyhow!("Cannot determine config directory"))?;
 
         let config_dir = base_dirs.config_dir().join("fastmail-cli");
-        let whitelist_path = config_dir.join("allowed-recipients.json");
+        Self::load_from(&config_dir.join("allowed-recipients.json"))
+    }
 
-        if !whitelist_path.exists() {
-            fs::create_dir_all(&config_dir)?;
-            let default = Self::default();
-            fs::write(&whitelist_path, serde_json::to_vec_pretty(&default)?)?;
-            set_owner_only_permissions(&whitelist_path)?;
+    /// Load the list from `path`, creating an empty one if it does not exist
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            let default = Self {
+                path: path.to_path_buf(),
+                ..Default::default()
+            };
+            default.save()?;
             return Ok(default);
         }
 
-        let content = fs::read_to_string(&whitelist_path)?;
-        let whitelist: Whitelist = serde_json::from_str(&content)?;
+        let content = fs::read_to_string(path)?;
+        let mut whitelist: Whitelist = serde_json::from_str(&content)?;
+        whitelist.path = path.to_path_buf();
         Ok(whitelist)
     }
 
@@ -35,6 +43,15 @@ impl Whitelist {
         self.allowed_recipients.iter().any(|r| r == email)
     }
 
+    /// The recipients that are not on the list
+    pub fn blocked(&self, recipients: &[&str]) -> Vec<String> {
+        recipients
+            .iter()
+            .filter(|r| !self.is_allowed(r))
+            .map(|r| r.to_string())
+            .collect()
+    }
+
     pub fn add(&mut self, email: String) -> Result<()> {
         if self.is_allowed(&email) {
             return Ok(());
@@ -53,16 +70,14 @@ impl Whitelist {
     }
 
     fn save(&self) -> Result<()> {
-        let base_dirs =
-            BaseDirs::new().ok_or_else(|| anyhow!("Cannot determine config directory"))?;
-
-        let config_dir = base_dirs.config_dir().join("fastmail-cli");
-        let whitelist_path = config_dir.join("allowed-recipients.json");
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
 
         let content = serde_json::to_string_pretty(self)?;
-        fs::write(&whitelist_path, content)?;
+        fs::write(&self.path, content)?;
 
-        set_owner_only_permissions(&whitelist_path)?;
+        set_owner_only_permissions(&self.path)?;
 
         Ok(())
     }
@@ -1,15 +1,20 @@
 // jmap-client/src/client.rs
 use crate::blob;
-use crate::error::JmapError;
-use crate::http::HttpClient;
+use crate::error::{BlobError, JmapError};
+use crate::http::{HttpClient, RateLimit};
+use crate::mime;
 use crate::types::{
     BlobCopyResponse, BlobGetResponse, BlobLookupInfo, BlobUploadObject, BlobUploadResponse,
-    ChangesResponse, Email, EmailCreate, EmailImport, EmailSubmission, Envelope, Identity, Mailbox,
-    Principal, PrincipalFilterCondition, PushSubscription, QueryChangesResponse, SearchSnippet,
-    Session, ShareNotification, ShareNotificationFilterCondition, Thread, VacationResponse,
+    BodyPart, ChangesResponse, Comparator, DestroyResult, Email, EmailCreate, EmailFilterCondition,
+    EmailImport, EmailQueryPage, EmailSubmission, Envelope, Filter, Identity, Mailbox, Principal,
+    PrincipalFilterCondition, PushSubscription, QueryChangesResponse, SearchSnippet, Session,
+    SetError, ShareNotification, ShareNotificationFilterCondition, Thread, UpdateResult,
+    VacationResponse,
 };
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
 use serde_json::json;
+use std::path::Path;
 
 const CORE_CAPABILITY: &str = "urn:ietf:params:jmap:core";
 const MAIL_CAPABILITY: &str = "urn:ietf:params:jmap:mail";
@@ -17,6 +22,10 @@ const BLOB_CAPABILITY: &str = "urn:ietf:params:jmap:blob";
 const PRINCIPALS_CAPABILITY: &str = "urn:ietf:params:jmap:principals";
 const SUBMISSION_CAPABILITY: &str = "urn:ietf:params:jmap:submission";
 const VACATION_CAPABILITY: &str = "urn:ietf:params:jmap:vacationresponse";
+/// Ids fetched per Email/query when paging through a whole mailbox
+const QUERY_PAGE_SIZE: usize = 1000;
+/// Batch size for /set calls when the server does not state maxObjectsInSet
+const DEFAULT_MAX_OBJECTS_IN_SET: usize = 500;
 
 #[derive(Debug, Clone)]
 pub struct Invocation {
@@ -25,6 +34,16 @@ pub struct Invocation {
     pub tag: String,
 }
 
+impl Invocation {
+    pub fn new(name: &str, args: serde_json::Value, tag: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            args,
+            tag: tag.to_string(),
+        }
+    }
+}
+
 pub struct JmapClient<C: HttpClient> {
     http: C,
     session: Session,
@@ -69,6 +88,29 @@ impl<C: HttpClient> JmapClient<C> {
         Ok(session)
     }
 
+    /// Create a client from a previously fetched session, selecting its primary account
+    pub fn from_session(http: C, session: Session) -> Result<Self> {
+        let account_id = Self::select_account_id(&session)?;
+        Ok(Self {
+            http,
+            session,
+            account_id,
+        })
+    }
+
+    /// Create a client from a previously fetched session, acting on `account_id`
+    /// (e.g. a shared account) rather than the primary one
+    pub fn from_session_with_account(http: C, session: Session, account_id: &str) -> Result<Self> {
+        if !session.accounts.contains_key(account_id) {
+            return Err(JmapError::AccountNotFound(account_id.to_string()).into());
+        }
+        Ok(Self {
+            http,
+            session,
+            account_id: account_id.to_string(),
+        })
+    }
+
     /// Select the primary account ID from a session (generic JMAP logic)
     pub fn select_account_id(session: &Session) -> Result<String> {
         if session.accounts.is_empty() {
@@ -99,19 +141,37 @@ impl<C: HttpClient> JmapClient<C> {
 
     /// Get the account email from session (tries username first, then account name)
     pub fn account_email(&self) -> Option<&str> {
-        // Try username first
-        self.session
+        Self::session_account_email(&self.session, &self.account_id)
+    }
+
+    /// Find the account email in a session: the username, then the name of the
+    /// primary mail account, then the name of `account_id`. Only values that look
+    /// like an email address are returned.
+    pub fn session_account_email<'a>(session: &'a Session, account_id: &str) -> Option<&'a str> {
+        let account_name = |id: &str| session.accounts.get(id).and_then(|a| a.name.as_deref());
+
+        session
             .username
             .as_deref()
-            .filter(|s| s.contains('@'))
-            .or_else(|| {
-                // Then try account name
-                self.session
-                    .accounts
-                    .get(&self.account_id)
-                    .and_then(|a| a.name.as_deref())
-                    .filter(|s| s.contains('@'))
-            })
+            .into_iter()
+            .chain(
+                session
+                    .primary_accounts
+                    .get(MAIL_CAPABILITY)
+                    .and_then(|id| account_name(id)),
+            )
+            .chain(account_name(account_id))
+            .find(|s| s.contains('@'))
+    }
+
+    /// Rate limit reported on the most recent response, if the HTTP client tracks it
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.http.rate_limit()
+    }
+
+    #[cfg(feature = "reqwest")]
+    pub(crate) fn http(&self) -> &C {
+        &self.http
     }
 
     /// Get a reference to the session
@@ -175,16 +235,7 @@ impl<C: HttpClient> JmapClient<C> {
         name: &str,
         type_: &str,
     ) -> Result<String> {
-        let template = self
-            .session
-            .download_url
-            .as_ref()
-            .ok_or_else(|| anyhow!("Server does not support downloadUrl"))?;
-        let url = template
-            .replace("{accountId}", &self.account_id)
-            .replace("{blobId}", blob_id)
-            .replace("{name}", name)
-            .replace("{type}", type_);
+        let url = self.blob_download_url(blob_id, name, type_)?;
         let bytes = self.http_get(&url).await?;
         Ok(String::from_utf8_lossy(&bytes).to_string())
     }
@@ -196,41 +247,89 @@ impl<C: HttpClient> JmapClient<C> {
         name: &str,
         type_: &str,
     ) -> Result<Vec<u8>> {
+        let url = self.blob_download_url(blob_id, name, type_)?;
+        self.http_get(&url).await
+    }
+
+    /// Fill in the session's downloadUrl template (RFC 8620 §6.2). The values
+    /// are percent-encoded, as the template is an RFC 6570 level 1 template.
+    fn blob_download_url(&self, blob_id: &str, name: &str, type_: &str) -> Result<String> {
         let template = self
             .session
             .download_url
             .as_ref()
             .ok_or_else(|| anyhow!("Server does not support downloadUrl"))?;
-        let url = template
-            .replace("{accountId}", &self.account_id)
-            .replace("{blobId}", blob_id)
-            .replace("{name}", name)
-            .replace("{type}", type_);
-        self.http_get(&url).await
+        Ok(template
+            .replace("{accountId}", &uri_template_value(&self.account_id))
+            .replace("{blobId}", &uri_template_value(blob_id))
+            .replace("{name}", &uri_template_value(name))
+            .replace("{type}", &uri_template_value(type_)))
     }
 
     /// Upload blob content using RFC 8620 uploadUrl template
     /// Returns the blobId from the server response
     pub async fn upload_blob_content(&self, data: &[u8], content_type: &str) -> Result<String> {
-        let template = self
-            .session
-            .upload_url
-            .as_ref()
-            .ok_or_else(|| anyhow!("Server does not support uploadUrl"))?;
-        let url = template.replace("{accountId}", &self.account_id);
+        check_blob_size(data.len() as u64, self.max_size_upload())?;
+        let url = self.upload_endpoint()?;
 
         // RFC 8620 says: POST with the file data as the body
         let resp_bytes = self.http_post(&url, data.to_vec(), content_type).await?;
+        upload_response_blob_id(&resp_bytes)
+    }
 
-        // Parse response to get blobId
-        // Format: { "blobId": "xxx", "size": yyy }
-        let resp: serde_json::Value = serde_json::from_slice(&resp_bytes)?;
-        let blob_id = resp
-            .get("blobId")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("No blobId in upload response"))?;
+    /// Upload a file through the uploadUrl, streaming it when the HTTP client can.
+    /// Files over the server's maxSizeUpload are rejected before anything is sent.
+    pub async fn upload_blob_file(&self, path: &Path, content_type: &str) -> Result<String> {
+        let size = std::fs::metadata(path)
+            .map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?
+            .len();
+        check_blob_size(size, self.max_size_upload())?;
+
+        let url = self.upload_endpoint()?;
+        let resp_bytes = self
+            .http
+            .post_file(&url, path, content_type)
+            .await
+            .map_err(|e| anyhow!("HTTP error: {}", e.message))?;
+        upload_response_blob_id(&resp_bytes)
+    }
+
+    /// Largest single upload the server accepts (RFC 8620 §2 maxSizeUpload)
+    pub fn max_size_upload(&self) -> Option<u64> {
+        self.session
+            .capabilities
+            .get(CORE_CAPABILITY)
+            .and_then(|core| core.get("maxSizeUpload"))
+            .and_then(|v| v.as_u64())
+    }
+
+    /// Most objects one /set call may create, update and destroy (RFC 8620 §2 maxObjectsInSet)
+    pub fn max_objects_in_set(&self) -> Option<u64> {
+        self.session
+            .capabilities
+            .get(CORE_CAPABILITY)
+            .and_then(|core| core.get("maxObjectsInSet"))
+            .and_then(|v| v.as_u64())
+    }
+
+    /// Largest blob Blob/upload accepts (RFC 9404 §4 maxSizeBlobSet)
+    pub fn max_size_blob_set(&self) -> Option<u64> {
+        self.session
+            .accounts
+            .get(&self.account_id)
+            .and_then(|acc| acc.account_capabilities.as_ref())
+            .and_then(|caps| caps.get(BLOB_CAPABILITY))
+            .and_then(|blob| blob.get("maxSizeBlobSet"))
+            .and_then(|v| v.as_u64())
+    }
 
-        Ok(blob_id.to_string())
+    fn upload_endpoint(&self) -> Result<String> {
+        let template = self
+            .session
+            .upload_url
+            .as_ref()
+            .ok_or_else(|| anyhow!("Server does not support uploadUrl"))?;
+        Ok(template.replace("{accountId}", &self.account_id))
     }
 
     pub async fn call_method(
@@ -248,9 +347,31 @@ impl<C: HttpClient> JmapClient<C> {
         method: &str,
         params: serde_json::Value,
     ) -> Result<serde_json::Value> {
+        let responses = self
+            .call_methods(using, vec![Invocation::new(method, params, "0")])
+            .await?;
+        let first = responses
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Empty JMAP response"))?;
+
+        method_response_args(first, method)
+    }
+
+    /// Send several method calls in one request (RFC 8620 §3.3)
+    /// Responses are returned in server order; match them to calls by tag.
+    /// Per-call "error" responses are returned as-is for the caller to inspect.
+    pub async fn call_methods(
+        &self,
+        using: &[&str],
+        invocations: Vec<Invocation>,
+    ) -> Result<Vec<Invocation>> {
+        let method_calls: Vec<serde_json::Value> = invocations
+            .into_iter()
+            .map(|inv| json!([inv.name, inv.args, inv.tag]))
+            .collect();
         let body = json!({
             "using": using,
-            "methodCalls": [[method, params, "0"]],
+            "methodCalls": method_calls,
         });
 
         let body_bytes = serde_json::to_vec(&body)?;
@@ -258,94 +379,170 @@ impl<C: HttpClient> JmapClient<C> {
             .http
             .post_json(&self.session.api_url, body_bytes)
             .await
-            .map_err(|e| anyhow::anyhow!("HTTP error: {}", e.message))?;
+            .map_err(anyhow::Error::new)?;
 
         let resp: serde_json::Value = serde_json::from_slice(&resp_bytes)?;
 
-        let responses = parse_method_responses(&resp)?;
-        let first = responses
-            .first()
-            .ok_or_else(|| anyhow::anyhow!("Empty JMAP response"))?;
-
-        if first.name == "error" {
-            let jmap_err = JmapError::from_value(&first.args);
-            return Err(anyhow!(jmap_err));
-        }
+        parse_method_responses(&resp)
+    }
 
-        if first.name != method {
-            anyhow::bail!(
-                "Unexpected JMAP response method: expected {}, got {}",
-                method,
-                first.name
-            );
-        }
+    /// List emails matching an optional filter (RFC 8621 §4.4)
+    /// An empty filter condition is omitted so it behaves like an unfiltered query
+    pub async fn email_query(
+        &self,
+        filter: Option<Filter<EmailFilterCondition>>,
+        sort: Option<Vec<Comparator>>,
+        position: Option<usize>,
+        limit: usize,
+    ) -> Result<EmailQueryPage> {
+        let params = self.email_query_params(filter, sort, position, limit)?;
+        let args = self.call_method("Email/query", params).await?;
+        parse_email_query_page(args)
+    }
 
-        Ok(first.args.clone())
+    /// Number of emails matching a filter, from `total` alone: no ids are returned
+    pub async fn email_count(&self, filter: Option<Filter<EmailFilterCondition>>) -> Result<usize> {
+        Ok(self.email_query(filter, None, None, 0).await?.total)
     }
 
-    /// List emails with optional limit
-    pub async fn email_query(&self, limit: usize) -> Result<Vec<String>> {
-        let params = json!({
+    /// Query and fetch emails in one request using a `#ids` result reference (RFC 8620 §3.7).
+    /// `properties` limits what is fetched for each email, as in `email_get`.
+    pub async fn email_query_and_get(
+        &self,
+        filter: Option<Filter<EmailFilterCondition>>,
+        sort: Option<Vec<Comparator>>,
+        position: Option<usize>,
+        limit: usize,
+        properties: Option<Vec<String>>,
+    ) -> Result<(EmailQueryPage, Vec<Email>)> {
+        let query = self.email_query_params(filter, sort, position, limit)?;
+        let mut get = json!({
             "accountId": self.account_id,
-            "limit": limit,
-            "sort": [{"property": "receivedAt", "isAscending": false}]
+            "#ids": {
+                "resultOf": "0",
+                "name": "Email/query",
+                "path": "/ids"
+            }
         });
+        if let Some(properties) = properties {
+            get["properties"] = json!(properties);
+        }
 
-        let args = self.call_method("Email/query", params).await?;
+        let responses = self
+            .call_methods(
+                &[CORE_CAPABILITY, MAIL_CAPABILITY],
+                vec![
+                    Invocation::new("Email/query", query, "0"),
+                    Invocation::new("Email/get", get, "1"),
+                ],
+            )
+            .await?;
 
-        let ids_arr = args
-            .get("ids")
-            .and_then(|v| v.as_array())
-            .ok_or_else(|| anyhow::anyhow!("Invalid JMAP response: no ids"))?;
+        let find = |tag: &str| {
+            responses
+                .iter()
+                .find(|r| r.tag == tag)
+                .ok_or_else(|| anyhow!("Invalid JMAP response: missing response {}", tag))
+        };
+        let page = parse_email_query_page(method_response_args(find("0")?, "Email/query")?)?;
+        let args = method_response_args(find("1")?, "Email/get")?;
 
-        let ids: Vec<String> = ids_arr
+        let list = args
+            .get("list")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| anyhow::anyhow!("Invalid JMAP response: no list"))?;
+        let emails = list
             .iter()
-            .filter_map(|v| v.as_str())
-            .map(String::from)
-            .collect();
+            .map(|v| serde_json::from_value(v.clone()).map_err(Into::into))
+            .collect::<Result<Vec<Email>>>()?;
 
-        Ok(ids)
+        Ok((page, emails))
+    }
+
+    fn email_query_params(
+        &self,
+        filter: Option<Filter<EmailFilterCondition>>,
+        sort: Option<Vec<Comparator>>,
+        position: Option<usize>,
+        limit: usize,
+    ) -> Result<serde_json::Value> {
+        let mut params = json!({
+            "accountId": self.account_id,
+            "limit": limit,
+            "sort": email_sort(sort),
+            "calculateTotal": true
+        });
+
+        if let Some(f) = filter {
+            let value = serde_json::to_value(f)?;
+            if value.as_object().is_none_or(|o| !o.is_empty()) {
+                params["filter"] = value;
+            }
+        }
+        if let Some(position) = position {
+            params["position"] = json!(position);
+        }
+
+        Ok(params)
     }
 
     /// List emails in a mailbox by ID with optional limit
     pub async fn email_query_in_mailbox(
         &self,
         mailbox_id: &str,
+        sort: Option<Vec<Comparator>>,
+        position: Option<usize>,
         limit: usize,
-    ) -> Result<Vec<String>> {
-        let params = json!({
+    ) -> Result<EmailQueryPage> {
+        let mut params = json!({
             "accountId": self.account_id,
             "limit": limit,
             "filter": { "inMailbox": mailbox_id },
-            "sort": [{"property": "receivedAt", "isAscending": false}]
+            "sort": email_sort(sort),
+            "calculateTotal": true
         });
 
-        let args = self.call_method("Email/query", params).await?;
-
-        let ids_arr = args
-            .get("ids")
-            .and_then(|v| v.as_array())
-            .ok_or_else(|| anyhow::anyhow!("Invalid JMAP response: no ids"))?;
-
-        let ids: Vec<String> = ids_arr
-            .iter()
-            .filter_map(|v| v.as_str())
-            .map(String::from)
-            .collect();
+        if let Some(position) = position {
+            params["position"] = json!(position);
+        }
 
-        Ok(ids)
+        let args = self.call_method("Email/query", params).await?;
+        parse_email_query_page(args)
+    }
+
+    /// Every email id in a mailbox, paging through Email/query until the total is reached
+    pub async fn email_query_all_in_mailbox(&self, mailbox_id: &str) -> Result<Vec<String>> {
+        let mut ids = Vec::new();
+        loop {
+            let page = self
+                .email_query_in_mailbox(mailbox_id, None, Some(ids.len()), QUERY_PAGE_SIZE)
+                .await?;
+            let exhausted = page.ids.is_empty();
+            ids.extend(page.ids);
+            if exhausted || ids.len() >= page.total {
+                return Ok(ids);
+            }
+        }
     }
 
-    /// Get emails by IDs
-    pub async fn email_get(&self, ids: &[String]) -> Result<Vec<Email>> {
+    /// Get emails by IDs. `properties` (e.g. `["id", "subject"]`) asks for only
+    /// those properties instead of the server's default set; the rest stay None.
+    pub async fn email_get(
+        &self,
+        ids: &[String],
+        properties: Option<Vec<String>>,
+    ) -> Result<Vec<Email>> {
         if ids.is_empty() {
             return Ok(Vec::new());
         }
 
-        let params = json!({
+        let mut params = json!({
             "accountId": self.account_id,
             "ids": ids,
         });
+        if let Some(properties) = properties {
+            params["properties"] = json!(properties);
+        }
 
         let args = self.call_method("Email/get", params).await?;
 
@@ -359,9 +556,24 @@ impl<C: HttpClient> JmapClient<C> {
             .collect()
     }
 
+    /// Current Email state string, the baseline for Email/changes (RFC 8620 §5.1)
+    pub async fn email_state(&self) -> Result<String> {
+        let params = json!({
+            "accountId": self.account_id,
+            "ids": [],
+            "properties": ["id"],
+        });
+
+        let args = self.call_method("Email/get", params).await?;
+        args.get("state")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("Invalid Email/get response: no state"))
+    }
+
     /// Get a single email by ID
     pub async fn get_email(&self, id: &str) -> Result<Email> {
-        let emails = self.email_get(&[id.to_string()]).await?;
+        let emails = self.email_get(&[id.to_string()], None).await?;
         emails
             .into_iter()
             .next()
@@ -369,13 +581,36 @@ impl<C: HttpClient> JmapClient<C> {
     }
 
     /// Get a single email by ID with body values (fetches actual email body content)
-    /// Downloads body parts via RFC 8620 downloadUrl if bodyValues not already populated
+    /// Downloads body parts via RFC 8620 downloadUrl if bodyValues not already populated.
+    /// Downloaded parts are decoded from their charset (and transfer encoding, if the
+    /// server returned the part still encoded) so bodyValues always hold UTF-8 text.
     pub async fn get_email_with_body(&self, id: &str) -> Result<Email> {
         // First get the email to find out what body parts exist
-        let email = self.get_email(id).await?;
+        let params = json!({
+            "accountId": self.account_id,
+            "ids": [id],
+            "bodyProperties": [
+                "partId", "blobId", "size", "name", "type", "charset", "disposition",
+                "cid", "header:Content-Transfer-Encoding:asText"
+            ],
+        });
+        let args = self.call_method("Email/get", params).await?;
+        let email: Email = args
+            .get("list")
+            .and_then(|v| v.as_array())
+            .and_then(|list| list.first())
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()?
+            .ok_or_else(|| anyhow::anyhow!("Email not found: {}", id))?;
 
         // If body values already populated, return as-is
-        if email.body_values.is_some() {
+        if email
+            .body_values
+            .as_ref()
+            .and_then(|v| v.as_object())
+            .is_some_and(|o| !o.is_empty())
+        {
             return Ok(email);
         }
 
@@ -386,41 +621,27 @@ impl<C: HttpClient> JmapClient<C> {
 
         let mut body_obj = serde_json::Map::new();
 
-        // Download HTML body parts
-        if let Some(html_body) = &email.html_body {
-            for part in html_body {
-                if let Some(blob_id) = &part.blob_id {
-                    let content = self
-                        .download_blob_content(blob_id, "email", &part.type_)
-                        .await?;
-                    body_obj.insert(
-                        part.part_id.clone(),
-                        json!({
-                            "value": content,
-                            "isEncodingProblem": false,
-                            "isTruncated": false
-                        }),
-                    );
-                }
+        let parts = email
+            .html_body
+            .iter()
+            .chain(email.text_body.iter())
+            .flatten();
+        for part in parts {
+            if body_obj.contains_key(&part.part_id) {
+                continue;
             }
-        }
-
-        // Download text body parts
-        if let Some(text_body) = &email.text_body {
-            for part in text_body {
-                if let Some(blob_id) = &part.blob_id {
-                    let content = self
-                        .download_blob_content(blob_id, "email", &part.type_)
-                        .await?;
-                    body_obj.insert(
-                        part.part_id.clone(),
-                        json!({
-                            "value": content,
-                            "isEncodingProblem": false,
-                            "isTruncated": false
-                        }),
-                    );
-                }
+            if let Some(blob_id) = &part.blob_id {
+                let bytes = self
+                    .download_blob_content_bytes(blob_id, "email", &part.type_)
+                    .await?;
+                body_obj.insert(
+                    part.part_id.clone(),
+                    json!({
+                        "value": decode_body_part(&bytes, part),
+                        "isEncodingProblem": false,
+                        "isTruncated": false
+                    }),
+                );
             }
         }
 
@@ -445,8 +666,107 @@ impl<C: HttpClient> JmapClient<C> {
             "destroy": ids,
         });
 
-        self.call_method("Email/set", params).await?;
-        Ok(())
+        let args = self.call_method("Email/set", params).await?;
+        check_set_errors(&args, "notDestroyed", "delete email")
+    }
+
+    /// Delete emails in as many Email/set calls as maxObjectsInSet requires.
+    /// Ids the server refuses are collected rather than failing the whole run.
+    pub async fn email_delete_batched(&self, ids: &[String]) -> Result<DestroyResult> {
+        let (destroyed, not_destroyed) = self
+            .email_set_batched(
+                ids,
+                |chunk| json!({ "destroy": chunk }),
+                "destroyed",
+                "notDestroyed",
+            )
+            .await?;
+        Ok(DestroyResult {
+            destroyed,
+            not_destroyed,
+        })
+    }
+
+    /// Apply the same patch (RFC 8620 §5.3) to every email, in as many
+    /// Email/set calls as maxObjectsInSet requires.
+    /// Ids the server refuses are collected rather than failing the whole run.
+    pub async fn email_update_batched(
+        &self,
+        ids: &[String],
+        patch: serde_json::Value,
+    ) -> Result<UpdateResult> {
+        let update = |chunk: &[String]| {
+            let updates: serde_json::Map<String, serde_json::Value> =
+                chunk.iter().map(|id| (id.clone(), patch.clone())).collect();
+            json!({ "update": updates })
+        };
+        let (updated, not_updated) = self
+            .email_set_batched(ids, update, "updated", "notUpdated")
+            .await?;
+        Ok(UpdateResult {
+            updated,
+            not_updated,
+        })
+    }
+
+    /// Set or clear one keyword on every email, leaving their other keywords alone
+    pub async fn email_set_keyword_batched(
+        &self,
+        ids: &[String],
+        keyword: &str,
+        value: bool,
+    ) -> Result<UpdateResult> {
+        self.email_update_batched(ids, keyword_patch(keyword, value))
+            .await
+    }
+
+    /// Send one Email/set per maxObjectsInSet ids, with `arguments` building
+    /// each call's arguments from its ids. Returns the ids the server listed
+    /// under `done` and the errors under `failed`. A failed call is returned as
+    /// an error only while nothing has changed yet; after that its ids are
+    /// recorded as serverFail so the caller still learns which emails changed.
+    async fn email_set_batched(
+        &self,
+        ids: &[String],
+        arguments: impl Fn(&[String]) -> serde_json::Value,
+        done: &str,
+        failed: &str,
+    ) -> Result<(Vec<String>, std::collections::HashMap<String, SetError>)> {
+        let batch = self
+            .max_objects_in_set()
+            .map_or(DEFAULT_MAX_OBJECTS_IN_SET, |max| max.max(1) as usize);
+        let mut succeeded = Vec::new();
+        let mut errors = std::collections::HashMap::new();
+        for chunk in ids.chunks(batch) {
+            let mut params = arguments(chunk);
+            params["accountId"] = json!(self.account_id);
+            let args = match self.call_method("Email/set", params).await {
+                Ok(args) => args,
+                Err(e) if succeeded.is_empty() && errors.is_empty() => return Err(e),
+                Err(e) => {
+                    let description = e.to_string();
+                    errors.extend(chunk.iter().map(|id| {
+                        let error = SetError {
+                            type_: "serverFail".to_string(),
+                            description: Some(description.clone()),
+                            properties: None,
+                        };
+                        (id.clone(), error)
+                    }));
+                    continue;
+                }
+            };
+            // "destroyed" is a list of ids, "updated" an object keyed by id
+            match args.get(done) {
+                Some(serde_json::Value::Array(list)) => {
+                    succeeded.extend(list.iter().filter_map(|id| id.as_str().map(String::from)))
+                }
+                Some(serde_json::Value::Object(map)) => succeeded.extend(map.keys().cloned()),
+                _ => {}
+            }
+            errors.extend(SetError::from_response(&args, failed));
+        }
+        Ok((succeeded, errors))
     }
 
     /// Create a new Email (RFC 8621 §4.6)
@@ -458,12 +778,7 @@ impl<C: HttpClient> JmapClient<C> {
 
         let args = self.call_method("Email/set", params).await?;
 
-        // Check for errors
-        if let Some(not_created) = args.get("notCreated") {
-            if let Some(error) = not_created.get("new") {
-                anyhow::bail!("Failed to create email: {}", error);
-            }
-        }
+        check_set_errors(&args, "notCreated", "create email")?;
 
         let created = args
             .get("created")
@@ -475,7 +790,8 @@ impl<C: HttpClient> JmapClient<C> {
     }
 
     /// Update an Email's mutable properties (RFC 8621 §4.6)
-    /// Only mailboxIds and keywords can be updated
+    /// Only mailboxIds and keywords can be updated; each given map replaces the old one.
+    /// Use email_set_keyword to toggle a single keyword.
     pub async fn email_update(
         &self,
         id: &str,
@@ -496,8 +812,20 @@ impl<C: HttpClient> JmapClient<C> {
             "update": { id: update },
         });
 
-        self.call_method("Email/set", params).await?;
-        Ok(())
+        let args = self.call_method("Email/set", params).await?;
+        check_set_errors(&args, "notUpdated", "update email")
+    }
+
+    /// Set or clear a single keyword with a patch (RFC 8621 §4.6, RFC 8620 §5.3)
+    /// Other keywords on the email are left untouched
+    pub async fn email_set_keyword(&self, id: &str, keyword: &str, value: bool) -> Result<()> {
+        let params = json!({
+            "accountId": self.account_id,
+            "update": { id: keyword_patch(keyword, value) },
+        });
+
+        let args = self.call_method("Email/set", params).await?;
+        check_set_errors(&args, "notUpdated", "update email")
     }
 
     /// Import an RFC 5322 message from a blob (RFC 8621 §4.8)
@@ -509,12 +837,7 @@ impl<C: HttpClient> JmapClient<C> {
 
         let args = self.call_method("Email/import", params).await?;
 
-        // Check for errors
-        if let Some(not_created) = args.get("notCreated") {
-            if let Some(error) = not_created.get("import1") {
-                anyhow::bail!("Failed to import email: {}", error);
-            }
-        }
+        check_set_errors(&args, "notCreated", "import email")?;
 
         let created = args
             .get("created")
@@ -566,7 +889,9 @@ impl<C: HttpClient> JmapClient<C> {
             .collect()
     }
 
-    /// Parse a blob as an RFC 5322 message without storing it (RFC 8621 §4.9)
+    /// Parse a blob as an RFC 5322 message without storing it (RFC 8621 §4.9).
+    /// Returns the parsed emails keyed by blobId. They are left untyped because a
+    /// parsed email has no id and may carry arbitrary `header:` properties.
     #[allow(clippy::too_many_arguments)]
     pub async fn email_parse(
         &self,
@@ -577,7 +902,7 @@ impl<C: HttpClient> JmapClient<C> {
         fetch_html_body_values: Option<bool>,
         fetch_all_body_values: Option<bool>,
         max_body_value_bytes: Option<u64>,
-    ) -> Result<Vec<Email>> {
+    ) -> Result<serde_json::Map<String, serde_json::Value>> {
         let mut params = json!({
             "accountId": self.account_id,
             "blobIds": blob_ids,
@@ -604,15 +929,12 @@ impl<C: HttpClient> JmapClient<C> {
 
         let args = self.call_method("Email/parse", params).await?;
 
-        let parsed = args
-            .get("parsed")
-            .and_then(|v| v.as_object())
-            .ok_or_else(|| anyhow::anyhow!("Invalid Email/parse response"))?;
-
-        parsed
-            .values()
-            .map(|v| serde_json::from_value(v.clone()).map_err(Into::into))
-            .collect()
+        match args.get("parsed") {
+            Some(serde_json::Value::Object(parsed)) => Ok(parsed.clone()),
+            // null when no blob could be parsed
+            Some(serde_json::Value::Null) => Ok(serde_json::Map::new()),
+            _ => Err(anyhow::anyhow!("Invalid Email/parse response")),
+        }
     }
 
     /// List all mailboxes
@@ -665,12 +987,7 @@ impl<C: HttpClient> JmapClient<C> {
 
         let args = self.call_method("Mailbox/set", params).await?;
 
-        // Check for errors in notCreated
-        if let Some(not_created) = args.get("notCreated") {
-            if let Some(error) = not_created.get("new") {
-                anyhow::bail!("Failed to create mailbox: {}", error);
-            }
-        }
+        check_set_errors(&args, "notCreated", "create mailbox")?;
 
         // Get the created mailbox ID
         let id = args
@@ -691,8 +1008,8 @@ impl<C: HttpClient> JmapClient<C> {
             "destroy": [id]
         });
 
-        self.call_method("Mailbox/set", params).await?;
-        Ok(())
+        let args = self.call_method("Mailbox/set", params).await?;
+        check_set_errors(&args, "notDestroyed", "delete mailbox")
     }
 
     /// Query Mailboxes with filter and sort (RFC 8621 §2.3)
@@ -730,6 +1047,48 @@ impl<C: HttpClient> JmapClient<C> {
             .collect())
     }
 
+    /// Query and fetch mailboxes in one request using a `#ids` result reference
+    pub async fn mailbox_query_and_get(
+        &self,
+        filter: crate::types::MailboxFilterCondition,
+    ) -> Result<Vec<Mailbox>> {
+        let query = json!({
+            "accountId": self.account_id,
+            "filter": filter,
+        });
+        let get = json!({
+            "accountId": self.account_id,
+            "#ids": {
+                "resultOf": "0",
+                "name": "Mailbox/query",
+                "path": "/ids"
+            }
+        });
+
+        let responses = self
+            .call_methods(
+                &[CORE_CAPABILITY, MAIL_CAPABILITY],
+                vec![
+                    Invocation::new("Mailbox/query", query, "0"),
+                    Invocation::new("Mailbox/get", get, "1"),
+                ],
+            )
+            .await?;
+
+        let get_response = responses
+            .iter()
+            .find(|r| r.tag == "1")
+            .ok_or_else(|| anyhow!("Invalid JMAP response: missing response 1"))?;
+        let args = method_response_args(get_response, "Mailbox/get")?;
+        let list = args
+            .get("list")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| anyhow::anyhow!("Invalid JMAP response: no list"))?;
+        list.iter()
+            .map(|v| serde_json::from_value(v.clone()).map_err(Into::into))
+            .collect()
+    }
+
     /// Update a Mailbox (RFC 8621 §2.5)
     pub async fn mailbox_update(
         &self,
@@ -759,7 +1118,9 @@ impl<C: HttpClient> JmapClient<C> {
             "update": { id: update },
         });
 
-        self.call_method("Mailbox/set", params).await?;
+        let args = self.call_method("Mailbox/set", params).await?;
+
+        check_set_errors(&args, "notUpdated", "update mailbox")?;
         Ok(())
     }
 
@@ -788,8 +1149,80 @@ impl<C: HttpClient> JmapClient<C> {
             .collect()
     }
 
-    /// Get Thread changes since state (RFC 8621 §3.2)
-    pub async fn thread_changes(
+    /// Fetch the thread containing `email_id` and all of its emails in one request,
+    /// chaining Email/get → Thread/get → Email/get with result references.
+    /// Emails are returned oldest first.
+    pub async fn thread_emails(&self, email_id: &str) -> Result<(Thread, Vec<Email>)> {
+        let email = json!({
+            "accountId": self.account_id,
+            "ids": [email_id],
+            "properties": ["threadId"],
+        });
+        let thread = json!({
+            "accountId": self.account_id,
+            "#ids": {"resultOf": "0", "name": "Email/get", "path": "/list/*/threadId"},
+        });
+        let emails = json!({
+            "accountId": self.account_id,
+            "#ids": {"resultOf": "1", "name": "Thread/get", "path": "/list/*/emailIds"},
+            "properties": [
+                "id", "threadId", "mailboxIds", "keywords", "receivedAt",
+                "from", "to", "subject", "preview"
+            ],
+        });
+
+        let responses = self
+            .call_methods(
+                &[CORE_CAPABILITY, MAIL_CAPABILITY],
+                vec![
+                    Invocation::new("Email/get", email, "0"),
+                    Invocation::new("Thread/get", thread, "1"),
+                    Invocation::new("Email/get", emails, "2"),
+                ],
+            )
+            .await?;
+
+        let find = |tag: &str| {
+            responses
+                .iter()
+                .find(|r| r.tag == tag)
+                .ok_or_else(|| anyhow!("Invalid JMAP response: missing response {}", tag))
+        };
+        let first = method_response_args(find("0")?, "Email/get")?;
+        if first
+            .get("notFound")
+            .and_then(|v| v.as_array())
+            .is_some_and(|ids| !ids.is_empty())
+        {
+            anyhow::bail!("Email not found: {}", email_id);
+        }
+
+        let threads = method_response_args(find("1")?, "Thread/get")?;
+        let thread: Thread = threads
+            .get("list")
+            .and_then(|v| v.as_array())
+            .and_then(|list| list.first())
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()?
+            .ok_or_else(|| anyhow!("No thread found for email {}", email_id))?;
+
+        let args = method_response_args(find("2")?, "Email/get")?;
+        let mut emails = args
+            .get("list")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| anyhow::anyhow!("Invalid JMAP response: no list"))?
+            .iter()
+            .map(|v| serde_json::from_value(v.clone()).map_err(Into::into))
+            .collect::<Result<Vec<Email>>>()?;
+        // receivedAt is UTCDate, so the strings sort chronologically
+        emails.sort_by(|a, b| a.received_at.cmp(&b.received_at));
+
+        Ok((thread, emails))
+    }
+
+    /// Get Thread changes since state (RFC 8621 §3.2)
+    pub async fn thread_changes(
         &self,
         since_state: &str,
         max_changes: Option<usize>,
@@ -889,11 +1322,22 @@ impl<C: HttpClient> JmapClient<C> {
     // RFC 8621 EmailSubmission methods (§7)
 
     /// Create and send an EmailSubmission (RFC 8621 §7.5)
+    ///
+    /// With `send_at`, the message is held by the server until that time using the
+    /// FUTURERELEASE `HOLDUNTIL` envelope parameter (RFC 4865). Until then the
+    /// submission keeps `undoStatus: pending` and can be withdrawn with
+    /// `email_submission_cancel`; after it is released the undo window is over.
+    /// Delayed sends need an explicit envelope to carry the parameter.
+    /// `on_success_update_email` is a patch the server applies to the email once
+    /// the submission is created, in the same request (`onSuccessUpdateEmail`,
+    /// RFC 8621 §7.5), e.g. to move it from Drafts to Sent.
     pub async fn email_submission_create(
         &self,
         identity_id: &str,
         email_id: &str,
         envelope: Option<Envelope>,
+        send_at: Option<DateTime<Utc>>,
+        on_success_update_email: Option<serde_json::Value>,
     ) -> Result<EmailSubmission> {
         let mut create_obj = json!({
             "identityId": identity_id,
@@ -903,23 +1347,29 @@ impl<C: HttpClient> JmapClient<C> {
         if let Some(env) = envelope {
             create_obj["envelope"] = serde_json::to_value(env)?;
         }
+        if let Some(at) = send_at {
+            let envelope = create_obj
+                .get_mut("envelope")
+                .ok_or_else(|| anyhow!("Delayed send requires an envelope"))?;
+            envelope["mailFrom"]["parameters"] = json!({
+                "HOLDUNTIL": at.to_rfc3339_opts(SecondsFormat::Secs, true)
+            });
+        }
 
-        let params = json!({
+        let mut params = json!({
             "accountId": self.account_id,
             "create": { "sub": create_obj },
         });
+        if let Some(patch) = on_success_update_email {
+            params["onSuccessUpdateEmail"] = json!({ "#sub": patch });
+        }
 
         let using = [CORE_CAPABILITY, MAIL_CAPABILITY, SUBMISSION_CAPABILITY];
         let args = self
             .call_method_with_using(&using, "EmailSubmission/set", params)
             .await?;
 
-        // Check for errors
-        if let Some(not_created) = args.get("notCreated") {
-            if let Some(error) = not_created.get("sub") {
-                anyhow::bail!("Failed to submit email: {}", error);
-            }
-        }
+        check_set_errors(&args, "notCreated", "submit email")?;
 
         let created = args
             .get("created")
@@ -964,9 +1414,10 @@ impl<C: HttpClient> JmapClient<C> {
         });
 
         let using = [CORE_CAPABILITY, MAIL_CAPABILITY, SUBMISSION_CAPABILITY];
-        self.call_method_with_using(&using, "EmailSubmission/set", params)
+        let args = self
+            .call_method_with_using(&using, "EmailSubmission/set", params)
             .await?;
-        Ok(())
+        check_set_errors(&args, "notUpdated", "cancel submission")
     }
 
     // RFC 8621 VacationResponse methods (§8)
@@ -996,12 +1447,13 @@ impl<C: HttpClient> JmapClient<C> {
         serde_json::from_value(first.clone()).map_err(Into::into)
     }
 
-    /// Update VacationResponse (RFC 8621 §8.2)
+    /// Update VacationResponse (RFC 8621 §8.2). Fields left as None are not
+    /// changed; a date of `Some(None)` is cleared.
     pub async fn vacation_response_set(
         &self,
         is_enabled: Option<bool>,
-        from_date: Option<&str>,
-        to_date: Option<&str>,
+        from_date: Option<Option<&str>>,
+        to_date: Option<Option<&str>>,
         subject: Option<&str>,
         text_body: Option<&str>,
         html_body: Option<&str>,
@@ -1033,9 +1485,10 @@ impl<C: HttpClient> JmapClient<C> {
         });
 
         let using = [CORE_CAPABILITY, VACATION_CAPABILITY];
-        self.call_method_with_using(&using, "VacationResponse/set", params)
+        let args = self
+            .call_method_with_using(&using, "VacationResponse/set", params)
             .await?;
-        Ok(())
+        check_set_errors(&args, "notUpdated", "update vacation response")
     }
 
     /// Upload blobs via Blob/upload
@@ -1177,6 +1630,7 @@ impl<C: HttpClient> JmapClient<C> {
 
     /// Upload text as a blob
     pub async fn blob_upload_text(&self, text: &str, type_: Option<&str>) -> Result<String> {
+        check_blob_size(text.len() as u64, self.max_size_blob_set())?;
         let mut create = std::collections::HashMap::new();
         create.insert(
             "single".to_string(),
@@ -1198,6 +1652,7 @@ impl<C: HttpClient> JmapClient<C> {
 
     /// Upload raw bytes as a blob
     pub async fn blob_upload_bytes(&self, bytes: &[u8], type_: Option<&str>) -> Result<String> {
+        check_blob_size(bytes.len() as u64, self.max_size_blob_set())?;
         let mut create = std::collections::HashMap::new();
         create.insert(
             "single".to_string(),
@@ -1440,9 +1895,10 @@ impl<C: HttpClient> JmapClient<C> {
         });
 
         let using = [CORE_CAPABILITY, PRINCIPALS_CAPABILITY];
-        self.call_method_with_using(&using, "ShareNotification/set", params)
+        let args = self
+            .call_method_with_using(&using, "ShareNotification/set", params)
             .await?;
-        Ok(())
+        check_set_errors(&args, "notDestroyed", "dismiss share notification")
     }
 
     // RFC 8620 Core methods
@@ -1495,24 +1951,28 @@ impl<C: HttpClient> JmapClient<C> {
 
     // RFC 8620 QueryChanges method (§5.6)
 
-    /// Get Email/queryChanges for incremental query sync (RFC 8620 §5.6)
+    /// Get Email/queryChanges for incremental query sync (RFC 8620 §5.6).
+    /// Filter and sort must be those of the query that returned `since_query_state`,
+    /// so they are sent exactly as `email_query` sends them.
     pub async fn email_query_changes(
         &self,
         since_query_state: &str,
-        filter: Option<serde_json::Value>,
-        sort: Option<Vec<serde_json::Value>>,
+        filter: Option<Filter<EmailFilterCondition>>,
+        sort: Option<Vec<Comparator>>,
         max_changes: Option<usize>,
     ) -> Result<QueryChangesResponse> {
         let mut params = json!({
             "accountId": self.account_id,
             "sinceQueryState": since_query_state,
+            "sort": email_sort(sort),
+            "calculateTotal": true
         });
 
         if let Some(f) = filter {
-            params["filter"] = f;
-        }
-        if let Some(s) = sort {
-            params["sort"] = json!(s);
+            let value = serde_json::to_value(f)?;
+            if value.as_object().is_none_or(|o| !o.is_empty()) {
+                params["filter"] = value;
+            }
         }
         if let Some(mc) = max_changes {
             params["maxChanges"] = json!(mc);
@@ -1600,15 +2060,27 @@ impl JmapClient<crate::http::ReqwestClient> {
     pub async fn connect(session_url: &str, token: String) -> Result<Self> {
         let http = crate::http::ReqwestClient::new().with_token(token);
         let session = Self::fetch_session(&http, session_url).await?;
-        let account_id = Self::select_account_id(&session)?;
-        Ok(Self {
-            http,
-            session,
-            account_id,
-        })
+        Self::from_session(http, session)
     }
 }
 
+/// Fail with the server's reasons if a /set response has errors in `section`.
+/// Creation ids are client-chosen placeholders, so only updated/destroyed ids are named.
+pub fn check_set_errors(args: &serde_json::Value, section: &str, action: &str) -> Result<()> {
+    let errors = SetError::from_response(args, section);
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let details: Vec<String> = errors
+        .iter()
+        .map(|(id, error)| match section {
+            "notCreated" => error.to_string(),
+            _ => format!("{} ({})", error, id),
+        })
+        .collect();
+    anyhow::bail!("Failed to {}: {}", action, details.join("; "))
+}
+
 fn parse_method_responses(resp: &serde_json::Value) -> Result<Vec<Invocation>> {
     let obj = resp
         .as_object()
@@ -1647,6 +2119,88 @@ fn parse_method_responses(resp: &serde_json::Value) -> Result<Vec<Invocation>> {
     Ok(invocations)
 }
 
+/// Unwrap a method response, turning a JMAP "error" into a JmapError
+fn method_response_args(response: &Invocation, method: &str) -> Result<serde_json::Value> {
+    if response.name == "error" {
+        let jmap_err = JmapError::from_value(&response.args);
+        return Err(anyhow!(jmap_err));
+    }
+
+    if response.name != method {
+        anyhow::bail!(
+            "Unexpected JMAP response method: expected {}, got {}",
+            method,
+            response.name
+        );
+    }
+
+    Ok(response.args.clone())
+}
+
+/// Decode a downloaded body part to text.
+/// Blobs are normally served with the transfer encoding already removed (RFC 8621 §4.1.4),
+/// in which case their length equals the part size; otherwise decode it here first.
+fn decode_body_part(bytes: &[u8], part: &BodyPart) -> String {
+    let still_encoded = part.size.is_some_and(|size| size != bytes.len() as u64);
+    let decoded = match &part.transfer_encoding {
+        Some(encoding) if still_encoded => mime::decode_transfer_encoding(bytes, encoding),
+        _ => bytes.to_vec(),
+    };
+    mime::decode_charset(&decoded, part.charset.as_deref())
+}
+
+/// Parse an Email/query response into a page of ids
+fn parse_email_query_page(args: serde_json::Value) -> Result<EmailQueryPage> {
+    if !args.get("ids").is_some_and(|v| v.is_array()) {
+        return Err(anyhow!("Invalid JMAP response: no ids"));
+    }
+    Ok(serde_json::from_value(args)?)
+}
+
+/// Build a `keywords/<kw>` patch; clearing a keyword sets it to null
+fn keyword_patch(keyword: &str, value: bool) -> serde_json::Value {
+    // Keywords are JSON Pointer path segments (RFC 6901 §3)
+    let path = format!("keywords/{}", keyword.replace('~', "~0").replace('/', "~1"));
+    let value = if value { json!(true) } else { json!(null) };
+    json!({ path: value })
+}
+
+/// Expand a value for a URL template variable (RFC 6570 §3.2.2): everything
+/// but unreserved characters is percent-encoded as UTF-8
+fn uri_template_value(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Sort for Email/query, defaulting to newest received first
+fn email_sort(sort: Option<Vec<Comparator>>) -> Vec<Comparator> {
+    sort.unwrap_or_else(|| vec![Comparator::desc("receivedAt")])
+}
+
+/// Refuse an upload locally rather than let the server reject it
+fn check_blob_size(size: u64, max_size: Option<u64>) -> Result<()> {
+    match max_size {
+        Some(max_size) if size > max_size => Err(BlobError::SizeExceeded { size, max_size }.into()),
+        _ => Ok(()),
+    }
+}
+
+/// blobId from an uploadUrl response: { "blobId": "xxx", "size": yyy }
+fn upload_response_blob_id(resp_bytes: &[u8]) -> Result<String> {
+    let resp: serde_json::Value = serde_json::from_slice(resp_bytes)?;
+    resp.get("blobId")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("No blobId in upload response"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1655,15 +2209,373 @@ mod tests {
 
     struct MockHttpClient {
         response: Vec<u8>,
+        requests: std::sync::Mutex<Vec<serde_json::Value>>,
+    }
+
+    impl MockHttpClient {
+        fn last_request(&self) -> serde_json::Value {
+            self.requests.lock().unwrap().last().cloned().unwrap()
+        }
     }
 
     #[async_trait]
     impl HttpClient for MockHttpClient {
-        async fn post_json(&self, _url: &str, _body: Vec<u8>) -> Result<Vec<u8>, HttpError> {
+        async fn post_json(&self, _url: &str, body: Vec<u8>) -> Result<Vec<u8>, HttpError> {
+            if let Ok(request) = serde_json::from_slice(&body) {
+                self.requests.lock().unwrap().push(request);
+            }
             Ok(self.response.clone())
         }
     }
 
+    #[allow(deprecated)]
+    fn mock_client(response: serde_json::Value) -> JmapClient<MockHttpClient> {
+        JmapClient::new(
+            MockHttpClient {
+                response: serde_json::to_vec(&response).unwrap(),
+                requests: std::sync::Mutex::new(Vec::new()),
+            },
+            "https://example.com/jmap".to_string(),
+            "acc1".to_string(),
+        )
+    }
+
+    fn fastmail_session(username: &str) -> Session {
+        serde_json::from_value(json!({
+            "capabilities": {
+                "urn:ietf:params:jmap:core": { "maxSizeUpload": 250000000 },
+                "urn:ietf:params:jmap:mail": {}
+            },
+            "accounts": {
+                "u1234abcd": {
+                    "name": "alice@fastmail.com",
+                    "isPersonal": true,
+                    "isReadOnly": false,
+                    "accountCapabilities": { "urn:ietf:params:jmap:mail": {} }
+                },
+                "u5678efgh": {
+                    "name": "Shared Team",
+                    "isPersonal": false,
+                    "isReadOnly": true,
+                    "accountCapabilities": {}
+                }
+            },
+            "primaryAccounts": {
+                "urn:ietf:params:jmap:core": "u1234abcd",
+                "urn:ietf:params:jmap:mail": "u1234abcd"
+            },
+            "username": username,
+            "apiUrl": "https://api.fastmail.com/jmap/api/",
+            "downloadUrl": "https://www.fastmailusercontent.com/jmap/download/{accountId}/{blobId}/{name}?type={type}",
+            "uploadUrl": "https://api.fastmail.com/jmap/upload/{accountId}/",
+            "eventSourceUrl": "https://api.fastmail.com/jmap/event/",
+            "state": "cyrus-0;p-5;vfs-0"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_session_account_email_prefers_username() {
+        let session = fastmail_session("bob@fastmail.com");
+        assert_eq!(
+            JmapClient::<MockHttpClient>::session_account_email(&session, "u5678efgh"),
+            Some("bob@fastmail.com")
+        );
+    }
+
+    #[test]
+    fn test_session_account_email_uses_primary_mail_account() {
+        // Token sessions can report a username that is not an address
+        let session = fastmail_session("u1234abcd");
+        assert_eq!(
+            JmapClient::<MockHttpClient>::session_account_email(&session, "u5678efgh"),
+            Some("alice@fastmail.com")
+        );
+    }
+
+    #[test]
+    fn test_session_account_email_none_without_address() {
+        let mut session = fastmail_session("u1234abcd");
+        session.primary_accounts.clear();
+        assert_eq!(
+            JmapClient::<MockHttpClient>::session_account_email(&session, "u5678efgh"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_from_session_with_account() {
+        let http = || MockHttpClient {
+            response: Vec::new(),
+            requests: std::sync::Mutex::new(Vec::new()),
+        };
+        let session = fastmail_session("alice@fastmail.com");
+        let client =
+            JmapClient::from_session_with_account(http(), session.clone(), "u5678efgh").unwrap();
+        assert_eq!(client.account_id(), "u5678efgh");
+
+        let err = JmapClient::from_session_with_account(http(), session, "nope")
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<JmapError>(),
+            Some(JmapError::AccountNotFound(id)) if id == "nope"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_mailbox_query_and_get_by_role() {
+        let client = mock_client(json!({
+            "methodResponses": [
+                ["Mailbox/query", { "ids": ["mb9"] }, "0"],
+                ["Mailbox/get", { "list": [{ "id": "mb9", "name": "Papierkorb", "role": "trash" }] }, "1"]
+            ]
+        }));
+
+        let mailboxes = client
+            .mailbox_query_and_get(crate::types::MailboxFilterCondition {
+                role: Some("trash".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(mailboxes[0].name, "Papierkorb");
+
+        let request = client.http.last_request();
+        assert_eq!(
+            request["methodCalls"][0][1]["filter"],
+            json!({ "role": "trash" })
+        );
+        assert_eq!(request["methodCalls"][1][1]["#ids"]["resultOf"], "0");
+    }
+
+    #[tokio::test]
+    async fn test_email_query_all_in_mailbox_pages() {
+        let client = mock_client(json!({
+            "methodResponses": [["Email/query", {
+                "ids": ["m1", "m2"],
+                "position": 0,
+                "total": 4
+            }, "0"]]
+        }));
+
+        let ids = client.email_query_all_in_mailbox("inbox").await.unwrap();
+        assert_eq!(ids.len(), 4);
+        let requests = client.http.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1]["methodCalls"][0][1]["position"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_vacation_response_set_clears_dates_with_null() {
+        let client = mock_client(json!({
+            "methodResponses": [["VacationResponse/set", { "updated": { "singleton": null } }, "0"]]
+        }));
+
+        client
+            .vacation_response_set(Some(true), Some(None), None, Some("Away"), None, None)
+            .await
+            .unwrap();
+
+        let request = client.http.last_request();
+        let update = &request["methodCalls"][0][1]["update"]["singleton"];
+        assert_eq!(
+            update,
+            &json!({ "isEnabled": true, "fromDate": null, "subject": "Away" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_email_count_asks_only_for_the_total() {
+        let client = mock_client(json!({
+            "methodResponses": [["Email/query", { "ids": [], "position": 0, "total": 42 }, "0"]]
+        }));
+
+        let filter = EmailFilterCondition {
+            in_mailbox: Some("inbox".to_string()),
+            not_keyword: Some("$seen".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(client.email_count(Some(filter.into())).await.unwrap(), 42);
+        let request = client.http.last_request();
+        let args = &request["methodCalls"][0][1];
+        assert_eq!(args["limit"], 0);
+        assert_eq!(args["calculateTotal"], true);
+        assert_eq!(args["filter"]["notKeyword"], "$seen");
+    }
+
+    #[tokio::test]
+    async fn test_email_query_changes_matches_the_query() {
+        let client = mock_client(json!({
+            "methodResponses": [["Email/queryChanges", {
+                "accountId": "u1234abcd",
+                "oldQueryState": "q1",
+                "newQueryState": "q2",
+                "total": 3,
+                "removed": ["m1"],
+                "added": [{ "id": "m9", "index": 0 }]
+            }, "0"]]
+        }));
+
+        let filter = EmailFilterCondition {
+            in_mailbox: Some("inbox".to_string()),
+            ..Default::default()
+        };
+        let changes = client
+            .email_query_changes("q1", Some(filter.into()), None, None)
+            .await
+            .unwrap();
+        assert_eq!(changes.new_query_state, "q2");
+        assert_eq!(changes.added[0].id, "m9");
+        assert_eq!(changes.total, Some(3));
+
+        let request = client.http.last_request();
+        let args = &request["methodCalls"][0][1];
+        assert_eq!(args["sinceQueryState"], "q1");
+        assert_eq!(args["filter"]["inMailbox"], "inbox");
+        assert_eq!(args["sort"][0]["property"], "receivedAt");
+        assert!(args.get("maxChanges").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_email_delete_batched_respects_max_objects_in_set() {
+        let mut session = fastmail_session("u1234abcd");
+        session
+            .capabilities
+            .insert(CORE_CAPABILITY.to_string(), json!({ "maxObjectsInSet": 2 }));
+        let client = JmapClient::from_session(
+            MockHttpClient {
+                response: serde_json::to_vec(&json!({
+                    "methodResponses": [["Email/set", {
+                        "destroyed": ["m1"],
+                        "notDestroyed": { "m2": { "type": "notFound" } }
+                    }, "0"]]
+                }))
+                .unwrap(),
+                requests: std::sync::Mutex::new(Vec::new()),
+            },
+            session,
+        )
+        .unwrap();
+
+        let ids: Vec<String> = (1..=5).map(|i| format!("m{}", i)).collect();
+        let result = client.email_delete_batched(&ids).await.unwrap();
+        // The mock answers every batch the same way
+        assert_eq!(result.destroyed, vec!["m1"; 3]);
+        assert_eq!(result.not_destroyed["m2"].type_, "notFound");
+
+        let requests = client.http.requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[2]["methodCalls"][0][1]["destroy"], json!(["m5"]));
+    }
+
+    /// Answers the first request with `response` and fails every later one
+    struct FailingAfterFirstHttpClient {
+        response: Vec<u8>,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl HttpClient for FailingAfterFirstHttpClient {
+        async fn post_json(&self, _url: &str, _body: Vec<u8>) -> Result<Vec<u8>, HttpError> {
+            if self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                Ok(self.response.clone())
+            } else {
+                Err(HttpError {
+                    status: Some(503),
+                    message: "Service Unavailable".to_string(),
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_email_delete_batched_keeps_earlier_batches_when_one_fails() {
+        let mut session = fastmail_session("u1234abcd");
+        session
+            .capabilities
+            .insert(CORE_CAPABILITY.to_string(), json!({ "maxObjectsInSet": 2 }));
+        let client = JmapClient::from_session(
+            FailingAfterFirstHttpClient {
+                response: serde_json::to_vec(&json!({
+                    "methodResponses": [["Email/set", { "destroyed": ["m1", "m2"] }, "0"]]
+                }))
+                .unwrap(),
+                calls: Default::default(),
+            },
+            session,
+        )
+        .unwrap();
+
+        let ids: Vec<String> = (1..=4).map(|i| format!("m{}", i)).collect();
+        let result = client.email_delete_batched(&ids).await.unwrap();
+        assert_eq!(result.destroyed, vec!["m1", "m2"]);
+        assert_eq!(result.not_destroyed.len(), 2);
+        assert_eq!(result.not_destroyed["m3"].type_, "serverFail");
+        assert!(result.not_destroyed["m4"].description.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_upload_blob_file_checks_max_size_upload() {
+        let mut session = fastmail_session("u1234abcd");
+        session
+            .capabilities
+            .insert(CORE_CAPABILITY.to_string(), json!({ "maxSizeUpload": 4 }));
+        let client = JmapClient::from_session(
+            MockHttpClient {
+                response: Vec::new(),
+                requests: std::sync::Mutex::new(Vec::new()),
+            },
+            session,
+        )
+        .unwrap();
+        assert_eq!(client.max_size_upload(), Some(4));
+
+        let path = std::env::temp_dir().join(format!("jmap-upload-{}", std::process::id()));
+        std::fs::write(&path, b"too large").unwrap();
+        let err = client
+            .upload_blob_file(&path, "text/plain")
+            .await
+            .unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            err.downcast_ref::<BlobError>(),
+            Some(BlobError::SizeExceeded {
+                size: 9,
+                max_size: 4
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_blob_upload_checks_max_size_blob_set() {
+        let mut session = fastmail_session("u1234abcd");
+        session
+            .accounts
+            .get_mut("u1234abcd")
+            .unwrap()
+            .account_capabilities
+            .as_mut()
+            .unwrap()
+            .insert(BLOB_CAPABILITY.to_string(), json!({ "maxSizeBlobSet": 3 }));
+        let client = JmapClient::from_session(
+            MockHttpClient {
+                response: Vec::new(),
+                requests: std::sync::Mutex::new(Vec::new()),
+            },
+            session,
+        )
+        .unwrap();
+
+        let err = client.blob_upload_bytes(b"four", None).await.unwrap_err();
+        assert_eq!(err.to_string(), "Blob size exceeds maximum: 4 > 3");
+        assert!(client.http.requests.lock().unwrap().is_empty());
+        assert!(check_blob_size(3, Some(3)).is_ok());
+        assert!(check_blob_size(u64::MAX, None).is_ok());
+    }
+
     #[tokio::test]
     async fn test_call_method_parses_method_responses() {
         let response = serde_json::json!({
@@ -1673,13 +2585,7 @@ mod tests {
             "sessionState": "state1"
         });
 
-        let client = JmapClient::new(
-            MockHttpClient {
-                response: serde_json::to_vec(&response).unwrap(),
-            },
-            "https://example.com/jmap".to_string(),
-            "acc1".to_string(),
-        );
+        let client = mock_client(response);
 
         let args = client
             .call_method("Email/query", serde_json::json!({"accountId": "acc1"}))
@@ -1688,4 +2594,518 @@ mod tests {
 
         assert_eq!(args["ids"], serde_json::json!(["id1"]));
     }
+
+    #[tokio::test]
+    async fn test_method_error_is_typed() {
+        let client = mock_client(json!({
+            "methodResponses": [["error", { "type": "rateLimit", "description": "slow down" }, "0"]]
+        }));
+
+        let err = client.email_query(None, None, None, 10).await.unwrap_err();
+        let jmap_err = err.downcast_ref::<JmapError>().unwrap();
+        assert!(
+            matches!(jmap_err, JmapError::RateLimit { description } if description.as_deref() == Some("slow down"))
+        );
+        assert!(jmap_err.is_transient());
+    }
+
+    #[tokio::test]
+    async fn test_email_query_sends_filter() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [["Email/query", {"ids": ["m1"]}, "0"]]
+        }));
+
+        let filter = Filter::and(vec![
+            EmailFilterCondition {
+                from: Some("alice@example.com".to_string()),
+                ..Default::default()
+            }
+            .into(),
+            EmailFilterCondition {
+                has_attachment: Some(true),
+                ..Default::default()
+            }
+            .into(),
+        ]);
+        let page = client
+            .email_query(Some(filter), None, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(page.ids, vec!["m1"]);
+
+        let args = &client.http.last_request()["methodCalls"][0][1];
+        assert_eq!(args["filter"]["operator"], "AND");
+        assert_eq!(args["filter"]["conditions"][0]["from"], "alice@example.com");
+        assert_eq!(args["filter"]["conditions"][1]["hasAttachment"], true);
+    }
+
+    #[tokio::test]
+    async fn test_email_query_omits_empty_filter() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [["Email/query", {"ids": []}, "0"]]
+        }));
+
+        client
+            .email_query(Some(EmailFilterCondition::default().into()), None, None, 10)
+            .await
+            .unwrap();
+
+        let args = &client.http.last_request()["methodCalls"][0][1];
+        assert!(args.get("filter").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_email_query_default_sort() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [["Email/query", {"ids": []}, "0"]]
+        }));
+
+        client.email_query(None, None, None, 10).await.unwrap();
+
+        let args = &client.http.last_request()["methodCalls"][0][1];
+        assert_eq!(
+            args["sort"],
+            serde_json::json!([{"property": "receivedAt", "isAscending": false}])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_email_query_custom_sort() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [["Email/query", {"ids": []}, "0"]]
+        }));
+
+        client
+            .email_query_in_mailbox("inbox", Some(vec![Comparator::new("size")]), None, 10)
+            .await
+            .unwrap();
+
+        let args = &client.http.last_request()["methodCalls"][0][1];
+        assert_eq!(
+            args["sort"],
+            serde_json::json!([{"property": "size", "isAscending": true}])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_email_query_returns_page() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [
+                ["Email/query", {"ids": ["m21", "m22"], "position": 20, "total": 4213}, "0"]
+            ]
+        }));
+
+        let page = client.email_query(None, None, Some(20), 2).await.unwrap();
+        assert_eq!(page.ids, vec!["m21", "m22"]);
+        assert_eq!(page.position, 20);
+        assert_eq!(page.total, 4213);
+
+        let args = &client.http.last_request()["methodCalls"][0][1];
+        assert_eq!(args["position"], 20);
+        assert_eq!(args["calculateTotal"], true);
+    }
+
+    #[tokio::test]
+    async fn test_call_methods_sends_all_invocations() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [
+                ["Mailbox/get", {"list": []}, "a"],
+                ["error", {"type": "unknownMethod"}, "b"]
+            ]
+        }));
+
+        let responses = client
+            .call_methods(
+                &[CORE_CAPABILITY, MAIL_CAPABILITY],
+                vec![
+                    Invocation::new("Mailbox/get", json!({"accountId": "acc1"}), "a"),
+                    Invocation::new("Foo/bar", json!({}), "b"),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let calls = &client.http.last_request()["methodCalls"];
+        assert_eq!(calls.as_array().unwrap().len(), 2);
+        assert_eq!(calls[1][0], "Foo/bar");
+        assert_eq!(calls[1][2], "b");
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].tag, "a");
+        assert_eq!(responses[1].name, "error");
+        assert_eq!(responses[1].tag, "b");
+    }
+
+    #[tokio::test]
+    async fn test_email_query_and_get_uses_result_reference() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [
+                ["Email/query", {"ids": ["m1"], "position": 0, "total": 1}, "0"],
+                ["Email/get", {"list": [{"id": "m1", "subject": "Hello"}], "notFound": []}, "1"]
+            ]
+        }));
+
+        let (page, emails) = client
+            .email_query_and_get(None, None, None, 5, None)
+            .await
+            .unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(emails.len(), 1);
+        assert_eq!(emails[0].id, "m1");
+
+        let calls = &client.http.last_request()["methodCalls"];
+        assert_eq!(calls[0][0], "Email/query");
+        assert_eq!(calls[0][2], "0");
+        assert_eq!(calls[1][0], "Email/get");
+        assert!(calls[1][1].get("ids").is_none());
+        assert_eq!(
+            calls[1][1]["#ids"],
+            serde_json::json!({"resultOf": "0", "name": "Email/query", "path": "/ids"})
+        );
+        assert!(calls[1][1].get("properties").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_email_get_requests_only_the_given_properties() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [
+                ["Email/get", {"list": [{"id": "m1", "subject": "Hello"}], "notFound": []}, "0"]
+            ]
+        }));
+
+        let properties = ["id", "subject", "from", "receivedAt"]
+            .map(String::from)
+            .to_vec();
+        let emails = client
+            .email_get(&["m1".to_string()], Some(properties.clone()))
+            .await
+            .unwrap();
+        assert_eq!(emails[0].subject.as_deref(), Some("Hello"));
+        assert!(emails[0].mailbox_ids.is_none() && emails[0].preview.is_none());
+
+        let args = &client.http.last_request()["methodCalls"][0][1];
+        assert_eq!(args["properties"], serde_json::json!(properties));
+    }
+
+    #[tokio::test]
+    async fn test_mailbox_update_reports_not_updated() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [["Mailbox/set", {
+                "notUpdated": {"mb1": {
+                    "type": "invalidProperties",
+                    "description": "Name already in use",
+                    "properties": ["name"]
+                }}
+            }, "0"]]
+        }));
+
+        let err = client
+            .mailbox_update("mb1", Some("Archive"), None, None, None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to update mailbox: invalidProperties: [name] — Name already in use (mb1)"
+        );
+        let args = &client.http.last_request()["methodCalls"][0][1];
+        assert_eq!(
+            args["update"],
+            serde_json::json!({"mb1": {"name": "Archive"}})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_email_state_and_cannot_calculate_changes() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [["Email/get", {"state": "s42", "list": [], "notFound": []}, "0"]]
+        }));
+        assert_eq!(client.email_state().await.unwrap(), "s42");
+        let args = &client.http.last_request()["methodCalls"][0][1];
+        assert_eq!(args["ids"], serde_json::json!([]));
+
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [["error", {"type": "cannotCalculateChanges"}, "0"]]
+        }));
+        let err = client.email_changes("s1", None).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<JmapError>(),
+            Some(JmapError::CannotCalculateChanges { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_email_parse_keeps_parsed_emails_untyped() {
+        let client = mock_client(json!({
+            "methodResponses": [["Email/parse", {
+                "accountId": "acc1",
+                "parsed": {
+                    "B1": {
+                        "id": null,
+                        "subject": "Saved",
+                        "header:X-Mailer:asText": "mutt"
+                    }
+                },
+                "notParsable": ["B2"]
+            }, "0"]],
+            "sessionState": "s"
+        }));
+
+        let parsed = client
+            .email_parse(
+                &["B1".to_string(), "B2".to_string()],
+                Some(vec![
+                    "subject".to_string(),
+                    "header:X-Mailer:asText".to_string(),
+                ]),
+                None,
+                None,
+                None,
+                Some(true),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(parsed["B1"]["header:X-Mailer:asText"], "mutt");
+        assert!(!parsed.contains_key("B2"));
+        let request = client.http.last_request();
+        assert_eq!(request["methodCalls"][0][1]["fetchAllBodyValues"], true);
+    }
+
+    #[tokio::test]
+    async fn test_thread_emails_chains_result_references() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [
+                ["Email/get", {"list": [{"id": "m2", "threadId": "t1"}], "notFound": []}, "0"],
+                ["Thread/get", {"list": [{"id": "t1", "emailIds": ["m1", "m2"]}]}, "1"],
+                ["Email/get", {"list": [
+                    {"id": "m2", "subject": "Re: Plan", "receivedAt": "2024-01-02T09:00:00Z"},
+                    {"id": "m1", "subject": "Plan", "receivedAt": "2024-01-01T09:00:00Z"}
+                ], "notFound": []}, "2"]
+            ]
+        }));
+
+        let (thread, emails) = client.thread_emails("m2").await.unwrap();
+        assert_eq!(thread.id, "t1");
+        assert_eq!(
+            emails.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            vec!["m1", "m2"]
+        );
+
+        let calls = &client.http.last_request()["methodCalls"];
+        assert_eq!(calls.as_array().unwrap().len(), 3);
+        assert_eq!(calls[1][1]["#ids"]["path"], "/list/*/threadId");
+        assert_eq!(calls[2][1]["#ids"]["resultOf"], "1");
+        assert_eq!(calls[2][1]["#ids"]["path"], "/list/*/emailIds");
+    }
+
+    #[tokio::test]
+    async fn test_set_errors_are_described() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [["Mailbox/set", {
+                "notCreated": {"new": {"type": "invalidProperties", "properties": ["name"],
+                    "description": "Mailbox name already exists"}}
+            }, "0"]]
+        }));
+        let err = client.mailbox_create("Inbox").await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to create mailbox: invalidProperties: [name] — Mailbox name already exists"
+        );
+
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [["Email/set", {
+                "destroyed": ["m1"],
+                "notDestroyed": {"m2": {"type": "notFound"}}
+            }, "0"]]
+        }));
+        let err = client
+            .email_delete(&["m1".to_string(), "m2".to_string()])
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Failed to delete email: notFound (m2)");
+    }
+
+    #[tokio::test]
+    async fn test_mailbox_update_moves_to_root() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [["Mailbox/set", {"updated": {"mb1": null}}, "0"]]
+        }));
+
+        client
+            .mailbox_update("mb1", None, Some(None), None, None)
+            .await
+            .unwrap();
+        let args = &client.http.last_request()["methodCalls"][0][1];
+        assert_eq!(
+            args["update"],
+            serde_json::json!({"mb1": {"parentId": null}})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_email_set_keyword_uses_patch() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [["Email/set", {"updated": {"m1": null}}, "0"]]
+        }));
+
+        client.email_set_keyword("m1", "$seen", true).await.unwrap();
+        let args = &client.http.last_request()["methodCalls"][0][1];
+        assert_eq!(
+            args["update"],
+            serde_json::json!({"m1": {"keywords/$seen": true}})
+        );
+
+        client
+            .email_set_keyword("m1", "$flagged", false)
+            .await
+            .unwrap();
+        let args = &client.http.last_request()["methodCalls"][0][1];
+        assert_eq!(
+            args["update"],
+            serde_json::json!({"m1": {"keywords/$flagged": null}})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_email_set_keyword_batched_sends_one_call() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [["Email/set", {
+                "updated": {"m1": null, "m3": null},
+                "notUpdated": {"m2": {"type": "notFound"}}
+            }, "0"]]
+        }));
+
+        let ids: Vec<String> = ["m1", "m2", "m3"].map(String::from).to_vec();
+        let result = client
+            .email_set_keyword_batched(&ids, "$seen", true)
+            .await
+            .unwrap();
+        assert_eq!(result.updated, vec!["m1", "m3"]);
+        assert_eq!(result.not_updated["m2"].type_, "notFound");
+
+        let requests = client.http.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let args = &requests[0]["methodCalls"][0][1];
+        assert_eq!(args["accountId"], "acc1");
+        assert_eq!(
+            args["update"],
+            serde_json::json!({
+                "m1": {"keywords/$seen": true},
+                "m2": {"keywords/$seen": true},
+                "m3": {"keywords/$seen": true}
+            })
+        );
+    }
+
+    #[test]
+    fn test_blob_download_url_encodes_values() {
+        let mut session = fastmail_session("u1234abcd");
+        session.download_url = Some(
+            "https://www.fastmailusercontent.com/jmap/download/{accountId}/{blobId}/{name}?type={type}"
+                .to_string(),
+        );
+        let client = JmapClient::from_session(
+            MockHttpClient {
+                response: Vec::new(),
+                requests: std::sync::Mutex::new(Vec::new()),
+            },
+            session,
+        )
+        .unwrap();
+
+        assert_eq!(
+            client
+                .blob_download_url("B1", "Q3 report (final).pdf", "application/pdf")
+                .unwrap(),
+            "https://www.fastmailusercontent.com/jmap/download/u1234abcd/B1/\
+             Q3%20report%20%28final%29.pdf?type=application%2Fpdf"
+        );
+        assert_eq!(uri_template_value("résumé.txt"), "r%C3%A9sum%C3%A9.txt");
+    }
+
+    #[test]
+    fn test_keyword_patch_escapes_pointer() {
+        assert_eq!(
+            keyword_patch("a/b~c", true),
+            serde_json::json!({"keywords/a~1b~0c": true})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_email_submission_create_holds_until_send_at() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [[
+                "EmailSubmission/set",
+                {"created": {"sub": {"id": "s1", "identityId": "i1", "emailId": "m1", "undoStatus": "pending"}}},
+                "0"
+            ]]
+        }));
+
+        let envelope = Envelope {
+            mail_from: crate::types::EmailAddress {
+                email: "me@example.com".to_string(),
+                name: None,
+            },
+            rcpt_to: vec![crate::types::EmailAddress {
+                email: "you@example.com".to_string(),
+                name: None,
+            }],
+        };
+        let send_at = DateTime::parse_from_rfc3339("2030-01-02T03:04:05Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let submission = client
+            .email_submission_create("i1", "m1", Some(envelope), Some(send_at), None)
+            .await
+            .unwrap();
+        assert_eq!(submission.id, "s1");
+
+        let args = &client.http.last_request()["methodCalls"][0][1];
+        assert_eq!(
+            args["create"]["sub"]["envelope"]["mailFrom"]["parameters"]["HOLDUNTIL"],
+            "2030-01-02T03:04:05Z"
+        );
+        assert!(args.get("onSuccessUpdateEmail").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_email_submission_create_files_the_email_on_success() {
+        let client = mock_client(serde_json::json!({
+            "methodResponses": [
+                ["EmailSubmission/set", {"created": {"sub": {"id": "s1", "identityId": "i1", "emailId": "m1", "undoStatus": "final"}}}, "0"],
+                ["Email/set", {"updated": {"m1": null}}, "0"]
+            ]
+        }));
+
+        let patch = serde_json::json!({ "mailboxIds": { "sent": true } });
+        client
+            .email_submission_create("i1", "m1", None, None, Some(patch.clone()))
+            .await
+            .unwrap();
+
+        let args = &client.http.last_request()["methodCalls"][0][1];
+        assert_eq!(
+            args["onSuccessUpdateEmail"],
+            serde_json::json!({ "#sub": patch })
+        );
+    }
+
+    #[test]
+    fn test_decode_body_part() {
+        let part = BodyPart {
+            part_id: "1".to_string(),
+            size: Some(5),
+            type_: "text/plain".to_string(),
+            charset: Some("iso-8859-1".to_string()),
+            transfer_encoding: Some("quoted-printable".to_string()),
+            ..Default::default()
+        };
+
+        // Server already removed the transfer encoding: only the charset is applied
+        assert_eq!(decode_body_part(b"caf\xe9=", &part), "café=");
+        // Still quoted-printable on the wire: decode it before the charset
+        assert_eq!(decode_body_part(b"caf=E9=3D", &part), "café=");
+    }
 }
@@ -85,6 +85,13 @@ pub enum JmapError {
     #[error("account read only")]
     AccountReadOnly,
 
+    #[error("rate limited: {description:?}")]
+    RateLimit { description: Option<String> },
+
+    // RFC 8620 §5.2 /changes error
+    #[error("cannot calculate changes: {description:?}")]
+    CannotCalculateChanges { description: Option<String> },
+
     // Catch-all for unknown error types
     #[error("JMAP error {type_}: {description:?}")]
     Unknown {
@@ -96,16 +103,25 @@ pub enum JmapError {
 impl JmapError {
     /// Parse a JMAP error response (the args field of an error invocation) into a typed variant.
     pub fn from_value(args: &serde_json::Value) -> Self {
-        let type_ = args
-            .get("type")
-            .and_then(|v| v.as_str())
-            .unwrap_or("unknown");
-        let description = args
-            .get("description")
-            .and_then(|v| v.as_str())
-            .map(String::from);
-
-        match type_ {
+        serde_json::from_value::<MethodError>(args.clone())
+            .unwrap_or_else(|_| MethodError {
+                type_: "unknown".to_string(),
+                description: None,
+            })
+            .into()
+    }
+
+    /// Whether the same request may succeed if retried later
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::ServerUnavailable | Self::RateLimit { .. })
+    }
+}
+
+impl From<MethodError> for JmapError {
+    fn from(err: MethodError) -> Self {
+        let MethodError { type_, description } = err;
+
+        match type_.as_str() {
             error_types::UNKNOWN_CAPABILITY => {
                 Self::UnknownCapability(description.unwrap_or_default())
             }
@@ -124,10 +140,9 @@ impl JmapError {
             }
             error_types::ACCOUNT_NOT_SUPPORTED_BY_METHOD => Self::AccountNotSupportedByMethod,
             error_types::ACCOUNT_READ_ONLY => Self::AccountReadOnly,
-            _ => Self::Unknown {
-                type_: type_.to_string(),
-                description,
-            },
+            error_types::RATE_LIMIT => Self::RateLimit { description },
+            error_types::CANNOT_CALCULATE_CHANGES => Self::CannotCalculateChanges { description },
+            _ => Self::Unknown { type_, description },
         }
     }
 }
@@ -150,4 +165,6 @@ pub mod error_types {
     pub const ACCOUNT_NOT_FOUND: &str = "accountNotFound";
     pub const ACCOUNT_NOT_SUPPORTED_BY_METHOD: &str = "accountNotSupportedByMethod";
     pub const ACCOUNT_READ_ONLY: &str = "accountReadOnly";
+    pub const RATE_LIMIT: &str = "rateLimit";
+    pub const CANNOT_CALCULATE_CHANGES: &str = "cannotCalculateChanges";
 }
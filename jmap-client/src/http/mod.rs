@@ -6,6 +6,28 @@ use async_trait::async_trait;
 pub struct HttpError {
     pub status: Option<u16>,
     pub message: String,
+    /// Wait requested by the server's Retry-After header, if any
+    pub retry_after: Option<std::time::Duration>,
+}
+
+impl HttpError {
+    /// Whether the request may succeed if tried again later (429, 502, 503, 504).
+    /// Clients that retry return this error once their retry budget is spent.
+    pub fn is_transient(&self) -> bool {
+        matches!(self.status, Some(429 | 502 | 503 | 504))
+    }
+
+    /// Whether the server turned the request away before acting on it: a 429,
+    /// or a 503 that says when to come back. A 502 or 504 may arrive after the
+    /// request took effect, so only these are safe to resend for a `*/set` or an
+    /// EmailSubmission.
+    pub fn is_rejected(&self) -> bool {
+        match self.status {
+            Some(429) => true,
+            Some(503) => self.retry_after.is_some(),
+            _ => false,
+        }
+    }
 }
 
 impl std::fmt::Display for HttpError {
@@ -20,6 +42,26 @@ impl std::fmt::Display for HttpError {
 
 impl std::error::Error for HttpError {}
 
+/// Mask every occurrence of the given secrets (tokens, passwords) in `text`.
+/// Empty secrets are ignored.
+pub fn redact(text: &str, secrets: &[&str]) -> String {
+    secrets
+        .iter()
+        .filter(|secret| !secret.is_empty())
+        .fold(text.to_string(), |text, secret| {
+            text.replace(secret, "[REDACTED]")
+        })
+}
+
+/// Rate-limit state reported by the server on its most recent response
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimit {
+    /// Requests left in the current window
+    pub remaining: u32,
+    /// When the window resets, if the server said
+    pub reset_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 /// Generic HTTP client trait - users can implement their own
 #[async_trait]
 pub trait HttpClient: Send + Sync {
@@ -37,9 +79,31 @@ pub trait HttpClient: Send + Sync {
         Err(HttpError {
             status: None,
             message: "post_binary not implemented".to_string(),
+            retry_after: None,
         })
     }
 
+    /// POST a file's contents to URL with custom Content-Type, return response bytes.
+    /// The default reads the whole file into memory; clients that can stream override it.
+    async fn post_file(
+        &self,
+        url: &str,
+        path: &std::path::Path,
+        content_type: &str,
+    ) -> Result<Vec<u8>, HttpError> {
+        let data = std::fs::read(path).map_err(|e| HttpError {
+            status: None,
+            message: format!("Cannot read {}: {}", path.display(), e),
+            retry_after: None,
+        })?;
+        self.post_binary(url, data, content_type).await
+    }
+
+    /// Rate limit from the last response, for clients that track it
+    fn rate_limit(&self) -> Option<RateLimit> {
+        None
+    }
+
     /// GET request for session (default implementation uses POST)
     async fn get(&self, url: &str, body: Vec<u8>) -> Result<Vec<u8>, HttpError> {
         self.post_json(url, body).await
@@ -50,4 +114,4 @@ pub trait HttpClient: Send + Sync {
 pub mod reqwest;
 
 #[cfg(feature = "reqwest")]
-pub use reqwest::ReqwestClient;
+pub use reqwest::{ReqwestClient, ReqwestClientBuilder};
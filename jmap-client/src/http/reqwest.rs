@@ -1,112 +1,328 @@
 // jmap-client/src/http/reqwest.rs
-use super::{HttpClient, HttpError};
+use super::{redact, HttpClient, HttpError, RateLimit};
+use crate::encode_base64;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Delay before the first retry when the server gives no Retry-After
+#[cfg(feature = "reqwest")]
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Upper bound on any single wait, including a server-provided Retry-After
+#[cfg(feature = "reqwest")]
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// User-Agent sent unless the builder sets another, so servers can tell us apart in logs
+#[cfg(feature = "reqwest")]
+pub const DEFAULT_USER_AGENT: &str = concat!("fastmail-cli/", env!("CARGO_PKG_VERSION"));
 
 #[cfg(feature = "reqwest")]
 pub struct ReqwestClient {
     inner: reqwest::Client,
-    bearer_token: Option<String>,
+    timeout: Option<Duration>,
+    auth: Option<Auth>,
+    max_retries: u32,
+    verbose: bool,
+    rate_limit: Mutex<Option<RateLimit>>,
 }
 
+/// How requests authenticate
 #[cfg(feature = "reqwest")]
-impl ReqwestClient {
-    pub fn new() -> Self {
+enum Auth {
+    Bearer(String),
+    /// An app password, which Fastmail accepts for JMAP as well as DAV
+    Basic {
+        username: String,
+        password: String,
+    },
+}
+
+/// Timeouts, connection pooling and User-Agent for a `ReqwestClient`.
+/// Anything not set keeps reqwest's default.
+#[cfg(feature = "reqwest")]
+#[derive(Debug, Clone)]
+pub struct ReqwestClientBuilder {
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    pool_idle_timeout: Option<Duration>,
+    user_agent: String,
+}
+
+#[cfg(feature = "reqwest")]
+impl Default for ReqwestClientBuilder {
+    fn default() -> Self {
         Self {
-            inner: reqwest::Client::new(),
-            bearer_token: None,
+            timeout: None,
+            connect_timeout: None,
+            pool_idle_timeout: None,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
         }
     }
+}
 
-    pub fn with_token(mut self, token: String) -> Self {
-        self.bearer_token = Some(token);
+#[cfg(feature = "reqwest")]
+impl ReqwestClientBuilder {
+    /// Limit on each request, from connecting until the body is read.
+    /// Event streams opened with `open_event_stream` stay open on purpose and are exempt.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
         self
     }
-}
 
-#[cfg(feature = "reqwest")]
-impl Default for ReqwestClient {
-    fn default() -> Self {
-        Self::new()
+    /// Limit on establishing the connection
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// How long an idle pooled connection is kept for reuse
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    pub fn build(self) -> Result<ReqwestClient, HttpError> {
+        let mut builder = reqwest::Client::builder().user_agent(self.user_agent);
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        let inner = builder.build().map_err(|e| HttpError {
+            status: None,
+            message: e.to_string(),
+            retry_after: None,
+        })?;
+
+        Ok(ReqwestClient {
+            inner,
+            timeout: self.timeout,
+            auth: None,
+            max_retries: 0,
+            verbose: false,
+            rate_limit: Mutex::new(None),
+        })
     }
 }
 
 #[cfg(feature = "reqwest")]
-#[async_trait]
-impl HttpClient for ReqwestClient {
-    async fn post_json(&self, url: &str, body: Vec<u8>) -> Result<Vec<u8>, HttpError> {
-        let mut req = self.inner.post(url);
+impl ReqwestClient {
+    /// A client with reqwest's defaults and the default User-Agent.
+    /// Like `reqwest::Client::new`, panics if the TLS backend cannot be initialized.
+    pub fn new() -> Self {
+        Self::builder()
+            .build()
+            .expect("Failed to initialize the HTTP client")
+    }
 
-        if let Some(token) = &self.bearer_token {
-            req = req.bearer_auth(token);
-        }
+    pub fn builder() -> ReqwestClientBuilder {
+        ReqwestClientBuilder::default()
+    }
 
-        let resp = req
-            .header("content-type", "application/json")
-            .body(body)
-            .send()
-            .await
-            .map_err(|e| HttpError {
-                status: None,
-                message: e.to_string(),
-            })?;
+    pub fn with_token(mut self, token: String) -> Self {
+        self.auth = Some(Auth::Bearer(token));
+        self
+    }
 
-        let status = resp.status();
-        let is_success = status.is_success();
-        let status_code = status.as_u16();
+    /// Authenticate with HTTP Basic instead of a bearer token
+    pub fn with_basic_auth(mut self, username: String, password: String) -> Self {
+        self.auth = Some(Auth::Basic { username, password });
+        self
+    }
 
-        let bytes = resp
-            .bytes()
-            .await
-            .map_err(|e| HttpError {
-                status: Some(status_code),
-                message: e.to_string(),
-            })?
-            .to_vec();
+    /// Retry up to `retries` times with exponential backoff, waiting as long as
+    /// a Retry-After header asks. GETs retry on 429, 502, 503 and 504; POSTs,
+    /// which may change state, only when the server rejected them outright
+    /// (see `HttpError::is_rejected`). Retries are off by default.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
 
-        if !is_success {
-            return Err(HttpError {
-                status: Some(status_code),
-                message: String::from_utf8_lossy(&bytes).to_string(),
-            });
+    /// Log JSON request and response bodies to stderr, with credentials masked
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    fn log(&self, line: &str, body: &[u8]) {
+        if self.verbose {
+            eprintln!("{}", self.log_entry(line, body));
         }
+    }
 
-        Ok(bytes)
+    /// A log line followed by the body, pretty-printed when it is JSON
+    fn log_entry(&self, line: &str, body: &[u8]) -> String {
+        let body = match serde_json::from_slice::<serde_json::Value>(body) {
+            Ok(json) => serde_json::to_string_pretty(&json).unwrap_or_default(),
+            Err(_) => String::from_utf8_lossy(body).into_owned(),
+        };
+        self.redact(&format!("{}\n{}", line, body))
     }
 
-    async fn get(&self, url: &str, _body: Vec<u8>) -> Result<Vec<u8>, HttpError> {
-        let mut req = self.inner.get(url);
+    /// Mask the credentials, e.g. in a response body that echoes the request
+    fn redact(&self, text: &str) -> String {
+        match &self.auth {
+            Some(Auth::Bearer(token)) => redact(text, &[token]),
+            Some(Auth::Basic { username, password }) => {
+                let encoded = encode_base64(format!("{}:{}", username, password).as_bytes());
+                redact(text, &[password, &encoded])
+            }
+            None => text.to_string(),
+        }
+    }
+
+    fn authorize(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.auth {
+            Some(Auth::Bearer(token)) => req.bearer_auth(token),
+            Some(Auth::Basic { username, password }) => req.basic_auth(username, Some(password)),
+            None => req,
+        }
+    }
+
+    /// Send a request built by `build`, retrying failures for which `retryable` holds
+    async fn execute<F>(
+        &self,
+        build: F,
+        retryable: fn(&HttpError) -> bool,
+    ) -> Result<Vec<u8>, HttpError>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let mut attempt = 0;
+        loop {
+            let mut req = self.authorize(build());
+            if let Some(timeout) = self.timeout {
+                req = req.timeout(timeout);
+            }
 
-        if let Some(token) = &self.bearer_token {
-            req = req.bearer_auth(token);
+            match self.send(req).await {
+                Err(err) if retryable(&err) && attempt < self.max_retries => {
+                    attempt += 1;
+                    tokio::time::sleep(retry_delay(&err, attempt)).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Open a long-lived `text/event-stream` response (RFC 8620 §7.3).
+    /// `last_event_id` lets the server resume after the last event seen before a reconnect.
+    pub async fn open_event_stream(
+        &self,
+        url: &str,
+        last_event_id: Option<&str>,
+    ) -> Result<reqwest::Response, HttpError> {
+        let mut req = self.authorize(self.inner.get(url).header("accept", "text/event-stream"));
+        if let Some(id) = last_event_id {
+            req = req.header("last-event-id", id);
         }
 
         let resp = req.send().await.map_err(|e| HttpError {
             status: None,
             message: e.to_string(),
+            retry_after: None,
         })?;
 
+        let status = resp.status();
+        if !status.is_success() {
+            let status_code = status.as_u16();
+            let message = self.redact(&resp.text().await.unwrap_or_default());
+            return Err(HttpError {
+                status: Some(status_code),
+                message,
+                retry_after: None,
+            });
+        }
+
+        Ok(resp)
+    }
+
+    /// Send one request, turning a non-success status into an `HttpError`
+    async fn send(&self, req: reqwest::RequestBuilder) -> Result<Vec<u8>, HttpError> {
+        let resp = req.send().await.map_err(request_error)?;
+
+        if let Some(rate_limit) = parse_rate_limit(resp.headers(), Utc::now()) {
+            *self.rate_limit.lock().unwrap() = Some(rate_limit);
+        }
+
         let status = resp.status();
         let is_success = status.is_success();
         let status_code = status.as_u16();
+        let retry_after = resp
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_retry_after);
 
         let bytes = resp
             .bytes()
             .await
             .map_err(|e| HttpError {
-                status: Some(status_code),
-                message: e.to_string(),
+                // A body cut off by the timeout is as retryable as no response at all
+                status: (!e.is_timeout()).then_some(status_code),
+                ..request_error(e)
             })?
             .to_vec();
 
         if !is_success {
             return Err(HttpError {
                 status: Some(status_code),
-                message: String::from_utf8_lossy(&bytes).to_string(),
+                message: self.redact(&String::from_utf8_lossy(&bytes)),
+                retry_after,
             });
         }
 
         Ok(bytes)
     }
+}
+
+#[cfg(feature = "reqwest")]
+impl Default for ReqwestClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "reqwest")]
+#[async_trait]
+impl HttpClient for ReqwestClient {
+    async fn post_json(&self, url: &str, body: Vec<u8>) -> Result<Vec<u8>, HttpError> {
+        self.log(&format!("> POST {}", url), &body);
+        let result = self
+            .execute(
+                || {
+                    self.inner
+                        .post(url)
+                        .header("content-type", "application/json")
+                        .body(body.clone())
+                },
+                HttpError::is_rejected,
+            )
+            .await;
+        match &result {
+            Ok(response) => self.log(&format!("< {}", url), response),
+            Err(e) => {
+                let status = e.status.map_or("failed".to_string(), |s| s.to_string());
+                self.log(&format!("< {} {}", status, url), e.message.as_bytes())
+            }
+        }
+        result
+    }
+
+    async fn get(&self, url: &str, _body: Vec<u8>) -> Result<Vec<u8>, HttpError> {
+        self.execute(|| self.inner.get(url), HttpError::is_transient)
+            .await
+    }
 
     async fn post_binary(
         &self,
@@ -114,42 +330,422 @@ impl HttpClient for ReqwestClient {
         data: Vec<u8>,
         content_type: &str,
     ) -> Result<Vec<u8>, HttpError> {
-        let mut req = self.inner.post(url);
+        self.execute(
+            || {
+                self.inner
+                    .post(url)
+                    .header("content-type", content_type)
+                    .body(data.clone())
+            },
+            HttpError::is_rejected,
+        )
+        .await
+    }
+
+    /// Streams the file from disk instead of buffering it.
+    /// Not retried, since a streamed body can only be sent once.
+    async fn post_file(
+        &self,
+        url: &str,
+        path: &std::path::Path,
+        content_type: &str,
+    ) -> Result<Vec<u8>, HttpError> {
+        let io_error = |e: std::io::Error| HttpError {
+            status: None,
+            message: format!("Cannot read {}: {}", path.display(), e),
+            retry_after: None,
+        };
+        let file = tokio::fs::File::open(path).await.map_err(io_error)?;
+        let len = file.metadata().await.map_err(io_error)?.len();
 
-        if let Some(token) = &self.bearer_token {
-            req = req.bearer_auth(token);
+        let mut req = self.authorize(
+            self.inner
+                .post(url)
+                .header("content-type", content_type)
+                .header("content-length", len)
+                .body(reqwest::Body::from(file)),
+        );
+        if let Some(timeout) = self.timeout {
+            req = req.timeout(timeout);
         }
+        self.send(req).await
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit.lock().unwrap().clone()
+    }
+}
+
+/// An error from reqwest before a complete response was read.
+/// Timeouts get no status, so they count as transient.
+#[cfg(feature = "reqwest")]
+fn request_error(e: reqwest::Error) -> HttpError {
+    let message = if e.is_timeout() {
+        format!("Request timed out: {}", e)
+    } else {
+        e.to_string()
+    };
+    HttpError {
+        status: None,
+        message,
+        retry_after: None,
+    }
+}
+
+/// How long to wait before retry number `attempt` (1-based)
+#[cfg(feature = "reqwest")]
+fn retry_delay(err: &HttpError, attempt: u32) -> Duration {
+    let backoff = BASE_BACKOFF * 2u32.saturating_pow(attempt - 1);
+    err.retry_after.unwrap_or(backoff).min(MAX_BACKOFF)
+}
+
+/// Parse a Retry-After value: delay seconds or an HTTP date (RFC 9110 §10.2.3)
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = at.with_timezone(&Utc) - Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Read Fastmail's X-RateLimit-Remaining / X-RateLimit-Reset headers.
+/// The reset is either a Unix timestamp or a number of seconds from now.
+fn parse_rate_limit(headers: &HeaderMap, now: DateTime<Utc>) -> Option<RateLimit> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    let remaining = header("x-ratelimit-remaining")?.trim().parse().ok()?;
+    let reset_at = header("x-ratelimit-reset")
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .and_then(|reset| {
+            // Anything before 2001 can only be a relative delay
+            if reset > 1_000_000_000 {
+                DateTime::from_timestamp(reset, 0)
+            } else {
+                Some(now + chrono::Duration::seconds(reset))
+            }
+        });
+
+    Some(RateLimit {
+        remaining,
+        reset_at,
+    })
+}
 
-        let resp = req
-            .header("content-type", content_type)
-            .body(data)
-            .send()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_post_binary_sends_content_type_and_auth() {
+        use wiremock::matchers::{body_bytes, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/upload/u1/"))
+            .and(header("content-type", "image/png"))
+            .and(header("authorization", "Bearer fmu1-token"))
+            .and(body_bytes(vec![0x89, b'P', b'N', b'G']))
+            .respond_with(ResponseTemplate::new(201).set_body_string(r#"{"blobId":"B1"}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = ReqwestClient::new().with_token("fmu1-token".to_string());
+        let url = format!("{}/upload/u1/", server.uri());
+        let body = client
+            .post_binary(&url, vec![0x89, b'P', b'N', b'G'], "image/png")
             .await
-            .map_err(|e| HttpError {
-                status: None,
-                message: e.to_string(),
-            })?;
+            .unwrap();
+        assert_eq!(body, br#"{"blobId":"B1"}"#);
 
-        let status = resp.status();
-        let is_success = status.is_success();
-        let status_code = status.as_u16();
+        let err = client
+            .post_binary(&url, b"text".to_vec(), "text/plain")
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, Some(404));
+    }
 
-        let bytes = resp
-            .bytes()
+    #[tokio::test]
+    async fn test_builder_sets_user_agent() {
+        use wiremock::matchers::header;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(header("user-agent", DEFAULT_USER_AGENT))
+            .respond_with(ResponseTemplate::new(200).set_body_string("default"))
+            .mount(&server)
+            .await;
+        Mock::given(header("user-agent", "my-agent/1.0"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("custom"))
+            .mount(&server)
+            .await;
+
+        let default = ReqwestClient::new();
+        assert_eq!(
+            default.get(&server.uri(), Vec::new()).await.unwrap(),
+            b"default"
+        );
+
+        let custom = ReqwestClient::builder()
+            .user_agent("my-agent/1.0")
+            .timeout(Duration::from_secs(30))
+            .connect_timeout(Duration::from_secs(5))
+            .pool_idle_timeout(Duration::from_secs(90))
+            .build()
+            .unwrap();
+        assert_eq!(
+            custom.get(&server.uri(), Vec::new()).await.unwrap(),
+            b"custom"
+        );
+    }
+
+    #[test]
+    fn test_log_entry_masks_the_token() {
+        let client = ReqwestClient::new()
+            .with_token("fmu1-secret".to_string())
+            .with_verbose(true);
+        let entry = client.log_entry(
+            "> POST https://api.example.com/jmap/api/",
+            br#"{"methodCalls":[["Core/echo",{"echo":"fmu1-secret"},"0"]]}"#,
+        );
+        assert!(entry.starts_with("> POST https://api.example.com/jmap/api/\n{"));
+        assert!(entry.contains("\"Core/echo\""));
+        assert!(entry.contains("[REDACTED]"));
+        assert!(!entry.contains("fmu1-secret"));
+    }
+
+    #[tokio::test]
+    async fn test_error_body_echoing_the_token_is_masked() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::any())
+            .respond_with(
+                ResponseTemplate::new(401)
+                    .set_body_string("Invalid header: Authorization: Bearer fmu1-secret"),
+            )
+            .mount(&server)
+            .await;
+
+        let client = ReqwestClient::new().with_token("fmu1-secret".to_string());
+        let err = client
+            .post_json(&server.uri(), b"{}".to_vec())
             .await
-            .map_err(|e| HttpError {
-                status: Some(status_code),
-                message: e.to_string(),
-            })?
-            .to_vec();
+            .unwrap_err();
+        assert_eq!(err.status, Some(401));
+        assert_eq!(
+            err.to_string(),
+            "HTTP error 401: Invalid header: Authorization: Bearer [REDACTED]"
+        );
+    }
 
-        if !is_success {
-            return Err(HttpError {
-                status: Some(status_code),
-                message: String::from_utf8_lossy(&bytes).to_string(),
-            });
-        }
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact(
+                "token fmu1-a, password pw, fmu1-a again",
+                &["fmu1-a", "pw", ""]
+            ),
+            "token [REDACTED], password [REDACTED], [REDACTED] again"
+        );
+    }
 
-        Ok(bytes)
+    #[tokio::test]
+    async fn test_timeout_is_a_transient_error() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::any())
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&server)
+            .await;
+
+        let client = ReqwestClient::builder()
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let err = client.get(&server.uri(), Vec::new()).await.unwrap_err();
+        assert_eq!(err.status, None);
+        assert!(err.message.starts_with("Request timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_post_is_only_retried_when_rejected() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        // A 502 may come after the server acted on the request, e.g. sent the email
+        Mock::given(method("POST"))
+            .and(path("/bad-gateway"))
+            .respond_with(ResponseTemplate::new(502))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/rate-limited"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+            .expect(3)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/bad-gateway"))
+            .respond_with(ResponseTemplate::new(502).insert_header("retry-after", "0"))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let client = ReqwestClient::new().with_retries(2);
+        let url = |p: &str| format!("{}{}", server.uri(), p);
+        let err = client
+            .post_json(&url("/bad-gateway"), b"{}".to_vec())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, Some(502));
+        let err = client
+            .post_json(&url("/rate-limited"), b"{}".to_vec())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, Some(429));
+        client
+            .get(&url("/bad-gateway"), Vec::new())
+            .await
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_is_rejected() {
+        let err = |status, retry_after| HttpError {
+            status: Some(status),
+            message: String::new(),
+            retry_after,
+        };
+        assert!(err(429, None).is_rejected());
+        assert!(err(503, Some(Duration::from_secs(1))).is_rejected());
+        assert!(!err(503, None).is_rejected());
+        assert!(!err(502, None).is_rejected());
+        assert!(!err(504, Some(Duration::from_secs(1))).is_rejected());
+    }
+
+    #[tokio::test]
+    async fn test_get_is_a_real_get_with_auth() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/jmap/session"))
+            .and(header("authorization", "Bearer fmu1-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("{}"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/download/missing"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("not found"))
+            .mount(&server)
+            .await;
+
+        let client = ReqwestClient::new().with_token("fmu1-token".to_string());
+        let session = client
+            .get(&format!("{}/jmap/session", server.uri()), Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(session, b"{}");
+
+        let err = client
+            .get(&format!("{}/download/missing", server.uri()), Vec::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, Some(404));
+        assert_eq!(err.message, "not found");
+    }
+
+    #[tokio::test]
+    async fn test_basic_auth_with_app_password() {
+        use wiremock::matchers::{header, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header(
+                "authorization",
+                "Basic bWVAZXhhbXBsZS5jb206YXBwLXBhc3N3b3Jk",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_string("{}"))
+            .mount(&server)
+            .await;
+
+        let client = ReqwestClient::new()
+            .with_basic_auth("me@example.com".to_string(), "app-password".to_string());
+        let body = client
+            .post_json(&server.uri(), b"{}".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(body, b"{}");
+        assert_eq!(
+            client.redact("app-password bWVAZXhhbXBsZS5jb206YXBwLXBhc3N3b3Jk"),
+            "[REDACTED] [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let err = HttpError {
+            status: Some(503),
+            message: String::new(),
+            retry_after: None,
+        };
+        assert_eq!(retry_delay(&err, 1), Duration::from_millis(500));
+        assert_eq!(retry_delay(&err, 3), Duration::from_secs(2));
+
+        let limited = HttpError {
+            status: Some(429),
+            message: String::new(),
+            retry_after: Some(Duration::from_secs(7)),
+        };
+        assert_eq!(retry_delay(&limited, 1), Duration::from_secs(7));
+
+        let too_long = HttpError {
+            retry_after: Some(Duration::from_secs(3600)),
+            ..limited
+        };
+        assert_eq!(retry_delay(&too_long, 1), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_parse_rate_limit() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_rate_limit(&headers, now), None);
+
+        headers.insert("X-RateLimit-Remaining", "42".parse().unwrap());
+        headers.insert("X-RateLimit-Reset", "1700000060".parse().unwrap());
+        assert_eq!(
+            parse_rate_limit(&headers, now),
+            Some(RateLimit {
+                remaining: 42,
+                reset_at: DateTime::from_timestamp(1_700_000_060, 0),
+            })
+        );
+
+        headers.insert("X-RateLimit-Reset", "30".parse().unwrap());
+        assert_eq!(
+            parse_rate_limit(&headers, now).unwrap().reset_at,
+            DateTime::from_timestamp(1_700_000_030, 0)
+        );
     }
 }
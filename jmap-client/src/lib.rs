@@ -3,12 +3,15 @@ pub mod blob;
 pub mod client;
 pub mod error;
 pub mod http;
+pub mod mime;
+pub mod push;
 pub mod types;
 
 pub use blob::{data_source_from_bytes, data_source_from_text, decode_base64, encode_base64};
-pub use client::JmapClient;
+pub use client::{check_set_errors, Invocation, JmapClient};
 pub use error::{BlobError, JmapError};
-pub use http::{HttpClient, HttpError};
+pub use http::{redact, HttpClient, HttpError, RateLimit};
+pub use push::PushEvent;
 pub use types::{
     AccountData,
     AddedItem,
@@ -26,6 +29,7 @@ pub use types::{
     CoreCapability,
     DataSourceObject,
     DeliveryStatus,
+    DestroyResult,
     // Email
     Email,
     EmailAddress,
@@ -35,6 +39,7 @@ pub use types::{
     EmailFilterCondition,
     EmailHeader,
     EmailImport,
+    EmailQueryPage,
     // EmailSubmission
     EmailSubmission,
     Entity,
@@ -73,6 +78,7 @@ pub use types::{
     // Thread
     Thread,
     UndoStatus,
+    UpdateResult,
     // VacationResponse
     VacationResponse,
 };
@@ -83,4 +89,4 @@ pub use error::MethodError;
 
 // Re-export reqwest client when feature is enabled
 #[cfg(feature = "reqwest")]
-pub use http::ReqwestClient;
+pub use http::{ReqwestClient, ReqwestClientBuilder};
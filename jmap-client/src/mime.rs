@@ -0,0 +1,114 @@
+// jmap-client/src/mime.rs
+//! Decoding helpers for MIME body parts (RFC 2045, RFC 2046)
+
+use crate::blob::decode_base64;
+
+/// Undo a Content-Transfer-Encoding. Unknown encodings are returned unchanged.
+pub fn decode_transfer_encoding(data: &[u8], encoding: &str) -> Vec<u8> {
+    match encoding.trim().to_ascii_lowercase().as_str() {
+        "quoted-printable" => decode_quoted_printable(data),
+        "base64" => {
+            let compact: String = String::from_utf8_lossy(data)
+                .chars()
+                .filter(|c| !c.is_ascii_whitespace())
+                .collect();
+            decode_base64(&compact).unwrap_or_else(|_| data.to_vec())
+        }
+        _ => data.to_vec(),
+    }
+}
+
+/// Decode quoted-printable data (RFC 2045 §6.7)
+/// Soft line breaks are removed; malformed escapes are kept literally.
+pub fn decode_quoted_printable(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] != b'=' {
+            out.push(data[i]);
+            i += 1;
+            continue;
+        }
+
+        match (data.get(i + 1), data.get(i + 2)) {
+            (Some(b'\r'), Some(b'\n')) => i += 3,
+            (Some(b'\n'), _) => i += 2,
+            (Some(&hi), Some(&lo)) => match (hex_value(hi), hex_value(lo)) {
+                (Some(hi), Some(lo)) => {
+                    out.push(hi << 4 | lo);
+                    i += 3;
+                }
+                _ => {
+                    out.push(b'=');
+                    i += 1;
+                }
+            },
+            _ => {
+                out.push(b'=');
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn hex_value(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+}
+
+/// Convert text in the given charset to a UTF-8 string.
+/// UTF-8, US-ASCII, ISO-8859-1 and Windows-1252 are supported; anything else is
+/// treated as UTF-8 with invalid sequences replaced.
+pub fn decode_charset(data: &[u8], charset: Option<&str>) -> String {
+    let charset = charset.unwrap_or("utf-8").trim().to_ascii_lowercase();
+    match charset.as_str() {
+        "iso-8859-1" | "iso8859-1" | "latin1" | "latin-1" => {
+            data.iter().map(|&b| b as char).collect()
+        }
+        "windows-1252" | "cp1252" => data.iter().map(|&b| windows_1252_char(b)).collect(),
+        _ => String::from_utf8_lossy(data).into_owned(),
+    }
+}
+
+/// Map a Windows-1252 byte to its Unicode character
+fn windows_1252_char(b: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž',
+        '\u{8f}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}',
+        'ž', 'Ÿ',
+    ];
+    match b {
+        0x80..=0x9f => HIGH[(b - 0x80) as usize],
+        _ => b as char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_quoted_printable() {
+        let decoded = decode_quoted_printable(b"Caf=C3=A9 au lait=\r\n is =3D good=");
+        assert_eq!(
+            String::from_utf8(decoded).unwrap(),
+            "Café au lait is = good="
+        );
+    }
+
+    #[test]
+    fn test_decode_transfer_encoding_base64() {
+        let decoded = decode_transfer_encoding(b"SGVsbG8s\r\nIHdvcmxk", "Base64");
+        assert_eq!(decoded, b"Hello, world");
+    }
+
+    #[test]
+    fn test_decode_charset() {
+        assert_eq!(decode_charset(b"caf\xe9", Some("ISO-8859-1")), "café");
+        assert_eq!(
+            decode_charset(b"\x93quoted\x94 \x80", Some("windows-1252")),
+            "“quoted” €"
+        );
+        assert_eq!(decode_charset("héllo".as_bytes(), None), "héllo");
+    }
+}
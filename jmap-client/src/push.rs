@@ -0,0 +1,321 @@
+// jmap-client/src/push.rs
+//! Push notifications over EventSource (RFC 8620 §7.3)
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A StateChange pushed by the server (RFC 8620 §7.1)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PushEvent {
+    /// EventSource id of the event, if the server sent one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// New state strings, by account ID then type name (e.g. "Email")
+    pub changed: HashMap<String, HashMap<String, String>>,
+}
+
+impl PushEvent {
+    /// New state of `type_name` in `account_id`, if it changed
+    pub fn state(&self, account_id: &str, type_name: &str) -> Option<&str> {
+        self.changed
+            .get(account_id)
+            .and_then(|types| types.get(type_name))
+            .map(String::as_str)
+    }
+}
+
+/// One dispatched `text/event-stream` event
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerSentEvent {
+    pub id: Option<String>,
+    pub event: String,
+    pub data: String,
+}
+
+/// Incremental parser for the `text/event-stream` format.
+/// Feed it chunks as they arrive; events split across chunks are reassembled.
+#[derive(Debug, Default)]
+pub struct EventStreamParser {
+    buffer: Vec<u8>,
+    last_id: Option<String>,
+    event: Option<String>,
+    data: Vec<String>,
+    retry: Option<u64>,
+}
+
+impl EventStreamParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Id of the last event seen, to send as Last-Event-ID when reconnecting
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_id.as_deref()
+    }
+
+    /// Reconnection delay in milliseconds requested by the server
+    pub fn retry(&self) -> Option<u64> {
+        self.retry
+    }
+
+    /// Forget any partially received event, e.g. after the connection dropped
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.event = None;
+        self.data.clear();
+    }
+
+    /// Consume a chunk of the stream and return the events it completes
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<ServerSentEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let mut line: Vec<u8> = self.buffer.drain(..=end).collect();
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+
+            if let Some(event) = self.process_line(&String::from_utf8_lossy(&line)) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<ServerSentEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            // Comment, used as a keep-alive
+            return None;
+        }
+
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            "id" if !value.contains('\0') => self.last_id = Some(value.to_string()),
+            "retry" => {
+                if let Ok(ms) = value.parse() {
+                    self.retry = Some(ms);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<ServerSentEvent> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+
+        Some(ServerSentEvent {
+            id: self.last_id.clone(),
+            event: event.unwrap_or_else(|| "message".to_string()),
+            data: std::mem::take(&mut self.data).join("\n"),
+        })
+    }
+}
+
+/// Turn a `state` event into a PushEvent; pings and other events yield None
+pub fn parse_push_event(event: &ServerSentEvent) -> Option<PushEvent> {
+    if event.event != "state" {
+        return None;
+    }
+
+    #[derive(Deserialize)]
+    struct StateChange {
+        #[serde(rename = "@type")]
+        type_: String,
+        changed: HashMap<String, HashMap<String, String>>,
+    }
+
+    let change: StateChange = serde_json::from_str(&event.data).ok()?;
+    (change.type_ == "StateChange").then(|| PushEvent {
+        id: event.id.clone(),
+        changed: change.changed,
+    })
+}
+
+#[cfg(feature = "reqwest")]
+mod stream {
+    use super::*;
+    use crate::client::JmapClient;
+    use crate::http::ReqwestClient;
+    use anyhow::{anyhow, Result};
+    use futures::Stream;
+    use std::collections::VecDeque;
+    use std::time::Duration;
+
+    /// Wait before reconnecting unless the server asked for something else
+    const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+    /// Seconds between server pings, so dead connections are noticed
+    const PING_INTERVAL_SECS: u32 = 60;
+
+    struct Watch<'a> {
+        http: &'a ReqwestClient,
+        url: String,
+        parser: EventStreamParser,
+        response: Option<reqwest::Response>,
+        pending: VecDeque<PushEvent>,
+        done: bool,
+    }
+
+    impl Watch<'_> {
+        async fn wait_before_reconnect(&mut self) {
+            self.response = None;
+            self.parser.reset();
+            let delay = self
+                .parser
+                .retry()
+                .map(Duration::from_millis)
+                .unwrap_or(DEFAULT_RECONNECT_DELAY);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    impl JmapClient<ReqwestClient> {
+        /// Stream state changes for the given types (e.g. `["Email", "Mailbox"]`,
+        /// or none for all types) from the session's eventSourceUrl.
+        /// Dropped connections are reopened with Last-Event-ID; the stream only
+        /// ends after yielding an error the server will not recover from.
+        pub fn event_source(
+            &self,
+            types: &[&str],
+        ) -> Result<impl Stream<Item = Result<PushEvent>> + '_> {
+            let template = self
+                .session()
+                .event_source_url
+                .as_deref()
+                .ok_or_else(|| anyhow!("Server does not provide an eventSourceUrl"))?;
+
+            let types = if types.is_empty() {
+                "*".to_string()
+            } else {
+                types.join(",")
+            };
+            let url = template
+                .replace("{types}", &types)
+                .replace("{closeafter}", "no")
+                .replace("{ping}", &PING_INTERVAL_SECS.to_string());
+
+            let watch = Watch {
+                http: self.http(),
+                url,
+                parser: EventStreamParser::new(),
+                response: None,
+                pending: VecDeque::new(),
+                done: false,
+            };
+
+            Ok(futures::stream::unfold(watch, |mut watch| async move {
+                loop {
+                    if let Some(event) = watch.pending.pop_front() {
+                        return Some((Ok(event), watch));
+                    }
+                    if watch.done {
+                        return None;
+                    }
+
+                    let Some(mut resp) = watch.response.take() else {
+                        let last_id = watch.parser.last_event_id().map(str::to_string);
+                        match watch
+                            .http
+                            .open_event_stream(&watch.url, last_id.as_deref())
+                            .await
+                        {
+                            Ok(resp) => watch.response = Some(resp),
+                            Err(e) if e.status.is_none() || e.is_transient() => {
+                                watch.wait_before_reconnect().await
+                            }
+                            Err(e) => {
+                                watch.done = true;
+                                return Some((Err(anyhow::Error::new(e)), watch));
+                            }
+                        }
+                        continue;
+                    };
+
+                    match resp.chunk().await {
+                        Ok(Some(bytes)) => {
+                            let events = watch.parser.feed(&bytes);
+                            watch
+                                .pending
+                                .extend(events.iter().filter_map(parse_push_event));
+                            watch.response = Some(resp);
+                        }
+                        // The server closed the stream or the connection broke
+                        Ok(None) | Err(_) => watch.wait_before_reconnect().await,
+                    }
+                }
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parser_reassembles_split_events() {
+        let mut parser = EventStreamParser::new();
+        assert!(parser
+            .feed(b": keep-alive\r\nevent: state\r\nid: 4")
+            .is_empty());
+
+        let events =
+            parser.feed(b"2\r\ndata: {\"a\":\r\ndata: 1}\r\n\r\nevent: ping\ndata: {}\n\n");
+        assert_eq!(
+            events,
+            vec![
+                ServerSentEvent {
+                    id: Some("42".to_string()),
+                    event: "state".to_string(),
+                    data: "{\"a\":\n1}".to_string(),
+                },
+                ServerSentEvent {
+                    id: Some("42".to_string()),
+                    event: "ping".to_string(),
+                    data: "{}".to_string(),
+                },
+            ]
+        );
+        assert_eq!(parser.last_event_id(), Some("42"));
+    }
+
+    #[test]
+    fn test_parser_reads_retry() {
+        let mut parser = EventStreamParser::new();
+        assert!(parser.feed(b"retry: 10000\n\n").is_empty());
+        assert_eq!(parser.retry(), Some(10000));
+    }
+
+    #[test]
+    fn test_parse_push_event() {
+        let event = ServerSentEvent {
+            id: Some("7".to_string()),
+            event: "state".to_string(),
+            data: r#"{"@type":"StateChange","changed":{"u1":{"Email":"s2","Mailbox":"m9"}}}"#
+                .to_string(),
+        };
+        let push = parse_push_event(&event).unwrap();
+        assert_eq!(push.id.as_deref(), Some("7"));
+        assert_eq!(push.state("u1", "Email"), Some("s2"));
+        assert_eq!(push.state("u1", "Thread"), None);
+
+        let ping = ServerSentEvent {
+            event: "ping".to_string(),
+            data: r#"{"interval":60}"#.to_string(),
+            ..event
+        };
+        assert_eq!(parse_push_event(&ping), None);
+    }
+}
@@ -90,7 +90,7 @@ pub struct EmailAddress {
 }
 
 /// Email body part structure (RFC 8621 §4.1.4)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EmailBodyPart {
     #[serde(rename = "partId")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -442,7 +442,7 @@ pub struct MailboxFilterCondition {
     pub is_subscribed: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct BodyPart {
     #[serde(rename = "partId")]
     pub part_id: String,
@@ -453,10 +453,18 @@ pub struct BodyPart {
     pub size: Option<u64>,
     #[serde(rename = "type")]
     pub type_: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub charset: Option<String>,
+    /// Content-Transfer-Encoding header, when requested via bodyProperties
+    #[serde(rename = "header:Content-Transfer-Encoding:asText")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transfer_encoding: Option<String>,
 }
 
 /// JMAP Session response (RFC 8620 Section 2)
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Session {
     /// Server capabilities (required)
     pub capabilities: HashMap<String, serde_json::Value>,
@@ -542,6 +550,30 @@ pub enum Filter<T> {
     Condition(T),
 }
 
+impl<T> Filter<T> {
+    /// Combine conditions so that all of them must match
+    pub fn and(conditions: Vec<Filter<T>>) -> Self {
+        Self::Compound {
+            operator: FilterOperator::And,
+            conditions,
+        }
+    }
+
+    /// Combine conditions so that at least one of them must match
+    pub fn or(conditions: Vec<Filter<T>>) -> Self {
+        Self::Compound {
+            operator: FilterOperator::Or,
+            conditions,
+        }
+    }
+}
+
+impl<T> From<T> for Filter<T> {
+    fn from(condition: T) -> Self {
+        Self::Condition(condition)
+    }
+}
+
 /// Sort comparator (RFC 8620 §5.5)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Comparator {
@@ -603,6 +635,22 @@ pub struct SetResponse<T> {
     pub not_destroyed: HashMap<String, SetError>,
 }
 
+/// Outcome of destroying objects over one or more /set calls
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DestroyResult {
+    pub destroyed: Vec<String>,
+    #[serde(rename = "notDestroyed", skip_serializing_if = "HashMap::is_empty")]
+    pub not_destroyed: HashMap<String, SetError>,
+}
+
+/// Outcome of updating objects over one or more /set calls
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateResult {
+    pub updated: Vec<String>,
+    #[serde(rename = "notUpdated", skip_serializing_if = "HashMap::is_empty")]
+    pub not_updated: HashMap<String, SetError>,
+}
+
 /// Error in /set method (RFC 8620 §5.3)
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SetError {
@@ -614,6 +662,42 @@ pub struct SetError {
     pub properties: Option<Vec<String>>,
 }
 
+impl SetError {
+    /// The errors in one section ("notCreated", "notUpdated" or "notDestroyed")
+    /// of a /set response, keyed by id. Malformed entries keep their raw JSON as the type.
+    pub fn from_response(args: &serde_json::Value, section: &str) -> Vec<(String, SetError)> {
+        args.get(section)
+            .and_then(|v| v.as_object())
+            .into_iter()
+            .flatten()
+            .map(|(id, error)| {
+                let error = serde_json::from_value(error.clone()).unwrap_or_else(|_| SetError {
+                    type_: error.to_string(),
+                    description: None,
+                    properties: None,
+                });
+                (id.clone(), error)
+            })
+            .collect()
+    }
+}
+
+/// Renders as e.g. "invalidProperties: [name] — Mailbox name already exists"
+impl std::fmt::Display for SetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.type_)?;
+        let properties = self.properties.as_deref().unwrap_or_default();
+        if !properties.is_empty() {
+            write!(f, ": [{}]", properties.join(", "))?;
+        }
+        match self.description {
+            Some(ref description) if !properties.is_empty() => write!(f, " — {}", description),
+            Some(ref description) => write!(f, ": {}", description),
+            None => Ok(()),
+        }
+    }
+}
+
 // Blob types (RFC 9404)
 
 /// JMAP Blob capability (urn:ietf:params:jmap:blob)
@@ -951,8 +1035,22 @@ pub struct ChangesResponse {
     pub destroyed: Vec<String>,
 }
 
+/// One page of Email/query results (RFC 8620 §5.5)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailQueryPage {
+    pub ids: Vec<String>,
+    #[serde(default)]
+    pub position: usize,
+    /// Total number of matching emails (requested via calculateTotal)
+    #[serde(default)]
+    pub total: usize,
+    /// State of the query results, the baseline for Email/queryChanges
+    #[serde(rename = "queryState", default)]
+    pub query_state: Option<String>,
+}
+
 /// QueryChanges response (RFC 8620 §5.6)
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryChangesResponse {
     #[serde(rename = "accountId")]
     pub account_id: String,
@@ -962,10 +1060,13 @@ pub struct QueryChangesResponse {
     pub new_query_state: String,
     pub added: Vec<AddedItem>,
     pub removed: Vec<String>,
+    /// Total number of results now in the query (requested via calculateTotal)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
 }
 
 /// Added item in QueryChanges (RFC 8620 §5.6)
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddedItem {
     pub id: String,
     pub index: usize,